@@ -0,0 +1,126 @@
+//! Fixed-size encoding of addresses, for use as keys of ordered indexes
+//!
+//! The binary encoding of an address has a variable length depending on its
+//! kind. Storage structures with fixed-size keys (e.g. B-trees) instead need
+//! every address encoded into the same number of bytes:
+//!
+//! ```text
+//! FIRST_BYTE (discrimination and kind) || ADDRESS_PAYLOAD || ZERO_PADDING
+//! ```
+//!
+//! The padding is always zero, so the encoding is canonical, and keys compare
+//! byte-wise: first by discrimination, then by kind, then by payload.
+
+use crate::{is_valid_data, Address, Error};
+
+/// Size of the largest binary address, the group address
+pub const MAX_ADDRESS_SIZE: usize = crate::ADDR_SIZE_GROUP;
+
+/// An address in the fixed-size key encoding
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AddressKey([u8; MAX_ADDRESS_SIZE]);
+
+impl AddressKey {
+    pub fn from_address(address: &Address) -> Self {
+        let mut key = [0u8; MAX_ADDRESS_SIZE];
+        let bytes = address.to_bytes();
+        key[..bytes.len()].copy_from_slice(&bytes);
+        AddressKey(key)
+    }
+
+    /// Read a key, checking the address it contains is structurally
+    /// valid and the padding is canonical.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let key: [u8; MAX_ADDRESS_SIZE] = bytes.try_into().map_err(|_| Error::InvalidAddress)?;
+        let key = AddressKey(key);
+        key.address_bytes()?;
+        Ok(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; MAX_ADDRESS_SIZE] {
+        &self.0
+    }
+
+    /// The binary encoding of the address, without the padding
+    pub fn address_bytes(&self) -> Result<&[u8], Error> {
+        let size = Self::address_size(self.0[0])?;
+        let (address, padding) = self.0.split_at(size);
+        is_valid_data(address)?;
+        if padding.iter().any(|b| *b != 0) {
+            return Err(Error::InvalidInternalEncoding);
+        }
+        Ok(address)
+    }
+
+    pub fn to_address(&self) -> Result<Address, Error> {
+        Address::from_bytes(self.address_bytes()?)
+    }
+
+    fn address_size(first_byte: u8) -> Result<usize, Error> {
+        match crate::get_kind_value(first_byte) {
+            crate::ADDR_KIND_SINGLE => Ok(crate::ADDR_SIZE_SINGLE),
+            crate::ADDR_KIND_GROUP => Ok(crate::ADDR_SIZE_GROUP),
+            crate::ADDR_KIND_ACCOUNT => Ok(crate::ADDR_SIZE_ACCOUNT),
+            crate::ADDR_KIND_MULTISIG => Ok(crate::ADDR_SIZE_MULTISIG),
+            crate::ADDR_KIND_SCRIPT => Ok(crate::ADDR_SIZE_SCRIPT),
+            _ => Err(Error::InvalidKind),
+        }
+    }
+}
+
+impl From<&Address> for AddressKey {
+    fn from(address: &Address) -> Self {
+        AddressKey::from_address(address)
+    }
+}
+
+impl AsRef<[u8]> for AddressKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for AddressKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("AddressKey")
+            .field(&AsRef::<[u8]>::as_ref(self))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Discrimination, Kind};
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    #[proptest]
+    fn key_round_trip(address: Address) {
+        let key = AddressKey::from_address(&address);
+
+        prop_assert_eq!(key.to_address().unwrap(), address);
+        prop_assert_eq!(AddressKey::from_bytes(key.as_bytes()).unwrap(), key);
+    }
+
+    #[proptest]
+    fn keys_order_by_first_byte_then_payload(a: Address, b: Address) {
+        let (ka, kb) = (AddressKey::from(&a), AddressKey::from(&b));
+        let (ba, bb) = (a.to_bytes(), b.to_bytes());
+        let expected = ba[0].cmp(&bb[0]).then_with(|| ba[1..].cmp(&bb[1..]));
+
+        prop_assert_eq!(ka.cmp(&kb), expected);
+    }
+
+    #[test]
+    fn non_canonical_padding_is_rejected() {
+        let address = Address(Discrimination::Production, Kind::Script([9; 32]));
+        let mut bytes = *AddressKey::from(&address).as_bytes();
+        bytes[MAX_ADDRESS_SIZE - 1] = 1;
+        assert!(matches!(
+            AddressKey::from_bytes(&bytes),
+            Err(Error::InvalidInternalEncoding)
+        ));
+        assert!(AddressKey::from_bytes(&bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Human readable prefixes of the bech32 representation of addresses
+//!
+//! The prefix is not part of the binary encoding of an address: the same
+//! address can be displayed with different prefixes, e.g. by testnets or
+//! sidechains wanting their addresses to be told apart by users. The
+//! `HrpRegistry` associates a prefix to each discrimination so that
+//! displaying and parsing addresses follows the conventions of a network.
+
+use crate::{Address, AddressReadable, Discrimination, Error};
+
+/// Prefix used by default for production addresses
+pub const DEFAULT_PRODUCTION_HRP: &str = "ca";
+
+/// Prefix used by default for test addresses
+pub const DEFAULT_TEST_HRP: &str = "ta";
+
+// limit on the length of the human readable part set by BIP-0173
+const HRP_MAX_LENGTH: usize = 83;
+
+/// A valid human readable part of a bech32 string
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hrp(String);
+
+impl Hrp {
+    /// Check that the string is a valid bech32 human readable part:
+    /// between 1 and 83 printable ASCII characters, in lower case.
+    pub fn new(hrp: &str) -> Result<Self, Error> {
+        if hrp.is_empty() || hrp.len() > HRP_MAX_LENGTH {
+            return Err(Error::InvalidPrefix);
+        }
+        let valid = hrp
+            .bytes()
+            .all(|c| (33..=126).contains(&c) && !c.is_ascii_uppercase());
+        if !valid {
+            return Err(Error::InvalidPrefix);
+        }
+        Ok(Hrp(hrp.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for Hrp {
+    type Error = Error;
+    fn try_from(hrp: &str) -> Result<Self, Self::Error> {
+        Hrp::new(hrp)
+    }
+}
+
+impl std::str::FromStr for Hrp {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hrp::new(s)
+    }
+}
+
+impl std::fmt::Display for Hrp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Hrp {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The prefixes used to display the addresses of a network,
+/// one for each discrimination.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HrpRegistry {
+    production: Hrp,
+    test: Hrp,
+}
+
+impl Default for HrpRegistry {
+    fn default() -> Self {
+        HrpRegistry {
+            production: Hrp(DEFAULT_PRODUCTION_HRP.to_string()),
+            test: Hrp(DEFAULT_TEST_HRP.to_string()),
+        }
+    }
+}
+
+impl HrpRegistry {
+    /// Create a registry with the given prefixes. The prefixes
+    /// have to be different, otherwise the discrimination of a
+    /// parsed address could not be checked against its prefix.
+    pub fn new(production: Hrp, test: Hrp) -> Result<Self, Error> {
+        if production == test {
+            return Err(Error::InvalidPrefix);
+        }
+        Ok(HrpRegistry { production, test })
+    }
+
+    /// The prefix of the addresses with the given discrimination
+    pub fn hrp(&self, discrimination: Discrimination) -> &Hrp {
+        match discrimination {
+            Discrimination::Production => &self.production,
+            Discrimination::Test => &self.test,
+        }
+    }
+
+    /// The discrimination of the addresses using the given prefix,
+    /// if the prefix is registered
+    pub fn discrimination(&self, hrp: &str) -> Option<Discrimination> {
+        if hrp == self.production.as_str() {
+            Some(Discrimination::Production)
+        } else if hrp == self.test.as_str() {
+            Some(Discrimination::Test)
+        } else {
+            None
+        }
+    }
+
+    /// Display the address with the prefix registered for its discrimination
+    pub fn to_readable(&self, address: &Address) -> AddressReadable {
+        AddressReadable::from_address_with_hrp(self.hrp(address.discrimination()), address)
+    }
+
+    /// Parse an address, checking that its prefix is registered and
+    /// matches the discrimination of the address.
+    pub fn parse(&self, s: &str) -> Result<AddressReadable, Error> {
+        let readable = AddressReadable::from_str_anyprefix(s)?;
+        let discrimination = self
+            .discrimination(&readable.get_prefix())
+            .ok_or(Error::InvalidPrefix)?;
+        if readable.to_address().discrimination() != discrimination {
+            return Err(Error::MismatchPrefix);
+        }
+        Ok(readable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+
+    fn address(discrimination: Discrimination) -> Address {
+        Address(discrimination, Kind::Script([7; 32]))
+    }
+
+    #[test]
+    fn invalid_hrps_are_rejected() {
+        assert!(Hrp::new("").is_err());
+        assert!(Hrp::new("Ca").is_err());
+        assert!(Hrp::new("c a").is_err());
+        assert!(Hrp::new(&"a".repeat(HRP_MAX_LENGTH + 1)).is_err());
+        assert!(Hrp::new("sidechain").is_ok());
+    }
+
+    #[test]
+    fn registry_prefixes_follow_discrimination() {
+        let registry =
+            HrpRegistry::new(Hrp::new("side").unwrap(), Hrp::new("tside").unwrap()).unwrap();
+        for discrimination in [Discrimination::Production, Discrimination::Test] {
+            let addr = address(discrimination);
+            let readable = registry.to_readable(&addr);
+            assert_eq!(readable.get_prefix(), registry.hrp(discrimination).as_str());
+            let parsed = registry.parse(readable.as_string()).unwrap();
+            assert_eq!(parsed.to_address(), addr);
+        }
+    }
+
+    #[test]
+    fn registry_rejects_unknown_or_mismatched_prefix() {
+        let registry = HrpRegistry::default();
+        let addr = address(Discrimination::Test);
+
+        let other = AddressReadable::from_address("other", &addr);
+        assert!(matches!(
+            registry.parse(other.as_string()),
+            Err(Error::InvalidPrefix)
+        ));
+
+        let mismatched = AddressReadable::from_address(DEFAULT_PRODUCTION_HRP, &addr);
+        assert!(matches!(
+            registry.parse(mismatched.as_string()),
+            Err(Error::MismatchPrefix)
+        ));
+    }
+
+    #[test]
+    fn same_binary_encoding_with_any_prefix() {
+        let addr = address(Discrimination::Production);
+        let a = AddressReadable::from_address_with_hrp(&Hrp::new("aaa").unwrap(), &addr);
+        let b = AddressReadable::from_address_with_hrp(&Hrp::new("bbb").unwrap(), &addr);
+        assert_ne!(a, b);
+        assert_eq!(a.to_address(), b.to_address());
+    }
+}
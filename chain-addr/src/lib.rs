@@ -28,7 +28,10 @@
 //! Script identifier:
 //!     DISCRIMINATION_BIT || SCRIPT_KIND_TYPE (7 bits) || SCRIPT_IDENTIFIER
 //!
-//! Address human format is bech32 encoded
+//! Test addresses can additionally be bound to a test network, see `NetworkAddress`.
+//!
+//! Address human format is bech32 encoded. The human readable prefix is not
+//! part of the binary format and can be chosen per network, see `HrpRegistry`.
 
 use bech32::{self, FromBase32, ToBase32};
 use chain_core::{
@@ -38,11 +41,23 @@ use chain_core::{
 use chain_crypto::{Ed25519, PublicKey, PublicKeyError};
 use std::string::ToString;
 
+mod address_key;
+mod hrp;
+mod network;
+#[cfg(feature = "serde")]
+mod serde_impl;
 #[cfg(any(test, feature = "property-test-api"))]
 mod testing;
+mod validate;
+
 #[cfg(any(test, feature = "property-test-api"))]
 use chain_crypto::testing::public_key_strategy;
 
+pub use address_key::{AddressKey, MAX_ADDRESS_SIZE};
+pub use hrp::{Hrp, HrpRegistry, DEFAULT_PRODUCTION_HRP, DEFAULT_TEST_HRP};
+pub use network::{Network, NetworkAddress, NetworkMagic};
+pub use validate::{validate_batch, validate_batch_with_policy, IndexedError, KindPolicy};
+
 // Allow to differentiate between address in
 // production and testing setting, so that
 // one type of address is not used in another setting.
@@ -157,6 +172,9 @@ pub enum Error {
     InvalidInternalEncoding,
     InvalidPrefix,
     MismatchPrefix,
+    MismatchDiscrimination,
+    MismatchNetworkMagic,
+    UnsupportedKind,
 }
 
 impl std::fmt::Display for Error {
@@ -168,6 +186,9 @@ impl std::fmt::Display for Error {
             Error::InvalidInternalEncoding => write!(f, "invalid internal encoding"),
             Error::InvalidPrefix => write!(f, "invalid prefix"),
             Error::MismatchPrefix => write!(f, "mismatch prefix"),
+            Error::MismatchDiscrimination => write!(f, "mismatch discrimination"),
+            Error::MismatchNetworkMagic => write!(f, "mismatch network magic"),
+            Error::UnsupportedKind => write!(f, "unsupported kind"),
         }
     }
 }
@@ -279,6 +300,80 @@ impl Address {
     }
 }
 
+impl Address {
+    /// Check the structure of a binary address and give access to its
+    /// fields without copying them.
+    ///
+    /// Only the kind and the length of the address are verified: the keys
+    /// are not decoded until the address is converted with `AddressRef::to_address`.
+    pub fn from_slice(bytes: &[u8]) -> Result<AddressRef<'_>, Error> {
+        let (discrimination, kind_type) = is_valid_data(bytes)?;
+        Ok(AddressRef {
+            bytes,
+            discrimination,
+            kind_type,
+        })
+    }
+}
+
+/// A borrowed view of the binary encoding of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AddressRef<'a> {
+    bytes: &'a [u8],
+    discrimination: Discrimination,
+    kind_type: KindType,
+}
+
+impl<'a> AddressRef<'a> {
+    pub fn discrimination(&self) -> Discrimination {
+        self.discrimination
+    }
+
+    pub fn kind_type(&self) -> KindType {
+        self.kind_type
+    }
+
+    /// The binary encoding of the whole address
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The spending or account public key, in the same cases
+    /// as `Address::public_key`
+    pub fn public_key_bytes(&self) -> Option<&'a [u8]> {
+        match self.kind_type {
+            KindType::Single | KindType::Account => Some(&self.bytes[1..]),
+            KindType::Group => Some(&self.bytes[1..33]),
+            KindType::Multisig | KindType::Script => None,
+        }
+    }
+
+    /// The group public key of a group address
+    pub fn group_key_bytes(&self) -> Option<&'a [u8]> {
+        match self.kind_type {
+            KindType::Group => Some(&self.bytes[33..]),
+            _ => None,
+        }
+    }
+
+    /// The identifier of a multisig account or a script
+    pub fn identifier(&self) -> Option<&'a [u8; 32]> {
+        match self.kind_type {
+            KindType::Multisig | KindType::Script => Some(
+                self.bytes[1..33]
+                    .try_into()
+                    .expect("the size of the address has been checked"),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Decode the address into its owned representation
+    pub fn to_address(&self) -> Result<Address, Error> {
+        Address::from_bytes(self.bytes)
+    }
+}
+
 fn get_kind_value(first_byte: u8) -> u8 {
     first_byte & 0b0111_1111
 }
@@ -394,6 +489,12 @@ impl AddressReadable {
         AddressReadable(r)
     }
 
+    /// Create a new AddressReadable from an encoded address, with
+    /// a prefix that is known to be valid
+    pub fn from_address_with_hrp(hrp: &Hrp, addr: &Address) -> Self {
+        Self::from_address(hrp.as_str(), addr)
+    }
+
     /// Convert a valid AddressReadable to an decoded address
     pub fn to_address(&self) -> Address {
         // the data has been verified ahead of time, so all unwrap are safe
@@ -416,6 +517,22 @@ impl std::str::FromStr for AddressReadable {
     }
 }
 
+/// Display the address in bech32, with the default prefix
+/// of its discrimination (see `HrpRegistry::default`)
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(&HrpRegistry::default().to_readable(self), f)
+    }
+}
+
+/// Parse a bech32 address with any prefix
+impl std::str::FromStr for Address {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AddressReadable::from_str_anyprefix(s).map(|readable| readable.to_address())
+    }
+}
+
 impl Serialize for Address {
     fn serialized_size(&self) -> usize {
         Codec::u8_size()
@@ -587,6 +704,69 @@ mod test {
         prop_assert_eq!(address, decoded);
     }
 
+    #[proptest]
+    fn from_slice_matches_from_bytes(address: Address) {
+        let bytes = address.to_bytes();
+        let view = Address::from_slice(&bytes).unwrap();
+
+        prop_assert_eq!(view.discrimination(), address.discrimination());
+        prop_assert_eq!(view.kind_type(), address.to_kind_type());
+        prop_assert_eq!(
+            view.public_key_bytes(),
+            address.public_key().map(|pk| pk.as_ref())
+        );
+        match address.kind() {
+            Kind::Group(_, group) => prop_assert_eq!(view.group_key_bytes(), Some(group.as_ref())),
+            Kind::Multisig(id) | Kind::Script(id) => prop_assert_eq!(view.identifier(), Some(id)),
+            _ => prop_assert_eq!(view.identifier(), None),
+        }
+        prop_assert_eq!(view.to_address().unwrap(), address);
+    }
+
+    #[proptest]
+    fn display_from_str(address: Address) {
+        let s = address.to_string();
+        let prefix = HrpRegistry::default()
+            .hrp(address.discrimination())
+            .to_string();
+
+        prop_assert!(s.starts_with(&prefix));
+        prop_assert_eq!(s.parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn display_from_str_all_kinds() {
+        let key: PublicKey<Ed25519> = PublicKey::from_binary(&[7; 32]).unwrap();
+        let kinds = [
+            Kind::Single(key.clone()),
+            Kind::Group(key.clone(), key.clone()),
+            Kind::Account(key),
+            Kind::Multisig([8; 32]),
+            Kind::Script([9; 32]),
+        ];
+        for kind in kinds {
+            for discrimination in [Discrimination::Production, Discrimination::Test] {
+                let address = Address(discrimination, kind.clone());
+                let parsed: Address = address.to_string().parse().unwrap();
+                assert_eq!(parsed, address);
+            }
+        }
+        assert!("ca1qqqqqqq".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn from_slice_rejects_invalid_data() {
+        assert!(matches!(Address::from_slice(&[]), Err(Error::EmptyAddress)));
+        assert!(matches!(
+            Address::from_slice(&[ADDR_KIND_SENTINEL]),
+            Err(Error::InvalidKind)
+        ));
+        assert!(matches!(
+            Address::from_slice(&[ADDR_KIND_SINGLE, 0]),
+            Err(Error::InvalidAddress)
+        ));
+    }
+
     #[test]
     fn unit_tests() {
         let fake_spendingkey: PublicKey<Ed25519> = PublicKey::from_binary(&[
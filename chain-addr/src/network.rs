@@ -0,0 +1,229 @@
+//! Network magic carried in test addresses
+//!
+//! The discrimination bit only separates production addresses from test
+//! addresses: the addresses of two testnets are interchangeable. To keep
+//! testnets apart, a test address can carry the magic number of its network
+//! after the usual encoding:
+//!
+//! ```text
+//! ADDRESS || NETWORK_MAGIC (4 bytes, big endian)
+//! ```
+//!
+//! The length of such an encoding is not a valid address length, so these
+//! addresses are rejected by `Address::from_bytes` and cannot be mistaken
+//! for plain addresses.
+
+use crate::{is_valid_data, Address, Discrimination, Error};
+
+/// Magic number identifying a test network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkMagic(u32);
+
+impl NetworkMagic {
+    pub const fn new(magic: u32) -> Self {
+        NetworkMagic(magic)
+    }
+
+    pub const fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for NetworkMagic {
+    fn from(magic: u32) -> Self {
+        NetworkMagic(magic)
+    }
+}
+
+impl std::fmt::Display for NetworkMagic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// An address optionally bound to a test network by its magic
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkAddress {
+    address: Address,
+    magic: Option<NetworkMagic>,
+}
+
+impl NetworkAddress {
+    /// Bind the address to the test network with the given magic.
+    ///
+    /// Only test addresses can carry a network magic.
+    pub fn new(address: Address, magic: Option<NetworkMagic>) -> Result<Self, Error> {
+        if magic.is_some() && address.discrimination() != Discrimination::Test {
+            return Err(Error::MismatchDiscrimination);
+        }
+        Ok(NetworkAddress { address, magic })
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn magic(&self) -> Option<NetworkMagic> {
+        self.magic
+    }
+
+    pub fn into_address(self) -> Address {
+        self.address
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if is_valid_data(bytes).is_ok() {
+            return Address::from_bytes(bytes).map(|address| NetworkAddress {
+                address,
+                magic: None,
+            });
+        }
+        if bytes.len() < 4 {
+            return Err(Error::InvalidAddress);
+        }
+        let (address, magic) = bytes.split_at(bytes.len() - 4);
+        let address = Address::from_bytes(address)?;
+        let magic = NetworkMagic(u32::from_be_bytes(magic.try_into().unwrap()));
+        Self::new(address, Some(magic))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.address.to_bytes();
+        if let Some(magic) = self.magic {
+            bytes.extend_from_slice(&magic.0.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl From<Address> for NetworkAddress {
+    fn from(address: Address) -> Self {
+        NetworkAddress {
+            address,
+            magic: None,
+        }
+    }
+}
+
+/// The network addresses are expected to belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Network {
+    discrimination: Discrimination,
+    magic: Option<NetworkMagic>,
+}
+
+impl Network {
+    pub fn production() -> Self {
+        Network {
+            discrimination: Discrimination::Production,
+            magic: None,
+        }
+    }
+
+    /// A test network, identified by the given magic if any
+    pub fn test(magic: Option<NetworkMagic>) -> Self {
+        Network {
+            discrimination: Discrimination::Test,
+            magic,
+        }
+    }
+
+    pub fn discrimination(&self) -> Discrimination {
+        self.discrimination
+    }
+
+    pub fn magic(&self) -> Option<NetworkMagic> {
+        self.magic
+    }
+
+    /// Check that the address belongs to this network.
+    ///
+    /// Both the discrimination and the magic have to match: a testnet with
+    /// a magic does not accept addresses without one, and the other way around.
+    pub fn validate(&self, address: &NetworkAddress) -> Result<(), Error> {
+        if address.address.discrimination() != self.discrimination {
+            return Err(Error::MismatchDiscrimination);
+        }
+        if address.magic != self.magic {
+            return Err(Error::MismatchNetworkMagic);
+        }
+        Ok(())
+    }
+
+    /// Decode an address and check that it belongs to this network.
+    pub fn parse(&self, bytes: &[u8]) -> Result<Address, Error> {
+        let address = NetworkAddress::from_bytes(bytes)?;
+        self.validate(&address)?;
+        Ok(address.into_address())
+    }
+
+    /// Bind the address to this network, failing if the
+    /// discrimination of the address does not match.
+    pub fn bind(&self, address: Address) -> Result<NetworkAddress, Error> {
+        let address = NetworkAddress::new(address, self.magic)?;
+        self.validate(&address)?;
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+    use proptest::prelude::*;
+    use test_strategy::proptest;
+
+    #[proptest]
+    fn bound_address_round_trip(address: Address, magic: Option<u32>) {
+        let network = match address.discrimination() {
+            Discrimination::Production => Network::production(),
+            Discrimination::Test => Network::test(magic.map(NetworkMagic::new)),
+        };
+        let bound = network.bind(address.clone()).unwrap();
+        let bytes = bound.to_bytes();
+
+        prop_assert_eq!(&NetworkAddress::from_bytes(&bytes).unwrap(), &bound);
+        prop_assert_eq!(network.parse(&bytes).unwrap(), address);
+    }
+
+    #[test]
+    fn testnets_do_not_accept_each_other() {
+        let address = Address(Discrimination::Test, Kind::Script([1; 32]));
+        let net_a = Network::test(Some(NetworkMagic::new(1)));
+        let net_b = Network::test(Some(NetworkMagic::new(2)));
+        let plain = Network::test(None);
+
+        let bytes = net_a.bind(address.clone()).unwrap().to_bytes();
+        assert!(net_a.parse(&bytes).is_ok());
+        assert!(matches!(
+            net_b.parse(&bytes),
+            Err(Error::MismatchNetworkMagic)
+        ));
+        assert!(matches!(
+            plain.parse(&bytes),
+            Err(Error::MismatchNetworkMagic)
+        ));
+        assert!(Address::from_bytes(&bytes).is_err());
+
+        assert!(matches!(
+            net_a.parse(&address.to_bytes()),
+            Err(Error::MismatchNetworkMagic)
+        ));
+        assert!(matches!(
+            Network::production().parse(&address.to_bytes()),
+            Err(Error::MismatchDiscrimination)
+        ));
+    }
+
+    #[test]
+    fn production_addresses_carry_no_magic() {
+        let address = Address(Discrimination::Production, Kind::Script([1; 32]));
+        assert!(matches!(
+            NetworkAddress::new(address.clone(), Some(NetworkMagic::new(1))),
+            Err(Error::MismatchDiscrimination)
+        ));
+        let mut bytes = address.to_bytes();
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        assert!(NetworkAddress::from_bytes(&bytes).is_err());
+    }
+}
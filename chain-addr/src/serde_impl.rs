@@ -0,0 +1,156 @@
+//! serde support for addresses
+//!
+//! In human readable formats (e.g. JSON or YAML) addresses are represented
+//! by their bech32 string, and by their binary encoding otherwise.
+//!
+//! An `Address` does not carry the prefix of its bech32 representation,
+//! so it is serialized with the default prefix for its discrimination
+//! (see `HrpRegistry::default`) and deserialized from a string with any prefix.
+//! `AddressReadable` on the other hand is always serialized as a string,
+//! in order to preserve its prefix.
+
+use crate::{Address, AddressReadable, HrpRegistry};
+use serde::{
+    de::{Deserializer, Error as _, SeqAccess, Visitor},
+    ser::Serializer,
+    Deserialize, Serialize,
+};
+use std::fmt;
+
+impl Serialize for Address {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            HrpRegistry::default()
+                .to_readable(self)
+                .serialize(serializer)
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            AddressReadable::deserialize(deserializer).map(|readable| readable.to_address())
+        } else {
+            deserializer.deserialize_bytes(AddressBytesVisitor)
+        }
+    }
+}
+
+struct AddressBytesVisitor;
+
+impl<'de> Visitor<'de> for AddressBytesVisitor {
+    type Value = Address;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the binary encoding of an address")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Address::from_bytes(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(crate::ADDR_SIZE_GROUP);
+        while let Some(byte) = seq.next_element()? {
+            if bytes.len() == crate::ADDR_SIZE_GROUP {
+                return Err(A::Error::invalid_length(bytes.len() + 1, &self));
+            }
+            bytes.push(byte);
+        }
+        Address::from_bytes(&bytes).map_err(A::Error::custom)
+    }
+}
+
+impl Serialize for AddressReadable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for AddressReadable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(AddressReadableVisitor)
+    }
+}
+
+struct AddressReadableVisitor;
+
+impl<'de> Visitor<'de> for AddressReadableVisitor {
+    type Value = AddressReadable;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a bech32 encoded address")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        AddressReadable::from_str_anyprefix(v).map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Discrimination, Kind, DEFAULT_TEST_HRP};
+    use serde_test::{assert_de_tokens_error, assert_tokens, Configure, Token};
+
+    fn address() -> Address {
+        Address(Discrimination::Test, Kind::Multisig([3; 32]))
+    }
+
+    const ADDRESS_BECH32: &str = "ta1scpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqvpsxv7zgxz";
+
+    const ADDRESS_BYTES: [u8; 33] = {
+        let mut bytes = [3u8; 33];
+        bytes[0] = 0x86;
+        bytes
+    };
+
+    #[test]
+    fn address_human_readable_is_bech32() {
+        let addr = address();
+        assert_tokens(&addr.clone().readable(), &[Token::Str(ADDRESS_BECH32)]);
+        let readable = AddressReadable::from_address(DEFAULT_TEST_HRP, &addr);
+        assert_tokens(&readable, &[Token::Str(ADDRESS_BECH32)]);
+    }
+
+    #[test]
+    fn address_compact_is_binary() {
+        assert_tokens(&address().compact(), &[Token::Bytes(&ADDRESS_BYTES)]);
+    }
+
+    #[test]
+    fn invalid_address_is_rejected() {
+        assert_de_tokens_error::<AddressReadable>(
+            &[Token::Str("not an address")],
+            "invalid internal encoding",
+        );
+        assert_de_tokens_error::<serde_test::Compact<Address>>(
+            &[Token::Bytes(&[0xff])],
+            "invalid kind",
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Validation of batches of addresses
+//!
+//! Loading a list of addresses (e.g. from a genesis file or when indexing
+//! blocks) requires checking every address for structure, kind and
+//! discrimination, and reporting all the invalid ones at once rather than
+//! stopping on the first error.
+
+use crate::{is_valid_data, Address, Discrimination, Error, KindType};
+
+/// The kinds of addresses accepted by a validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KindPolicy {
+    allowed: u8,
+}
+
+impl Default for KindPolicy {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl KindPolicy {
+    /// Accept every kind of address
+    pub fn all() -> Self {
+        KindPolicy { allowed: u8::MAX }
+    }
+
+    /// Accept no kind of address, to be used with `allow`
+    pub fn none() -> Self {
+        KindPolicy { allowed: 0 }
+    }
+
+    pub fn allow(mut self, kind: KindType) -> Self {
+        self.allowed |= Self::mask(kind);
+        self
+    }
+
+    pub fn deny(mut self, kind: KindType) -> Self {
+        self.allowed &= !Self::mask(kind);
+        self
+    }
+
+    pub fn is_allowed(&self, kind: KindType) -> bool {
+        self.allowed & Self::mask(kind) != 0
+    }
+
+    fn mask(kind: KindType) -> u8 {
+        1 << kind.to_value()
+    }
+}
+
+/// The error found in the address at `index` in the batch
+#[derive(Debug)]
+pub struct IndexedError {
+    pub index: usize,
+    pub error: Error,
+}
+
+impl std::fmt::Display for IndexedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "address #{}: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for IndexedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Decode and validate all the binary addresses of the batch, accepting
+/// any kind of address with the expected discrimination.
+///
+/// See `validate_batch_with_policy`.
+pub fn validate_batch<I>(
+    addresses: I,
+    expected_discrimination: Discrimination,
+) -> Result<Vec<Address>, Vec<IndexedError>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    validate_batch_with_policy(addresses, expected_discrimination, KindPolicy::all())
+}
+
+/// Decode and validate all the binary addresses of the batch.
+///
+/// Returns the decoded addresses if they are all valid, or else the
+/// errors of all the invalid addresses, along with their index in the batch.
+pub fn validate_batch_with_policy<I>(
+    addresses: I,
+    expected_discrimination: Discrimination,
+    policy: KindPolicy,
+) -> Result<Vec<Address>, Vec<IndexedError>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut valid = Vec::new();
+    let mut errors = Vec::new();
+    for (index, bytes) in addresses.into_iter().enumerate() {
+        match validate_one(bytes.as_ref(), expected_discrimination, policy) {
+            Ok(address) => valid.push(address),
+            Err(error) => errors.push(IndexedError { index, error }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(valid)
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_one(
+    bytes: &[u8],
+    expected_discrimination: Discrimination,
+    policy: KindPolicy,
+) -> Result<Address, Error> {
+    let (discrimination, kind) = is_valid_data(bytes)?;
+    if discrimination != expected_discrimination {
+        return Err(Error::MismatchDiscrimination);
+    }
+    if !policy.is_allowed(kind) {
+        return Err(Error::UnsupportedKind);
+    }
+    Address::from_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+
+    #[test]
+    fn all_errors_are_reported_with_their_index() {
+        let batch = vec![
+            Address(Discrimination::Test, Kind::Script([0; 32])).to_bytes(),
+            Address(Discrimination::Production, Kind::Script([1; 32])).to_bytes(),
+            vec![],
+            Address(Discrimination::Test, Kind::Multisig([2; 32])).to_bytes(),
+            Address(Discrimination::Test, Kind::Script([3; 32])).to_bytes(),
+        ];
+        let policy = KindPolicy::all().deny(KindType::Multisig);
+
+        let errors = validate_batch_with_policy(&batch, Discrimination::Test, policy).unwrap_err();
+        let errors: Vec<_> = errors.iter().map(|e| (e.index, &e.error)).collect();
+        assert!(matches!(
+            errors[..],
+            [
+                (1, Error::MismatchDiscrimination),
+                (2, Error::EmptyAddress),
+                (3, Error::UnsupportedKind),
+            ]
+        ));
+
+        let addresses = validate_batch(&batch[3..], Discrimination::Test).unwrap();
+        assert_eq!(addresses.len(), 2);
+    }
+
+    #[test]
+    fn kind_policy() {
+        let policy = KindPolicy::none().allow(KindType::Account);
+        assert!(policy.is_allowed(KindType::Account));
+        assert!(!policy.is_allowed(KindType::Single));
+        assert!(!policy.deny(KindType::Account).is_allowed(KindType::Account));
+        assert!(KindPolicy::default().is_allowed(KindType::Script));
+    }
+}
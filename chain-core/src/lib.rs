@@ -1,3 +1,7 @@
 pub use chain_ser::abor;
+#[cfg(feature = "async")]
+pub use chain_ser::async_packer;
+#[cfg(feature = "cbor")]
+pub use chain_ser::cbor;
 pub use chain_ser::packer;
 pub mod property;
@@ -34,8 +34,7 @@ pub use chain_ser::deser::*;
 use std::{fmt::Debug, hash::Hash};
 
 /// Trait identifying the block identifier type.
-/// TODO: add a Readable trait bound
-pub trait BlockId: Eq + Ord + Clone + Debug + Hash + Serialize {
+pub trait BlockId: Eq + Ord + Clone + Debug + Hash + Serialize + DeserializeFromSlice {
     /// A special ID used to denote a non-existent block (e.g. the
     /// parent of the first block).
     fn zero() -> Self;
@@ -54,7 +53,7 @@ pub trait ChainLength: Eq + Ord + Clone + Debug {
 pub trait TransactionId: Eq + Hash + Debug {}
 
 /// Trait identifying the block header type.
-pub trait Header: Serialize {
+pub trait Header: Serialize + DeserializeFromSlice {
     /// The block header id.
     type Id: BlockId;
 
@@ -96,8 +95,7 @@ pub trait Header: Serialize {
 /// recent block to the furthest/oldest block.
 ///
 /// The Oldest block is called the Genesis Block.
-/// TODO: add a Readable trait bound
-pub trait Block: Serialize {
+pub trait Block: Serialize + DeserializeFromSlice {
     /// the Block identifier. It must be unique. This mean that
     /// 2 different blocks have 2 different identifiers.
     ///
@@ -150,16 +148,14 @@ pub trait HasHeader {
 }
 
 /// Trait identifying the fragment identifier type.
-/// TODO: add a Readable trait bound
-pub trait FragmentId: Eq + Hash + Clone + Debug + Serialize {}
+pub trait FragmentId: Eq + Hash + Clone + Debug + Serialize + DeserializeFromSlice {}
 
 /// A fragment is some item contained in a block, such as a
 /// transaction, a delegation-related certificate, an update proposal,
 /// and so on. Fragments can be serialized (so that they can be
 /// concatenated to form a binary block( and have a unique ID
 /// (typically the hash of their serialization).
-/// TODO: add a Readable trait bound
-pub trait Fragment: Serialize {
+pub trait Fragment: Serialize + DeserializeFromSlice {
     type Id: FragmentId;
 
     /// Return the message's identifier.
@@ -184,9 +180,7 @@ pub trait HasFragments<'a> {
 /// define a transaction within the blockchain. This transaction can be used
 /// for the UTxO model. However it can also be used for any other elements that
 /// the blockchain has (a transaction type to add Stacking Pools and so on...).
-///
-/// TODO: add a Readable trait bound
-pub trait Transaction: Serialize {
+pub trait Transaction: Serialize + DeserializeFromSlice {
     /// The input type of the transaction (if none use `()`).
     type Input;
     /// The output type of the transaction (if none use `()`).
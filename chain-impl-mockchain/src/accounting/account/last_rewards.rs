@@ -1,11 +1,12 @@
 use crate::date::Epoch;
 use crate::value::Value;
+use chain_ser::{ChainDeserialize, ChainSerialize};
 
 /// Last rewards associated with a state
 ///
 /// It tracks the epoch where the rewards has been received,
 /// and the total amount of reward for such an epoch
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, ChainSerialize, ChainDeserialize)]
 #[cfg_attr(
     any(test, feature = "property-test-api"),
     derive(test_strategy::Arbitrary)
@@ -59,6 +59,21 @@ impl Block {
     pub fn fragments(&self) -> impl Iterator<Item = &Fragment> {
         self.contents.iter()
     }
+
+    /// Checks the decoded contents against the header.
+    fn from_parts(header: Header, contents: Contents) -> Result<Self, ReadError> {
+        let (content_hash, _content_size) = contents.compute_hash_size();
+
+        if header.block_content_hash() != content_hash {
+            return Err(ReadError::InvalidData(format!(
+                "Inconsistent block content hash in header: block {} header {}",
+                content_hash,
+                header.block_content_hash()
+            )));
+        }
+
+        Ok(Block { header, contents })
+    }
 }
 
 impl property::Block for Block {
@@ -112,41 +127,65 @@ impl Serialize for Block {
 
 impl Deserialize for Block {
     fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
-        let header = Header::deserialize(codec)?;
+        let header = codec.scoped("header", Header::deserialize)?;
         let mut remaining_content_size = header.block_content_size() as usize;
         let mut contents = ContentsBuilder::new();
 
         while remaining_content_size > 0 {
-            let message = Fragment::deserialize(codec)?;
-            let message_size = message.serialized_size();
-
-            if message_size > remaining_content_size {
-                return Err(ReadError::StructureInvalid(format!(
-                    "{} bytes remaining according to the header but got a fragment of size {}",
-                    message_size, remaining_content_size
-                )));
-            }
-
+            let message = codec.scoped("contents", Fragment::deserialize)?;
+            remaining_content_size = check_fragment_size(&message, remaining_content_size)?;
             contents.push(message);
-
-            remaining_content_size -= message_size;
         }
 
-        let contents: Contents = contents.into();
-        let (content_hash, _content_size) = contents.compute_hash_size();
+        Block::from_parts(header, contents.into())
+    }
+}
 
-        if header.block_content_hash() != content_hash {
-            return Err(ReadError::InvalidData(format!(
-                "Inconsistent block content hash in header: block {} header {}",
-                content_hash,
-                header.block_content_hash()
-            )));
+#[cfg(feature = "async")]
+impl Block {
+    /// Reads a block from an asynchronous stream.
+    ///
+    /// The fragments are decoded as they arrive, so at most one fragment
+    /// is buffered at a time, and no fragment may be larger than
+    /// the content size remaining according to the header.
+    pub async fn deserialize_async<R>(
+        codec: &mut chain_core::async_packer::AsyncCodec<R>,
+    ) -> Result<Self, ReadError>
+    where
+        R: futures::io::AsyncRead + Unpin,
+    {
+        let header = Header::deserialize_async(codec).await?;
+        let mut remaining_content_size = header.block_content_size() as usize;
+        let mut contents = ContentsBuilder::new();
+
+        while remaining_content_size > 0 {
+            let max_size = remaining_content_size.saturating_sub(Codec::u32_size());
+            let message = Fragment::deserialize_async(codec, max_size).await?;
+            remaining_content_size = check_fragment_size(&message, remaining_content_size)?;
+            contents.push(message);
         }
 
-        Ok(Block { header, contents })
+        Block::from_parts(header, contents.into())
     }
 }
 
+// Returns the content size remaining after the fragment.
+fn check_fragment_size(
+    message: &Fragment,
+    remaining_content_size: usize,
+) -> Result<usize, ReadError> {
+    let message_size = message.serialized_size();
+
+    if message_size > remaining_content_size {
+        return Err(ReadError::StructureInvalid(format!(
+            "{} bytes remaining according to the header but got a fragment of size {}",
+            message_size, remaining_content_size
+        )));
+    }
+
+    Ok(remaining_content_size - message_size)
+}
+
 impl<'a> property::HasFragments<'a> for &'a Block {
     type Fragment = Fragment;
     type Fragments = slice::Iter<'a, Fragment>;
@@ -61,6 +61,77 @@ quickcheck! {
     }
 }
 
+#[test]
+fn fragment_errors_in_block_have_absolute_offsets() {
+    use crate::{chaintypes::ChainLength, date::BlockDate, fragment::ConfigParams, key::Hash};
+    use chain_core::property::ReadError;
+
+    let mut contents = ContentsBuilder::new();
+    contents.push(Fragment::Initial(ConfigParams::new()));
+    let contents: Contents = contents.into();
+    let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+        .set_parent(&Hash::zero_hash(), ChainLength(0))
+        .set_date(BlockDate::first())
+        .into_unsigned_header()
+        .unwrap()
+        .generalize();
+    let header_size = header.serialize_as_vec().unwrap().len();
+    let mut bytes = Block { header, contents }.serialize_as_vec().unwrap();
+
+    // the tag of the fragment follows its size and padding tag
+    let tag_offset = header_size + 4 + 1;
+    bytes[tag_offset] = 0xff;
+
+    let err = Block::deserialize(&mut Codec::new(bytes.as_slice())).unwrap_err();
+    assert_eq!(err.path(), ["contents", "tag"]);
+    assert_eq!(err.offset(), Some(tag_offset + 1));
+    assert!(matches!(err.root_cause(), ReadError::UnknownTag(0xff)));
+}
+
+#[cfg(feature = "async")]
+quickcheck! {
+    fn block_async_deserialization(b: Block) -> bool {
+        use chain_core::async_packer::AsyncCodec;
+
+        let bytes = b.serialize_as_vec().unwrap();
+        let mut codec = AsyncCodec::new(bytes.as_slice());
+        let decoded = futures::executor::block_on(Block::deserialize_async(&mut codec)).unwrap();
+        decoded.serialize_as_vec().unwrap() == bytes && codec.into_inner().is_empty()
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_block_deserialization_rejects_oversized_fragments() {
+    use crate::{chaintypes::ChainLength, date::BlockDate, fragment::ConfigParams, key::Hash};
+    use chain_core::{async_packer::AsyncCodec, property::ReadError};
+
+    let mut contents = ContentsBuilder::new();
+    contents.push(Fragment::Initial(ConfigParams::new()));
+    let contents: Contents = contents.into();
+    let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+        .set_parent(&Hash::zero_hash(), ChainLength(0))
+        .set_date(BlockDate::first())
+        .into_unsigned_header()
+        .unwrap()
+        .generalize();
+    let header_size = header.serialize_as_vec().unwrap().len();
+    let content_size = header.block_content_size() as usize;
+    let mut bytes = Block { header, contents }.serialize_as_vec().unwrap();
+
+    // the size of the only fragment exceeds the content size in the header
+    bytes[header_size..header_size + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+    let mut codec = AsyncCodec::new(bytes.as_slice());
+    let err = futures::executor::block_on(Block::deserialize_async(&mut codec)).unwrap_err();
+    assert!(matches!(
+        err,
+        ReadError::SizeTooBig(max, size) if max == content_size - 4 && size == u32::MAX as usize
+    ));
+    // the body of the fragment has not been read
+    assert_eq!(codec.into_inner().len(), content_size - 4);
+}
+
 #[cfg(test)]
 fn are_desc_equal(left: HeaderDesc, right: HeaderDesc) -> bool {
     left.id == right.id
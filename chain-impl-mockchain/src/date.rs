@@ -1,4 +1,5 @@
 use chain_core::property;
+use chain_ser::{ChainDeserialize, ChainSerialize};
 use chain_time::era::EpochPosition;
 use chain_time::era::TimeEra;
 
@@ -7,7 +8,9 @@ use std::{error, fmt, num::ParseIntError, str};
 /// Non unique identifier of the transaction position in the
 /// blockchain. There may be many transactions related to the same
 /// `SlotId`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ChainSerialize, ChainDeserialize,
+)]
 pub struct BlockDate {
     pub epoch: Epoch,
     pub slot_id: SlotId,
@@ -126,70 +126,83 @@ impl Fragment {
 
 impl Deserialize for Fragment {
     fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
-        let size = codec.get_be_u32()? as usize;
-        let bytes = codec.get_bytes(size)?;
+        let size = codec.scoped("size", |codec| codec.get_be_u32())? as usize;
+        let bytes = codec.scoped("body", |codec| codec.get_bytes(size))?;
+        let body_start = codec.bytes_consumed() - size;
 
-        let mut codec = Codec::new(bytes.as_slice());
+        let mut codec = Codec::with_offset(bytes.as_slice(), body_start);
 
-        let padding_tag = codec.get_u8()?;
-        if padding_tag != 0 {
-            return Err(ReadError::StructureInvalid(format!(
+        codec.scoped("padding_tag", |codec| match codec.get_u8()? {
+            0 => Ok(()),
+            padding_tag => Err(ReadError::StructureInvalid(format!(
                 "fragment padding tag expected at 0 but got {}",
                 padding_tag
-            )));
-        }
+            ))),
+        })?;
 
-        let tag = codec.get_u8()?;
-        match FragmentTag::from_u8(tag) {
-            Some(FragmentTag::Initial) => {
-                ConfigParams::deserialize_from_slice(&mut codec).map(Fragment::Initial)
+        let tag = codec.scoped("tag", |codec| {
+            let tag = codec.get_u8()?;
+            FragmentTag::from_u8(tag).ok_or(ReadError::UnknownTag(tag as u32))
+        })?;
+        codec.scoped("payload", |codec| match tag {
+            FragmentTag::Initial => {
+                ConfigParams::deserialize_from_slice(codec).map(Fragment::Initial)
             }
-            Some(FragmentTag::OldUtxoDeclaration) => {
-                legacy::UtxoDeclaration::deserialize_from_slice(&mut codec)
+            FragmentTag::OldUtxoDeclaration => {
+                legacy::UtxoDeclaration::deserialize_from_slice(codec)
                     .map(Fragment::OldUtxoDeclaration)
             }
-            Some(FragmentTag::Transaction) => {
-                Transaction::deserialize(&mut codec).map(Fragment::Transaction)
-            }
-            Some(FragmentTag::OwnerStakeDelegation) => {
-                Transaction::deserialize(&mut codec).map(Fragment::OwnerStakeDelegation)
-            }
-            Some(FragmentTag::StakeDelegation) => {
-                Transaction::deserialize(&mut codec).map(Fragment::StakeDelegation)
-            }
-            Some(FragmentTag::PoolRegistration) => {
-                Transaction::deserialize(&mut codec).map(Fragment::PoolRegistration)
-            }
-            Some(FragmentTag::PoolRetirement) => {
-                Transaction::deserialize(&mut codec).map(Fragment::PoolRetirement)
-            }
-            Some(FragmentTag::PoolUpdate) => {
-                Transaction::deserialize(&mut codec).map(Fragment::PoolUpdate)
-            }
-            Some(FragmentTag::UpdateProposal) => {
-                Transaction::deserialize(&mut codec).map(Fragment::UpdateProposal)
+            FragmentTag::Transaction => Transaction::deserialize(codec).map(Fragment::Transaction),
+            FragmentTag::OwnerStakeDelegation => {
+                Transaction::deserialize(codec).map(Fragment::OwnerStakeDelegation)
             }
-            Some(FragmentTag::UpdateVote) => {
-                Transaction::deserialize(&mut codec).map(Fragment::UpdateVote)
+            FragmentTag::StakeDelegation => {
+                Transaction::deserialize(codec).map(Fragment::StakeDelegation)
             }
-            Some(FragmentTag::VotePlan) => {
-                Transaction::deserialize(&mut codec).map(Fragment::VotePlan)
+            FragmentTag::PoolRegistration => {
+                Transaction::deserialize(codec).map(Fragment::PoolRegistration)
             }
-            Some(FragmentTag::VoteCast) => {
-                Transaction::deserialize(&mut codec).map(Fragment::VoteCast)
+            FragmentTag::PoolRetirement => {
+                Transaction::deserialize(codec).map(Fragment::PoolRetirement)
             }
-            Some(FragmentTag::VoteTally) => {
-                Transaction::deserialize(&mut codec).map(Fragment::VoteTally)
+            FragmentTag::PoolUpdate => Transaction::deserialize(codec).map(Fragment::PoolUpdate),
+            FragmentTag::UpdateProposal => {
+                Transaction::deserialize(codec).map(Fragment::UpdateProposal)
             }
-            Some(FragmentTag::MintToken) => {
-                Transaction::deserialize(&mut codec).map(Fragment::MintToken)
-            }
-            Some(FragmentTag::Evm) => EvmTransaction::deserialize(&mut codec).map(Fragment::Evm),
-            Some(FragmentTag::EvmMapping) => {
-                Transaction::deserialize(&mut codec).map(Fragment::EvmMapping)
-            }
-            None => Err(ReadError::UnknownTag(tag as u32)),
+            FragmentTag::UpdateVote => Transaction::deserialize(codec).map(Fragment::UpdateVote),
+            FragmentTag::VotePlan => Transaction::deserialize(codec).map(Fragment::VotePlan),
+            FragmentTag::VoteCast => Transaction::deserialize(codec).map(Fragment::VoteCast),
+            FragmentTag::VoteTally => Transaction::deserialize(codec).map(Fragment::VoteTally),
+            FragmentTag::MintToken => Transaction::deserialize(codec).map(Fragment::MintToken),
+            FragmentTag::Evm => EvmTransaction::deserialize(codec).map(Fragment::Evm),
+            FragmentTag::EvmMapping => Transaction::deserialize(codec).map(Fragment::EvmMapping),
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl Fragment {
+    /// Reads a fragment from an asynchronous stream.
+    ///
+    /// Only the bytes of this fragment are buffered before it is decoded,
+    /// and a fragment with a body larger than `max_size` is rejected
+    /// before its body is read.
+    pub async fn deserialize_async<R>(
+        codec: &mut chain_core::async_packer::AsyncCodec<R>,
+        max_size: usize,
+    ) -> Result<Self, ReadError>
+    where
+        R: futures::io::AsyncRead + Unpin,
+    {
+        let size = codec.get_be_u32().await?;
+        if size as usize > max_size {
+            return Err(ReadError::SizeTooBig(max_size, size as usize));
         }
+        // keep the size prefix so that the errors are reported as by `deserialize`
+        let mut bytes = vec![0; Codec::u32_size() + size as usize];
+        bytes[..Codec::u32_size()].copy_from_slice(&size.to_be_bytes());
+        codec.copy_to_slice(&mut bytes[Codec::u32_size()..]).await?;
+        Fragment::deserialize(&mut Codec::new(bytes.as_slice()))
     }
 }
 
@@ -241,8 +254,9 @@ impl Serialize for Fragment {
             Fragment::EvmMapping(evm_mapping) => evm_mapping.serialize(&mut tmp)?,
         };
         let bytes = tmp.into_inner();
-        codec.put_be_u32(bytes.len() as u32)?;
-        codec.put_bytes(bytes.as_slice())
+        // a single write of the size and the body when the writer is e.g. a socket
+        let size = (bytes.len() as u32).to_be_bytes();
+        codec.put_bytes_vectored(&[&size, &bytes])
     }
 }
 
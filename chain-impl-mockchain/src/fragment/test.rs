@@ -57,3 +57,18 @@ quickcheck! {
         serialization_bijection(config_params)
     }
 }
+
+#[test]
+fn fragment_errors_carry_field_path() {
+    // the body is shorter than announced by the size
+    let bytes = [0u8, 0, 0, 8, 0, 2];
+    let err = Fragment::deserialize(&mut Codec::new(&bytes[..])).unwrap_err();
+    assert_eq!(err.path(), ["body"]);
+    assert_eq!(err.offset(), Some(4));
+
+    let bytes = [0u8, 0, 0, 2, 0, 0xff];
+    let err = Fragment::deserialize(&mut Codec::new(&bytes[..])).unwrap_err();
+    assert_eq!(err.path(), ["tag"]);
+    assert_eq!(err.offset(), Some(6));
+    assert!(matches!(err.root_cause(), ReadError::UnknownTag(0xff)));
+}
@@ -314,20 +314,44 @@ impl Serialize for Header {
     }
 }
 
+fn header_size(tag: u8) -> Result<usize, ReadError> {
+    match BlockVersion::from_u8(tag) {
+        Some(BlockVersion::Ed25519Signed) => Ok(HEADER_BFT_SIZE),
+        Some(BlockVersion::Genesis) => Ok(HEADER_COMMON_SIZE),
+        Some(BlockVersion::KesVrfproof) => Ok(HEADER_GP_SIZE),
+        None => Err(ReadError::UnknownTag(tag as u32)),
+    }
+}
+
+fn header_from_bytes(bytes: &[u8]) -> Result<Header, ReadError> {
+    Header::from_slice(bytes).map_err(|e| match e {
+        HeaderError::InvalidSize => ReadError::NotEnoughBytes(0, 0),
+        HeaderError::UnknownVersion => ReadError::UnknownTag(0),
+        HeaderError::SizeMismatch { expected, got } => ReadError::SizeTooBig(expected, got),
+    })
+}
+
 impl Deserialize for Header {
     fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
-        let tag = codec.get_u8()?;
-        let header_size = match BlockVersion::from_u8(tag) {
-            Some(BlockVersion::Ed25519Signed) => HEADER_BFT_SIZE,
-            Some(BlockVersion::Genesis) => HEADER_COMMON_SIZE,
-            Some(BlockVersion::KesVrfproof) => HEADER_GP_SIZE,
-            None => return Err(ReadError::UnknownTag(tag as u32)),
-        };
+        let header_size = header_size(codec.get_u8()?)?;
         let bytes = codec.get_bytes(header_size)?;
-        Header::from_slice(bytes.as_slice()).map_err(|e| match e {
-            HeaderError::InvalidSize => ReadError::NotEnoughBytes(0, 0),
-            HeaderError::UnknownVersion => ReadError::UnknownTag(0),
-            HeaderError::SizeMismatch { expected, got } => ReadError::SizeTooBig(expected, got),
-        })
+        header_from_bytes(bytes.as_slice())
+    }
+}
+
+#[cfg(feature = "async")]
+impl Header {
+    /// Reads a header from an asynchronous stream. The size of the header
+    /// is determined by the version tag, so no more than the bytes of the
+    /// header are read from the stream.
+    pub async fn deserialize_async<R>(
+        codec: &mut chain_core::async_packer::AsyncCodec<R>,
+    ) -> Result<Self, ReadError>
+    where
+        R: futures::io::AsyncRead + Unpin,
+    {
+        let header_size = header_size(codec.get_u8().await?)?;
+        let bytes = codec.get_bytes(header_size).await?;
+        header_from_bytes(bytes.as_slice())
     }
 }
@@ -37,7 +37,7 @@ impl Ledger {
             ),
             format!(
                 "accounts: #{} Total={:?}",
-                accounts.iter().count(),
+                accounts.len(),
                 Value::sum(accounts.iter().map(|x| x.1.value))
             ),
             format!(
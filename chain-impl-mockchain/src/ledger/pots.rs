@@ -1,6 +1,7 @@
 use crate::ledger::Error;
 use crate::treasury::Treasury;
 use crate::value::{Value, ValueError};
+use chain_ser::{ChainDeserialize, ChainSerialize};
 use std::cmp;
 use std::fmt::Debug;
 
@@ -12,7 +13,7 @@ pub struct Pots {
     pub(crate) rewards: Value,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ChainSerialize, ChainDeserialize)]
 pub enum Entry {
     Fees(Value),
     Treasury(Value),
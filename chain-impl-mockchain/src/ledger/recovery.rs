@@ -52,7 +52,7 @@ use crate::header::{ChainLength, HeaderId};
 use crate::key::serialize_public_key;
 use crate::ledger::{Globals, Ledger, LedgerStaticParameters};
 use crate::legacy;
-use crate::multisig::{DeclElement, Declaration};
+use crate::multisig::Declaration;
 use crate::stake::{PoolLastRewards, PoolState};
 use crate::tokens::identifier::TokenIdentifier;
 use crate::tokens::name::TokenName;
@@ -122,15 +122,11 @@ fn pack_digestof<H: DigestAlg, T, W: std::io::Write>(
     digestof: &DigestOf<H, T>,
     codec: &mut Codec<W>,
 ) -> Result<(), WriteError> {
-    let inner_data = digestof.as_ref();
-    codec.put_be_u64(inner_data.len() as u64)?;
-    codec.put_bytes(inner_data)?;
-    Ok(())
+    codec.put_len_prefixed_bytes(digestof.as_ref())
 }
 
 fn unpack_digestof<H: DigestAlg, T>(codec: &mut Codec<&[u8]>) -> Result<DigestOf<H, T>, ReadError> {
-    let size = codec.get_be_u64()? as usize;
-    let bytes = codec.get_bytes(size)?;
+    let bytes = codec.get_len_prefixed_bytes(H::HASH_SIZE)?;
     DigestOf::try_from(bytes.as_slice()).map_err(|e| ReadError::InvalidData(e.to_string()))
 }
 
@@ -231,7 +227,7 @@ fn pack_account_state<W: std::io::Write>(
     pack_spending_strategy(&account_state.spending, codec)?;
     pack_delegation_type(&account_state.delegation, codec)?;
     codec.put_be_u64(account_state.value.0)?;
-    pack_last_rewards(&account_state.last_rewards, codec)?;
+    account_state.last_rewards.serialize(codec)?;
     #[cfg(feature = "evm")]
     pack_evm_state(&account_state.evm_state, codec)?;
     Ok(())
@@ -241,7 +237,7 @@ fn unpack_account_state(codec: &mut Codec<&[u8]>) -> Result<AccountState<()>, Re
     let spending = unpack_spending_strategy(codec)?;
     let delegation = unpack_delegation_type(codec)?;
     let value = codec.get_be_u64()?;
-    let last_rewards = unpack_last_rewards(codec)?;
+    let last_rewards = LastRewards::deserialize(codec)?;
     #[cfg(feature = "evm")]
     let evm_state = unpack_evm_state(codec)?;
     Ok(AccountState {
@@ -316,22 +312,6 @@ fn unpack_delegation_type(codec: &mut Codec<&[u8]>) -> Result<DelegationType, Re
     }
 }
 
-fn pack_last_rewards<W: std::io::Write>(
-    last_rewards: &LastRewards,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    codec.put_be_u32(last_rewards.epoch)?;
-    codec.put_be_u64(last_rewards.reward.0)?;
-    Ok(())
-}
-
-fn unpack_last_rewards(codec: &mut Codec<&[u8]>) -> Result<LastRewards, ReadError> {
-    Ok(LastRewards {
-        epoch: codec.get_be_u32()?,
-        reward: Value(codec.get_be_u64()?),
-    })
-}
-
 #[cfg(test)]
 fn pack_consensus_version<W: std::io::Write>(
     consensus_version: ConsensusVersion,
@@ -382,20 +362,6 @@ fn unpack_config_param(codec: &mut Codec<&[u8]>) -> Result<ConfigParam, ReadErro
     ConfigParam::deserialize_from_slice(codec)
 }
 
-fn pack_block_date<W: std::io::Write>(
-    block_date: BlockDate,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    codec.put_be_u32(block_date.epoch)?;
-    codec.put_be_u32(block_date.slot_id)
-}
-
-fn unpack_block_date(codec: &mut Codec<&[u8]>) -> Result<BlockDate, ReadError> {
-    let epoch = codec.get_be_u32()?;
-    let slot_id = codec.get_be_u32()?;
-    Ok(BlockDate { epoch, slot_id })
-}
-
 #[cfg(test)]
 fn pack_linear_fee<W: std::io::Write>(
     linear_fee: &LinearFee,
@@ -551,7 +517,7 @@ fn pack_globals<W: std::io::Write>(
     globals: &Globals,
     codec: &mut Codec<W>,
 ) -> Result<(), WriteError> {
-    pack_block_date(globals.date, codec)?;
+    globals.date.serialize(codec)?;
     codec.put_be_u32(globals.chain_length.0)?;
     pack_ledger_static_parameters(&globals.static_params, codec)?;
     pack_time_era(&globals.era, codec)?;
@@ -559,7 +525,7 @@ fn pack_globals<W: std::io::Write>(
 }
 
 fn unpack_globals(codec: &mut Codec<&[u8]>) -> Result<Globals, ReadError> {
-    let date = unpack_block_date(codec)?;
+    let date = BlockDate::deserialize(codec)?;
     let chain_length = ChainLength(codec.get_be_u32()?);
     let static_params = unpack_ledger_static_parameters(codec)?;
     let era = unpack_time_era(codec)?;
@@ -571,39 +537,6 @@ fn unpack_globals(codec: &mut Codec<&[u8]>) -> Result<Globals, ReadError> {
     })
 }
 
-fn pack_pot_entry<W: std::io::Write>(
-    entry: &pots::Entry,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    match entry {
-        pots::Entry::Fees(value) => {
-            codec.put_u8(0)?;
-            codec.put_be_u64(value.0)?;
-        }
-        pots::Entry::Treasury(value) => {
-            codec.put_u8(1)?;
-            codec.put_be_u64(value.0)?;
-        }
-        pots::Entry::Rewards(value) => {
-            codec.put_u8(2)?;
-            codec.put_be_u64(value.0)?;
-        }
-    }
-    Ok(())
-}
-
-fn unpack_pot_entry(codec: &mut Codec<&[u8]>) -> Result<pots::Entry, ReadError> {
-    match codec.get_u8()? {
-        0 => Ok(pots::Entry::Fees(Value(codec.get_be_u64()?))),
-        1 => Ok(pots::Entry::Treasury(Value(codec.get_be_u64()?))),
-        2 => Ok(pots::Entry::Rewards(Value(codec.get_be_u64()?))),
-        code => Err(ReadError::InvalidData(format!(
-            "Invalid Entry type code {}",
-            code
-        ))),
-    }
-}
-
 fn pack_multisig_identifier<W: std::io::Write>(
     identifier: &multisig::Identifier,
     codec: &mut Codec<W>,
@@ -615,90 +548,17 @@ fn unpack_multisig_identifier(codec: &mut Codec<&[u8]>) -> Result<multisig::Iden
     Ok(multisig::Identifier(key::Hash::deserialize(codec)?))
 }
 
-fn pack_declaration<W: std::io::Write>(
-    declaration: &Declaration,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    codec.put_u8(declaration.threshold)?;
-    codec.put_be_u64(declaration.owners.len() as u64)?;
-    for owner in &declaration.owners {
-        pack_decl_element(owner, codec)?;
-    }
-    Ok(())
-}
-
-fn unpack_declaration(codec: &mut Codec<&[u8]>) -> Result<Declaration, ReadError> {
-    let threshold = codec.get_u8()?;
-    let size = codec.get_be_u64()?;
-    let mut owners: Vec<DeclElement> = Vec::with_capacity(size as usize);
-    for _ in 0..size {
-        let decl_element = unpack_decl_element(codec)?;
-        owners.push(decl_element);
-    }
-    Ok(Declaration { threshold, owners })
-}
-
-fn pack_decl_element<W: std::io::Write>(
-    decl_element: &DeclElement,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    match &decl_element {
-        DeclElement::Sub(declaration) => {
-            codec.put_u8(0)?;
-            pack_declaration(declaration, codec)?;
-        }
-        DeclElement::Owner(hash) => {
-            codec.put_u8(1)?;
-            hash.serialize(codec)?;
-        }
-    }
-    Ok(())
-}
-
-fn unpack_decl_element(codec: &mut Codec<&[u8]>) -> Result<DeclElement, ReadError> {
-    match codec.get_u8()? {
-        0 => Ok(DeclElement::Sub(unpack_declaration(codec)?)),
-        1 => Ok(DeclElement::Owner(key::Hash::deserialize(codec)?)),
-        code => Err(ReadError::InvalidData(format!(
-            "Invalid DeclElement type code {}",
-            code
-        ))),
-    }
-}
-
-fn pack_pool_last_rewards<W: std::io::Write>(
-    pool_last_rewards: &PoolLastRewards,
-    codec: &mut Codec<W>,
-) -> Result<(), WriteError> {
-    codec.put_be_u32(pool_last_rewards.epoch)?;
-    codec.put_be_u64(pool_last_rewards.value_taxed.0)?;
-    codec.put_be_u64(pool_last_rewards.value_for_stakers.0)?;
-    Ok(())
-}
-
-fn unpack_pool_last_rewards(codec: &mut Codec<&[u8]>) -> Result<PoolLastRewards, ReadError> {
-    let epoch = codec.get_be_u32()?;
-    let value_taxed = Value(codec.get_be_u64()?);
-    let value_for_stakers = Value(codec.get_be_u64()?);
-
-    Ok(PoolLastRewards {
-        epoch,
-        value_taxed,
-        value_for_stakers,
-    })
-}
-
 fn pack_pool_state<W: std::io::Write>(
     pool_state: &PoolState,
     codec: &mut Codec<W>,
 ) -> Result<(), WriteError> {
-    pack_pool_last_rewards(&pool_state.last_rewards, codec)?;
+    pool_state.last_rewards.serialize(codec)?;
     pack_pool_registration(&pool_state.registration, codec)?;
     Ok(())
 }
 
 fn unpack_pool_state(codec: &mut Codec<&[u8]>) -> Result<PoolState, ReadError> {
-    let last_rewards = unpack_pool_last_rewards(codec)?;
+    let last_rewards = PoolLastRewards::deserialize(codec)?;
     let registration = Arc::new(unpack_pool_registration(codec)?);
 
     Ok(PoolState {
@@ -712,7 +572,7 @@ fn pack_update_proposal_state<W: std::io::Write>(
     codec: &mut Codec<W>,
 ) -> Result<(), WriteError> {
     pack_update_proposal(&update_proposal_state.proposal, codec)?;
-    pack_block_date(update_proposal_state.proposal_date, codec)?;
+    update_proposal_state.proposal_date.serialize(codec)?;
     codec.put_be_u64(update_proposal_state.votes.size() as u64)?;
     {
         for (voter, _) in &update_proposal_state.votes {
@@ -726,7 +586,7 @@ fn unpack_update_proposal_state(
     codec: &mut Codec<&[u8]>,
 ) -> Result<UpdateProposalState, ReadError> {
     let proposal = unpack_update_proposal(codec)?;
-    let proposal_date = unpack_block_date(codec)?;
+    let proposal_date = BlockDate::deserialize(codec)?;
     let total_votes = codec.get_be_u64()?;
     let mut votes = Hamt::new();
 
@@ -836,8 +696,9 @@ fn pack_old_addr<W: std::io::Write>(
 }
 
 fn unpack_old_addr(codec: &mut Codec<&[u8]>) -> Result<legacy::OldAddress, ReadError> {
-    let size = codec.get_be_u64()? as usize;
-    let v = codec.get_bytes(size)?;
+    // the legacy addresses come from the UTxO declarations of the genesis
+    // block, where their size is a u16
+    let v = codec.get_len_prefixed_bytes(u16::MAX as usize)?;
     Ok(legacy::OldAddress::new(v))
 }
 
@@ -854,9 +715,8 @@ fn pack_address<W: std::io::Write>(
 
 fn unpack_address(codec: &mut Codec<&[u8]>) -> Result<Address, ReadError> {
     // TODO use Deserialize trait
-    let size = codec.get_be_u64()? as usize;
-    let v = codec.get_slice(size)?;
-    Address::from_bytes(v).map_err(|e| {
+    let v = codec.get_len_prefixed_bytes(chain_addr::MAX_ADDRESS_SIZE)?;
+    Address::from_bytes(&v).map_err(|e| {
         ReadError::InvalidData(format!("Error reading address from packed bytes: {}", e))
     })
 }
@@ -971,9 +831,9 @@ fn pack_vote_plan<W: std::io::Write>(
     vote_plan: &VotePlan,
     codec: &mut Codec<W>,
 ) -> Result<(), WriteError> {
-    pack_block_date(vote_plan.vote_start(), codec)?;
-    pack_block_date(vote_plan.vote_end(), codec)?;
-    pack_block_date(vote_plan.committee_end(), codec)?;
+    vote_plan.vote_start().serialize(codec)?;
+    vote_plan.vote_end().serialize(codec)?;
+    vote_plan.committee_end().serialize(codec)?;
     pack_payload_type(vote_plan.payload_type(), codec)?;
     pack_vote_proposals(vote_plan.proposals(), codec)?;
     pack_committee_public_keys(vote_plan.committee_public_keys(), codec)?;
@@ -982,9 +842,9 @@ fn pack_vote_plan<W: std::io::Write>(
 }
 
 fn unpack_vote_plan(codec: &mut Codec<&[u8]>) -> Result<VotePlan, ReadError> {
-    let vote_start = unpack_block_date(codec)?;
-    let vote_end = unpack_block_date(codec)?;
-    let committee_end = unpack_block_date(codec)?;
+    let vote_start = BlockDate::deserialize(codec)?;
+    let vote_end = BlockDate::deserialize(codec)?;
+    let committee_end = BlockDate::deserialize(codec)?;
     let payload_type = unpack_payload_type(codec)?;
     let proposals = unpack_proposals(codec)?;
     let keys = unpack_committee_public_keys(codec)?;
@@ -1049,7 +909,7 @@ fn pack_entry<W: std::io::Write>(
         }
         Entry::Pot(entry) => {
             codec.put_u8(EntrySerializeCode::Pot as u8)?;
-            pack_pot_entry(entry, codec)?;
+            entry.serialize(codec)?;
         }
         Entry::Utxo(entry) => {
             codec.put_u8(EntrySerializeCode::Utxo as u8)?;
@@ -1081,7 +941,7 @@ fn pack_entry<W: std::io::Write>(
         Entry::MultisigDeclaration((identifier, declaration)) => {
             codec.put_u8(EntrySerializeCode::MultisigDeclaration as u8)?;
             pack_multisig_identifier(identifier, codec)?;
-            pack_declaration(declaration, codec)?;
+            declaration.serialize(codec)?;
         }
         Entry::StakePool((pool_id, pool_state)) => {
             codec.put_u8(EntrySerializeCode::StakePool as u8)?;
@@ -1111,7 +971,7 @@ fn unpack_entry_owned(codec: &mut Codec<&[u8]>) -> Result<EntryOwned, ReadError>
     })?;
     match code {
         EntrySerializeCode::Globals => Ok(EntryOwned::Globals(unpack_globals(codec)?)),
-        EntrySerializeCode::Pot => Ok(EntryOwned::Pot(unpack_pot_entry(codec)?)),
+        EntrySerializeCode::Pot => Ok(EntryOwned::Pot(pots::Entry::deserialize(codec)?)),
         EntrySerializeCode::Utxo => Ok(EntryOwned::Utxo(unpack_utxo_entry_owned(
             &mut unpack_address,
             codec,
@@ -1138,7 +998,7 @@ fn unpack_entry_owned(codec: &mut Codec<&[u8]>) -> Result<EntryOwned, ReadError>
         }
         EntrySerializeCode::MultisigDeclaration => {
             let identifier = unpack_multisig_identifier(codec)?;
-            let declaration = unpack_declaration(codec)?;
+            let declaration = Declaration::deserialize(codec)?;
             Ok(EntryOwned::MultisigDeclaration((identifier, declaration)))
         }
         EntrySerializeCode::StakePool => {
@@ -1198,6 +1058,7 @@ impl DeserializeFromSlice for Ledger {
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::multisig::DeclElement;
     use crate::testing::{ConfigBuilder, LedgerBuilder, StakePoolBuilder};
     use cardano_legacy_address::Addr;
     use chain_crypto::Blake2b256;
@@ -1320,14 +1181,45 @@ pub mod test {
 
         let vec = Vec::new();
         let mut codec = Codec::new(vec);
-        pack_last_rewards(&last_rewards, &mut codec).unwrap();
+        last_rewards.serialize(&mut codec).unwrap();
 
         let inner = codec.into_inner();
         let mut codec = Codec::new(inner.as_slice());
-        let deserialize_last_rewards = unpack_last_rewards(&mut codec).unwrap();
+        let deserialize_last_rewards = LastRewards::deserialize(&mut codec).unwrap();
         assert_eq!(last_rewards, deserialize_last_rewards);
     }
 
+    #[test]
+    pub fn derived_serialization_keeps_the_packed_layout() {
+        let last_rewards = LastRewards {
+            epoch: 0x0102_0304,
+            reward: Value(5),
+        };
+        assert_eq!(
+            last_rewards.serialize_as_vec().unwrap(),
+            [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 5]
+        );
+
+        let pool_last_rewards = PoolLastRewards {
+            epoch: 7,
+            value_taxed: Value(0x0a0b),
+            value_for_stakers: Value(1),
+        };
+        assert_eq!(
+            pool_last_rewards.serialize_as_vec().unwrap(),
+            [0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0x0a, 0x0b, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+
+        let block_date = BlockDate {
+            epoch: 2,
+            slot_id: 0x0100,
+        };
+        let bytes = block_date.serialize_as_vec().unwrap();
+        assert_eq!(bytes, [0, 0, 0, 2, 0, 0, 1, 0]);
+        let mut codec = Codec::new(bytes.as_slice());
+        assert_eq!(BlockDate::deserialize(&mut codec).unwrap(), block_date);
+    }
+
     #[test]
     pub fn pots_entry_pack_unpack_bijection() {
         for entry_value in [
@@ -1339,11 +1231,11 @@ pub mod test {
         {
             let vec = Vec::new();
             let mut codec = Codec::new(vec);
-            pack_pot_entry(entry_value, &mut codec).unwrap();
+            entry_value.serialize(&mut codec).unwrap();
 
             let inner = codec.into_inner();
             let mut codec = Codec::new(inner.as_slice());
-            let other_value = unpack_pot_entry(&mut codec).unwrap();
+            let other_value = pots::Entry::deserialize(&mut codec).unwrap();
             assert_eq!(entry_value, &other_value);
         }
     }
@@ -1377,11 +1269,11 @@ pub mod test {
         {
             let vec = Vec::new();
             let mut codec = Codec::new(vec);
-            pack_decl_element(decl_element, &mut codec).unwrap();
+            decl_element.serialize(&mut codec).unwrap();
 
             let inner = codec.into_inner();
             let mut codec = Codec::new(inner.as_slice());
-            let other_value = unpack_decl_element(&mut codec).unwrap();
+            let other_value = DeclElement::deserialize(&mut codec).unwrap();
             assert_eq!(decl_element, &other_value);
         }
     }
@@ -1394,11 +1286,11 @@ pub mod test {
             owners: Vec::new(),
             threshold: 0,
         };
-        pack_declaration(&declaration, &mut codec).unwrap();
+        declaration.serialize(&mut codec).unwrap();
 
         let inner = codec.into_inner();
         let mut codec = Codec::new(inner.as_slice());
-        let other_value = unpack_declaration(&mut codec).unwrap();
+        let other_value = Declaration::deserialize(&mut codec).unwrap();
         assert_eq!(declaration, other_value);
     }
 
@@ -1496,8 +1388,8 @@ pub mod test {
 
         fn blockdate_pack_unpack_bijection(block_date: BlockDate) -> TestResult {
             pack_unpack_bijection(
-                &|v, p| pack_block_date(*v, p),
-                &unpack_block_date,
+                &|v, p| v.serialize(p),
+                &|p| BlockDate::deserialize(p),
                 block_date
             )
         }
@@ -1560,8 +1452,8 @@ pub mod test {
 
         fn pool_last_rewards_pack_unpack_bijection(pool_last_rewards: PoolLastRewards) -> TestResult {
             pack_unpack_bijection(
-                &pack_pool_last_rewards,
-                &unpack_pool_last_rewards,
+                &|v, p| v.serialize(p),
+                &|p| PoolLastRewards::deserialize(p),
                 pool_last_rewards
             )
         }
@@ -1,5 +1,6 @@
 use crate::{account, key};
 use chain_crypto::{PublicKey, Signature};
+use chain_ser::{ChainDeserialize, ChainSerialize};
 
 use super::index::{Index, TreeIndex, LEVEL_MAXLIMIT};
 pub use crate::transaction::WitnessMultisigData;
@@ -53,7 +54,7 @@ impl std::fmt::Display for Identifier {
 ///
 /// * a threshold that need to be between 1 and the size of owners
 /// * a bunch of owners which is either a hash of a key, or a sub declaration
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, ChainSerialize, ChainDeserialize)]
 pub struct Declaration {
     pub(crate) threshold: u8, // between 1 and len(owners)
     pub(crate) owners: Vec<DeclElement>,
@@ -69,7 +70,7 @@ impl Declaration {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, ChainSerialize, ChainDeserialize)]
 pub enum DeclElement {
     Sub(Declaration),
     Owner(key::Hash),
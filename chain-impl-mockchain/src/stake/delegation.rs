@@ -1,6 +1,7 @@
 use crate::certificate::{PoolId, PoolRegistration, PoolRegistrationHash};
 use crate::date::Epoch;
 use crate::value::Value;
+use chain_ser::{ChainDeserialize, ChainSerialize};
 use imhamt::Hamt;
 use std::collections::hash_map::DefaultHasher;
 use std::fmt::{self, Debug};
@@ -18,7 +19,7 @@ pub enum PoolError {
     NotFound(PoolId),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, ChainSerialize, ChainDeserialize)]
 pub struct PoolLastRewards {
     pub epoch: Epoch,
     pub value_taxed: Value,
@@ -102,7 +103,7 @@ impl PoolsState {
     }
 
     pub fn stake_pool_ids(&self) -> impl Iterator<Item = PoolId> + '_ {
-        self.stake_pools.iter().map(|(id, _)| id.clone())
+        self.stake_pools.keys().cloned()
     }
 
     pub fn stake_pool_exists(&self, pool_id: &PoolId) -> bool {
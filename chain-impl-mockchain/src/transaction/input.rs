@@ -218,9 +218,9 @@ impl Serialize for Input {
 
 impl Deserialize for Input {
     fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
-        let index_or_account = codec.get_u8()?;
-        let value = Value::deserialize(codec)?;
-        let input_ptr = <[u8; INPUT_PTR_SIZE]>::deserialize(codec)?;
+        let index_or_account = codec.scoped("index_or_account", |codec| codec.get_u8())?;
+        let value = codec.scoped("value", Value::deserialize)?;
+        let input_ptr = codec.scoped("input_ptr", <[u8; INPUT_PTR_SIZE]>::deserialize)?;
         Ok(Input {
             index_or_account,
             value,
@@ -144,10 +144,11 @@ impl EncryptedVote {
     }
 
     pub(crate) fn serialize_in(&self, bb: ByteBuilder<Self>) -> ByteBuilder<Self> {
-        bb.iter8(&self.0, |bb, ct| {
-            let buffer = ct.to_bytes();
-            bb.bytes(&buffer)
-        })
+        bb.size_hint(1 + self.0.len() * Ciphertext::BYTES_LEN)
+            .iter8(&self.0, |bb, ct| {
+                let buffer = ct.to_bytes();
+                bb.bytes(&buffer)
+            })
     }
 
     pub fn serialize(&self) -> ByteArray<Self> {
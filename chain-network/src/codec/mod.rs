@@ -0,0 +1,8 @@
+//! Protobuf encoding of the protocol messages, shared by the gRPC mapping
+//! and the byte-stream transports.
+
+pub(crate) mod proto;
+
+mod convert;
+
+pub(crate) use convert::{ids_into_repeated_bytes, FromProtobuf, IntoProtobuf};
@@ -1,4 +1,4 @@
-// landing for gRPC code generated by tonic-build
+// landing for code generated by tonic-build: the protobuf messages and the gRPC services
 
 pub(crate) mod types {
     tonic::include_proto!("iohk.chain.types");
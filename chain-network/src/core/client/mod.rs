@@ -0,0 +1,5 @@
+#[cfg(feature = "retry")]
+mod retry;
+
+#[cfg(feature = "retry")]
+pub use retry::{Backoff, RetryPolicy};
@@ -0,0 +1,282 @@
+use crate::error::{Code, Error};
+use futures::prelude::*;
+use rand::Rng;
+
+use std::time::Duration;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+const DEFAULT_MULTIPLIER: u32 = 2;
+
+/// Policy for retrying failed client requests with jittered
+/// exponential backoff.
+///
+/// The policy applies to any asynchronous operation resolving to
+/// `Result<T, Error>`, so the same policy can be used for unary requests
+/// and for re-establishing subscription streams:
+///
+/// ```ignore
+/// let policy = RetryPolicy::new();
+/// let subscription = policy
+///     .retry(|| {
+///         let mut client = client.clone();
+///         let outbound = make_outbound_stream();
+///         async move { client.block_subscription(outbound).await }
+///     })
+///     .await?;
+/// ```
+///
+/// The gRPC client applies a policy to all of its requests when
+/// wrapped in `grpc::client::RetryingClient`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: u32,
+    jitter: bool,
+    retryable: Vec<Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter: true,
+            retryable: vec![Code::Unavailable, Code::Aborted, Code::ResourceExhausted],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy with default settings: up to 5 attempts,
+    /// backoff starting at 100 ms and doubling up to 10 s, with full jitter.
+    /// Errors with codes `Unavailable`, `Aborted`, and `ResourceExhausted`
+    /// are retried.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    /// A value of 1 disables retries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is 0.
+    pub fn max_attempts(&mut self, attempts: u32) -> &mut Self {
+        assert!(attempts > 0, "at least one attempt must be made");
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_backoff(&mut self, delay: Duration) -> &mut Self {
+        self.initial_backoff = delay;
+        self
+    }
+
+    /// Sets the upper bound on the delay between attempts.
+    pub fn max_backoff(&mut self, delay: Duration) -> &mut Self {
+        self.max_backoff = delay;
+        self
+    }
+
+    /// Sets the factor by which the delay grows after each failed attempt.
+    pub fn multiplier(&mut self, multiplier: u32) -> &mut Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Enables or disables jitter. With jitter enabled, each delay is
+    /// picked uniformly at random between zero and the computed
+    /// exponential delay, so that clients failing at the same time
+    /// do not retry in lockstep.
+    pub fn jitter(&mut self, enabled: bool) -> &mut Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Replaces the set of error codes that are considered transient
+    /// and warrant another attempt.
+    pub fn retry_on<I>(&mut self, codes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Code>,
+    {
+        self.retryable = codes.into_iter().collect();
+        self
+    }
+
+    /// Checks whether the error is classified as retryable by this policy.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        self.retryable.contains(&error.code())
+    }
+
+    /// Returns the sequence of delays to wait between attempts.
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            next_delay: self.initial_backoff,
+            max_delay: self.max_backoff,
+            multiplier: self.multiplier,
+            jitter: self.jitter,
+            remaining: self.max_attempts - 1,
+        }
+    }
+
+    /// Runs the operation produced by `op`, repeating it after a backoff
+    /// delay while it fails with a retryable error and the maximum number
+    /// of attempts has not been reached.
+    ///
+    /// The error of the last attempt is returned if all attempts fail,
+    /// or immediately if the error is not retryable.
+    pub async fn retry<F, Fut, T>(&self, op: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.retry_if(op, |e| self.is_retryable(e)).await
+    }
+
+    /// Like `retry`, for operations failing with other error types than
+    /// `Error`. The predicate `is_retryable` classifies the errors
+    /// instead of the error codes configured in the policy.
+    pub async fn retry_if<F, Fut, T, E, P>(&self, mut op: F, mut is_retryable: P) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        P: FnMut(&E) -> bool,
+    {
+        let mut backoff = self.backoff();
+        loop {
+            let e = match op().await {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+            if !is_retryable(&e) {
+                return Err(e);
+            }
+            match backoff.next() {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(e),
+            }
+        }
+    }
+}
+
+/// Iterator over the delays between successive attempts,
+/// as prescribed by a `RetryPolicy`.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    next_delay: Duration,
+    max_delay: Duration,
+    multiplier: u32,
+    jitter: bool,
+    remaining: u32,
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let delay = std::cmp::min(self.next_delay, self.max_delay);
+        self.next_delay = self
+            .next_delay
+            .checked_mul(self.multiplier)
+            .unwrap_or(self.max_delay);
+        if self.jitter {
+            Some(rand::thread_rng().gen_range(Duration::ZERO..=delay))
+        } else {
+            Some(delay)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.remaining as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Backoff {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let mut policy = RetryPolicy::new();
+        policy
+            .max_attempts(6)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .jitter(false);
+        let delays: Vec<_> = policy.backoff().map(|d| d.as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 500, 500]);
+    }
+
+    #[test]
+    fn jittered_backoff_stays_in_bounds() {
+        let mut policy = RetryPolicy::new();
+        policy.max_attempts(10).max_backoff(Duration::from_secs(1));
+        for delay in policy.backoff() {
+            assert!(delay <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn classifies_errors_by_code() {
+        let mut policy = RetryPolicy::new();
+        assert!(policy.is_retryable(&Error::new(Code::Unavailable, "down")));
+        assert!(!policy.is_retryable(&Error::new(Code::InvalidArgument, "bad")));
+        policy.retry_on([Code::Internal]);
+        assert!(!policy.is_retryable(&Error::new(Code::Unavailable, "down")));
+        assert!(policy.is_retryable(&Error::new(Code::Internal, "oops")));
+    }
+
+    #[tokio::test]
+    async fn retries_until_success_or_permanent_error() {
+        let mut policy = RetryPolicy::new();
+        policy.initial_backoff(Duration::ZERO);
+
+        let mut attempts = 0;
+        let res = policy
+            .retry(|| {
+                attempts += 1;
+                let res = if attempts < 3 {
+                    Err(Error::new(Code::Unavailable, "down"))
+                } else {
+                    Ok(attempts)
+                };
+                async move { res }
+            })
+            .await;
+        assert_eq!(res.unwrap(), 3);
+
+        let mut attempts = 0;
+        let res: Result<(), _> = policy
+            .retry(|| {
+                attempts += 1;
+                async { Err(Error::new(Code::InvalidArgument, "bad")) }
+            })
+            .await;
+        assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+        assert_eq!(attempts, 1);
+
+        let mut attempts = 0;
+        let res: Result<(), _> = policy
+            .retry(|| {
+                attempts += 1;
+                async { Err(Error::new(Code::Unavailable, "down")) }
+            })
+            .await;
+        assert_eq!(res.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(attempts, 5);
+    }
+}
@@ -3,11 +3,15 @@ mod fragment;
 mod gossip;
 mod node;
 mod push;
+mod relay;
 
 pub use block::BlockService;
 pub use fragment::FragmentService;
 pub use gossip::GossipService;
+pub use relay::RelayService;
 
 pub use node::Node;
 
-pub use push::PushStream;
+pub use push::{
+    outbound_queue, Closed, FlowControl, OutboundQueue, OutboundSender, PushStream, TrySendError,
+};
@@ -1,4 +1,4 @@
-use super::{BlockService, FragmentService, GossipService};
+use super::{BlockService, FragmentService, GossipService, RelayService};
 use crate::data::p2p::{AuthenticatedNodeId, Peer};
 use crate::data::HandshakeResponse;
 use crate::error::Error;
@@ -20,6 +20,9 @@ pub trait Node: Send + Sync + 'static {
     /// The implementation of the gossip service.
     type GossipService: GossipService + Send + Sync;
 
+    /// The implementation of the message relay service.
+    type RelayService: RelayService + Send + Sync;
+
     /// Implements node handshake. The server returns the ID of the genesis
     /// block and its own node ID, authenticated with the signature of `nonce`.
     async fn handshake(&self, peer: Peer, nonce: &[u8]) -> Result<HandshakeResponse, Error>;
@@ -38,4 +41,13 @@ pub trait Node: Send + Sync + 'static {
     /// Instantiates the gossip service,
     /// if supported by this node.
     fn gossip_service(&self) -> Option<&Self::GossipService>;
+
+    /// Instantiates the message relay service,
+    /// if supported by this node.
+    ///
+    /// The default implementation does not support message relay,
+    /// so that nodes not interested in it need not implement this method.
+    fn relay_service(&self) -> Option<&Self::RelayService> {
+        None
+    }
 }
@@ -1,4 +1,159 @@
 use crate::error::Error;
+use futures::channel::mpsc;
+use futures::prelude::*;
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 /// Type alias for inbound stream objects passed to the application.
 pub type PushStream<T> = futures::stream::BoxStream<'static, Result<T, Error>>;
+
+/// Default capacity of outbound subscription queues.
+pub const DEFAULT_QUEUE_SIZE: usize = 32;
+
+/// Flow control settings for the streams exchanged with a peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FlowControl {
+    /// Maximum number of items that can be queued in an outbound
+    /// subscription stream before the producer is made to wait
+    /// or is notified that the queue is full.
+    ///
+    /// The gRPC server reads the subscription streams returned by the
+    /// services at most this number of items ahead of the peer, so a peer
+    /// consuming items slowly holds back the service's stream rather than
+    /// making the server buffer items for it.
+    pub queue_size: usize,
+    /// Size of the receive window of the transport for each inbound stream,
+    /// in bytes. This bounds the amount of data the peer can send ahead of
+    /// the application consuming a `PushStream`. If `None`, the transport's
+    /// default is used.
+    pub stream_window: Option<u32>,
+}
+
+impl Default for FlowControl {
+    fn default() -> Self {
+        FlowControl {
+            queue_size: DEFAULT_QUEUE_SIZE,
+            stream_window: None,
+        }
+    }
+}
+
+/// Creates a bounded queue to feed an outbound subscription stream,
+/// with the capacity given by the flow control settings.
+///
+/// The receiving half can be returned by the subscription methods of the
+/// service traits. Producers sending through the `OutboundSender` half
+/// are made to wait when the peer consumes items slower than they are
+/// produced, instead of buffering without bound.
+pub fn outbound_queue<T>(flow: &FlowControl) -> (OutboundSender<T>, OutboundQueue<T>) {
+    let (tx, rx) = mpsc::channel(flow.queue_size);
+    (OutboundSender { inner: tx }, OutboundQueue { inner: rx })
+}
+
+/// The sending half of an outbound subscription queue.
+#[derive(Clone, Debug)]
+pub struct OutboundSender<T> {
+    inner: mpsc::Sender<Result<T, Error>>,
+}
+
+/// The error returned by `OutboundSender::try_send`,
+/// giving back the item that could not be sent.
+#[derive(Debug)]
+pub enum TrySendError<T> {
+    /// The queue is full: the peer is not keeping up with the stream.
+    Full(T),
+    /// The subscription stream has been closed.
+    Closed(T),
+}
+
+/// The error returned by `OutboundSender::send` when the subscription
+/// stream has been closed.
+#[derive(Debug, thiserror::Error)]
+#[error("the subscription stream has been closed")]
+pub struct Closed;
+
+impl<T> OutboundSender<T> {
+    /// Sends an item, waiting for space in the queue if it is full.
+    pub async fn send(&mut self, item: T) -> Result<(), Closed> {
+        self.inner.send(Ok(item)).await.map_err(|_| Closed)
+    }
+
+    /// Attempts to send an item without waiting.
+    pub fn try_send(&mut self, item: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(Ok(item)).map_err(|e| {
+            let full = e.is_full();
+            let item = match e.into_inner() {
+                Ok(item) => item,
+                Err(_) => unreachable!(),
+            };
+            if full {
+                TrySendError::Full(item)
+            } else {
+                TrySendError::Closed(item)
+            }
+        })
+    }
+
+    /// Terminates the subscription stream with a "slow down" signal,
+    /// telling the peer that it is overloading this node and should
+    /// back off before subscribing again.
+    pub async fn slow_down(self) -> Result<(), Closed> {
+        self.close_with_error(Error::slow_down()).await
+    }
+
+    /// Terminates the subscription stream with the given error.
+    pub async fn close_with_error(mut self, error: Error) -> Result<(), Closed> {
+        self.inner.send(Err(error)).await.map_err(|_| Closed)
+    }
+}
+
+/// The receiving half of an outbound subscription queue,
+/// to be returned as the subscription stream.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct OutboundQueue<T> {
+    inner: mpsc::Receiver<Result<T, Error>>,
+}
+
+impl<T> Stream for OutboundQueue<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Code;
+    use futures::executor::block_on;
+
+    #[test]
+    fn queue_is_bounded() {
+        let flow = FlowControl {
+            queue_size: 1,
+            ..Default::default()
+        };
+        let (mut tx, mut rx) = outbound_queue(&flow);
+        let mut sent = 0;
+        while tx.try_send(sent).is_ok() {
+            sent += 1;
+            assert!(sent < 10, "the queue should fill up");
+        }
+        assert!(matches!(tx.try_send(sent), Err(TrySendError::Full(_))));
+        let first = block_on(rx.next()).unwrap().unwrap();
+        assert_eq!(first, 0);
+        block_on(tx.slow_down()).unwrap();
+        let mut last = None;
+        while let Some(item) = block_on(rx.next()) {
+            last = Some(item);
+        }
+        assert_eq!(last.unwrap().unwrap_err().code(), Code::ResourceExhausted);
+    }
+}
@@ -0,0 +1,31 @@
+use super::PushStream;
+use crate::data::{Peer, RelayMessage, TopicId};
+use crate::error::Error;
+use async_trait::async_trait;
+use futures::stream::Stream;
+
+/// Interface for the blockchain node service implementation responsible for
+/// relaying opaque application messages scoped by topic.
+///
+/// The protocol does not interpret the message payloads; it is up to the
+/// implementation to validate messages, honor their time to live,
+/// and decide which subscribers to forward them to.
+#[async_trait]
+pub trait RelayService {
+    /// The type of outbound asynchronous streams returned by the
+    /// `relay_subscription` method.
+    type SubscriptionStream: Stream<Item = Result<RelayMessage, Error>> + Send + Sync;
+
+    /// Called by the protocol implementation to establish a
+    /// bidirectional subscription stream for the given topics.
+    /// The inbound stream is passed to the asynchronous method,
+    /// which resolves to the outbound stream.
+    /// The outbound stream should only carry messages on the
+    /// topics requested by the subscriber.
+    async fn relay_subscription(
+        &self,
+        subscriber: Peer,
+        topics: Box<[TopicId]>,
+        stream: PushStream<RelayMessage>,
+    ) -> Result<Self::SubscriptionStream, Error>;
+}
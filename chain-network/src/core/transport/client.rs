@@ -0,0 +1,411 @@
+use super::message::{read_reply, write_message};
+use super::{write_frame, Connection, FrameError, Method, DEFAULT_MAX_FRAME_SIZE};
+use crate::codec::{ids_into_repeated_bytes, proto, FromProtobuf, IntoProtobuf};
+use crate::data::block::{Block, BlockEvent, BlockId, BlockIds, Header};
+use crate::data::fragment::{Fragment, FragmentIds};
+use crate::data::p2p::{AuthenticatedNodeId, NodeId};
+use crate::data::relay::{self, RelayMessage, TopicId};
+use crate::data::{Gossip, HandshakeResponse};
+use crate::error::{Code, Error, HandshakeError};
+use crate::PROTOCOL_VERSION;
+use futures::future::BoxFuture;
+use futures::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use futures::prelude::*;
+use futures::stream::BoxStream;
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The stream of items received in response to a request.
+pub type ResponseStream<T> = BoxStream<'static, Result<T, Error>>;
+
+/// Client side of the node protocol over a connection of a `Transport`.
+///
+/// Every request opens a new stream on the connection, so requests
+/// can be made concurrently through a shared reference to the client.
+#[derive(Debug)]
+pub struct Client<C> {
+    conn: C,
+    max_frame_size: usize,
+}
+
+/// The inbound stream of a subscription.
+///
+/// The outbound stream passed to the subscription method is sent to the
+/// peer while this stream is polled. An error returned by the peer upon
+/// establishing the subscription is received as the first item.
+#[must_use = "streams do nothing unless polled"]
+pub struct Subscription<T> {
+    outbound: Option<BoxFuture<'static, Result<(), Error>>>,
+    inbound: ResponseStream<T>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(outbound) = &mut self.outbound {
+            if let Poll::Ready(res) = outbound.as_mut().poll(cx) {
+                self.outbound = None;
+                if let Err(e) = res {
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+        self.inbound.as_mut().poll_next(cx)
+    }
+}
+
+fn response_stream<R, P, T>(recv: R, max_frame_size: usize) -> ResponseStream<T>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+    P: prost::Message + Default + Send,
+    T: FromProtobuf<P> + Send + 'static,
+{
+    stream::unfold(Some(recv), move |recv| async move {
+        let mut recv = recv?;
+        match read_reply::<_, P>(&mut recv, max_frame_size).await {
+            Ok(Some(msg)) => Some((T::from_message(msg), Some(recv))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+    .boxed()
+}
+
+async fn send_stream<W, S>(mut send: W, stream: S) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    S: Stream,
+    S::Item: IntoProtobuf,
+    <S::Item as IntoProtobuf>::Message: prost::Message,
+{
+    futures::pin_mut!(stream);
+    while let Some(item) = stream.next().await {
+        write_message(&mut send, &item.into_message()).await?;
+    }
+    send.close().await.map_err(FrameError::from)?;
+    Ok(())
+}
+
+async fn send_request<W, M>(mut send: W, req: &M) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    M: prost::Message,
+{
+    write_message(&mut send, req).await?;
+    send.close().await.map_err(FrameError::from)?;
+    Ok(())
+}
+
+fn missing_response() -> Error {
+    Error::new(
+        Code::Unavailable,
+        "the stream was closed without a response",
+    )
+}
+
+impl<C> Client<C>
+where
+    C: Connection,
+{
+    pub fn new(conn: C) -> Self {
+        Client {
+            conn,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Sets the limit on the size of message frames received from the peer.
+    pub fn max_frame_size(&mut self, size: usize) -> &mut Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    pub fn connection(&self) -> &C {
+        &self.conn
+    }
+
+    async fn open(&self, method: Method) -> Result<(C::SendStream, C::RecvStream), Error> {
+        let (mut send, recv) = self.conn.open_stream().await?;
+        send.write_all(&[method.into()])
+            .await
+            .map_err(FrameError::from)?;
+        Ok((send, recv))
+    }
+
+    async fn unary<Req, Res>(&self, method: Method, req: Req) -> Result<Res, Error>
+    where
+        Req: prost::Message,
+        Res: prost::Message + Default,
+    {
+        let (send, mut recv) = self.open(method).await?;
+        let sent = send_request(send, &req).await;
+        match read_reply(&mut recv, self.max_frame_size).await? {
+            Some(res) => Ok(res),
+            None => Err(sent.err().unwrap_or_else(missing_response)),
+        }
+    }
+
+    async fn server_streaming<Req, P, T>(
+        &self,
+        method: Method,
+        req: Req,
+    ) -> Result<ResponseStream<T>, Error>
+    where
+        Req: prost::Message,
+        P: prost::Message + Default + Send + 'static,
+        T: FromProtobuf<P> + Send + 'static,
+    {
+        let (send, mut recv) = self.open(method).await?;
+        let sent = send_request(send, &req).await;
+        // Resolve to the error if the peer rejects the request outright
+        let first = match read_reply::<_, P>(&mut recv, self.max_frame_size).await? {
+            Some(msg) => T::from_message(msg),
+            None => {
+                sent?;
+                return Ok(stream::empty().boxed());
+            }
+        };
+        let rest = response_stream(recv, self.max_frame_size);
+        Ok(stream::once(future::ready(first)).chain(rest).boxed())
+    }
+
+    async fn client_streaming<S, Res>(&self, method: Method, stream: S) -> Result<(), Error>
+    where
+        S: Stream,
+        S::Item: IntoProtobuf,
+        <S::Item as IntoProtobuf>::Message: prost::Message,
+        Res: prost::Message + Default,
+    {
+        let (send, mut recv) = self.open(method).await?;
+        let sent = send_stream(send, stream).await;
+        // If the peer rejected the request, the error it sent back
+        // explains the failure better than the closed stream
+        let _: Res = read_reply(&mut recv, self.max_frame_size)
+            .await?
+            .ok_or_else(missing_response)?;
+        sent
+    }
+
+    async fn subscription<S, P, T>(
+        &self,
+        method: Method,
+        topics: Option<&[TopicId]>,
+        outbound: S,
+    ) -> Result<Subscription<T>, Error>
+    where
+        S: Stream + Send + 'static,
+        S::Item: IntoProtobuf,
+        <S::Item as IntoProtobuf>::Message: prost::Message + Send + Sync,
+        P: prost::Message + Default + Send,
+        T: FromProtobuf<P> + Send + 'static,
+    {
+        let (mut send, recv) = self.open(method).await?;
+        if let Some(topics) = topics {
+            write_frame(&mut send, &relay::encode_topics(topics)).await?;
+        }
+        Ok(Subscription {
+            outbound: Some(send_stream(send, outbound).boxed()),
+            inbound: response_stream(recv, self.max_frame_size),
+        })
+    }
+
+    /// Requests the identifier of the genesis block from the service node.
+    ///
+    /// This method should be called first after establishing the connection.
+    pub async fn handshake(&self, nonce: &[u8]) -> Result<HandshakeResponse, HandshakeError> {
+        let req = proto::node::HandshakeRequest {
+            nonce: nonce.into(),
+        };
+        let res: proto::node::HandshakeResponse = self
+            .unary(Method::Handshake, req)
+            .await
+            .map_err(HandshakeError::Rpc)?;
+        if res.version != PROTOCOL_VERSION {
+            return Err(HandshakeError::UnsupportedVersion(
+                res.version.to_string().into(),
+            ));
+        }
+        let block0_id =
+            BlockId::try_from(&res.block0[..]).map_err(HandshakeError::InvalidBlock0)?;
+        let node_id = NodeId::try_from(&res.node_id[..]).map_err(HandshakeError::InvalidNodeId)?;
+        let auth = node_id
+            .authenticated(&res.signature)
+            .map_err(HandshakeError::MalformedSignature)?;
+        Ok(HandshakeResponse {
+            block0_id,
+            auth,
+            nonce: res.nonce.into(),
+        })
+    }
+
+    pub async fn client_auth(&self, auth: AuthenticatedNodeId) -> Result<(), Error> {
+        let req = proto::node::ClientAuthRequest {
+            node_id: auth.id().as_bytes().into(),
+            signature: auth.signature().into(),
+        };
+        let proto::node::ClientAuthResponse {} = self.unary(Method::ClientAuth, req).await?;
+        Ok(())
+    }
+
+    /// One-off request for a list of peers known to the remote node.
+    pub async fn peers(&self, limit: u32) -> Result<Gossip, Error> {
+        let req = proto::node::PeersRequest { limit };
+        let res: proto::node::PeersResponse = self.unary(Method::Peers, req).await?;
+        Gossip::from_message(res)
+    }
+
+    /// Requests the header of the tip block in the node's chain.
+    pub async fn tip(&self) -> Result<Header, Error> {
+        let res: proto::node::TipResponse =
+            self.unary(Method::Tip, proto::node::TipRequest {}).await?;
+        Ok(Header::from_bytes(res.block_header))
+    }
+
+    /// Requests the identified blocks in a streamed response.
+    pub async fn get_blocks(&self, ids: BlockIds) -> Result<ResponseStream<Block>, Error> {
+        let req = proto::types::BlockIds {
+            ids: ids_into_repeated_bytes(ids.iter()),
+        };
+        self.server_streaming::<_, proto::types::Block, _>(Method::GetBlocks, req)
+            .await
+    }
+
+    /// Requests the headers of the identified blocks in a streamed response.
+    pub async fn get_headers(&self, ids: BlockIds) -> Result<ResponseStream<Header>, Error> {
+        let req = proto::types::BlockIds {
+            ids: ids_into_repeated_bytes(ids.iter()),
+        };
+        self.server_streaming::<_, proto::types::Header, _>(Method::GetHeaders, req)
+            .await
+    }
+
+    /// Requests the identified fragments in a streamed response.
+    pub async fn get_fragments(&self, ids: FragmentIds) -> Result<ResponseStream<Fragment>, Error> {
+        let req = proto::types::FragmentIds {
+            ids: ids_into_repeated_bytes(ids.iter()),
+        };
+        self.server_streaming::<_, proto::types::Fragment, _>(Method::GetFragments, req)
+            .await
+    }
+
+    /// Stream blocks from the provided range.
+    pub async fn pull_blocks(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<ResponseStream<Block>, Error> {
+        let req = proto::node::PullBlocksRequest {
+            from: ids_into_repeated_bytes(from.iter()),
+            to: to.as_bytes().into(),
+        };
+        self.server_streaming::<_, proto::types::Block, _>(Method::PullBlocks, req)
+            .await
+    }
+
+    /// Stream blocks from the first of the given starting points
+    /// that is found in the peer's chain, to the chain's tip.
+    pub async fn pull_blocks_to_tip(&self, from: BlockIds) -> Result<ResponseStream<Block>, Error> {
+        let req = proto::node::PullBlocksToTipRequest {
+            from: ids_into_repeated_bytes(from.iter()),
+        };
+        self.server_streaming::<_, proto::types::Block, _>(Method::PullBlocksToTip, req)
+            .await
+    }
+
+    /// Requests headers of blocks in the range between the latest of
+    /// the given starting points, and the given ending point.
+    pub async fn pull_headers(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<ResponseStream<Header>, Error> {
+        let req = proto::node::PullHeadersRequest {
+            from: ids_into_repeated_bytes(from.iter()),
+            to: to.as_bytes().into(),
+        };
+        self.server_streaming::<_, proto::types::Header, _>(Method::PullHeaders, req)
+            .await
+    }
+
+    /// The outbound counterpart of `pull_headers`, called in response to a
+    /// `BlockEvent::Missing` solicitation.
+    pub async fn push_headers<S>(&self, headers: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Header>,
+    {
+        self.client_streaming::<_, proto::node::PushHeadersResponse>(Method::PushHeaders, headers)
+            .await
+    }
+
+    /// Uploads blocks to the service in response to `BlockEvent::Solicit`.
+    pub async fn upload_blocks<S>(&self, blocks: S) -> Result<(), Error>
+    where
+        S: Stream<Item = Block>,
+    {
+        self.client_streaming::<_, proto::node::UploadBlocksResponse>(Method::UploadBlocks, blocks)
+            .await
+    }
+
+    /// Establishes a bidirectional stream of notifications for blocks
+    /// created or accepted by either of the peers.
+    pub async fn block_subscription<S>(
+        &self,
+        outbound: S,
+    ) -> Result<Subscription<BlockEvent>, Error>
+    where
+        S: Stream<Item = Header> + Send + 'static,
+    {
+        self.subscription::<_, proto::node::BlockEvent, _>(
+            Method::BlockSubscription,
+            None,
+            outbound,
+        )
+        .await
+    }
+
+    /// Establishes a bidirectional stream for exchanging fragments
+    /// created or accepted by either of the peers.
+    pub async fn fragment_subscription<S>(
+        &self,
+        outbound: S,
+    ) -> Result<Subscription<Fragment>, Error>
+    where
+        S: Stream<Item = Fragment> + Send + 'static,
+    {
+        self.subscription::<_, proto::types::Fragment, _>(
+            Method::FragmentSubscription,
+            None,
+            outbound,
+        )
+        .await
+    }
+
+    /// Establishes a bidirectional stream for exchanging network gossip.
+    pub async fn gossip_subscription<S>(&self, outbound: S) -> Result<Subscription<Gossip>, Error>
+    where
+        S: Stream<Item = Gossip> + Send + 'static,
+    {
+        self.subscription::<_, proto::node::Gossip, _>(Method::GossipSubscription, None, outbound)
+            .await
+    }
+
+    /// Establishes a bidirectional stream for exchanging opaque messages
+    /// on the given topics.
+    pub async fn relay_subscription<S>(
+        &self,
+        topics: &[TopicId],
+        outbound: S,
+    ) -> Result<Subscription<RelayMessage>, Error>
+    where
+        S: Stream<Item = RelayMessage> + Send + 'static,
+    {
+        self.subscription::<_, proto::node::RelayMessage, _>(
+            Method::RelaySubscription,
+            Some(topics),
+            outbound,
+        )
+        .await
+    }
+}
@@ -0,0 +1,103 @@
+use crate::error::{Code, Error};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use std::io;
+
+/// The default limit on the size of a single message frame.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Errors that can occur when reading or writing message frames.
+#[derive(Debug, thiserror::Error)]
+pub enum FrameError {
+    #[error("I/O error on the transport stream")]
+    Io(#[from] io::Error),
+    #[error("frame size {size} exceeds the limit of {max} bytes")]
+    TooLarge { size: usize, max: usize },
+}
+
+impl From<FrameError> for Error {
+    fn from(e: FrameError) -> Self {
+        let code = match e {
+            FrameError::Io(_) => Code::Unavailable,
+            FrameError::TooLarge { .. } => Code::InvalidArgument,
+        };
+        Error::new(code, e)
+    }
+}
+
+/// Writes a message frame: the length of `data` as a 32-bit big-endian
+/// integer, followed by the data bytes.
+pub async fn write_frame<W>(writer: &mut W, data: &[u8]) -> Result<(), FrameError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let len = u32::try_from(data.len()).map_err(|_| FrameError::TooLarge {
+        size: data.len(),
+        max: u32::MAX as usize,
+    })?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(data).await?;
+    Ok(())
+}
+
+/// Reads a message frame written by `write_frame`.
+///
+/// Resolves to `None` if the stream ends cleanly before the start of
+/// a frame. A frame longer than `max_size` is rejected before any of
+/// its content is read.
+pub async fn read_frame<R>(reader: &mut R, max_size: usize) -> Result<Option<Vec<u8>>, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len_buf = [0; 4];
+    let mut filled = 0;
+    while filled < len_buf.len() {
+        match reader.read(&mut len_buf[filled..]).await? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            n => filled += n,
+        }
+    }
+    let size = u32::from_be_bytes(len_buf) as usize;
+    if size > max_size {
+        return Err(FrameError::TooLarge {
+            size,
+            max: max_size,
+        });
+    }
+    let mut data = vec![0; size];
+    reader.read_exact(&mut data).await?;
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    #[test]
+    fn frames_round_trip() {
+        let mut buf = Cursor::new(Vec::new());
+        block_on(write_frame(&mut buf, b"hello")).unwrap();
+        block_on(write_frame(&mut buf, b"")).unwrap();
+        buf.set_position(0);
+        let frame = block_on(read_frame(&mut buf, DEFAULT_MAX_FRAME_SIZE)).unwrap();
+        assert_eq!(frame.as_deref(), Some(&b"hello"[..]));
+        let frame = block_on(read_frame(&mut buf, DEFAULT_MAX_FRAME_SIZE)).unwrap();
+        assert_eq!(frame.as_deref(), Some(&b""[..]));
+        let frame = block_on(read_frame(&mut buf, DEFAULT_MAX_FRAME_SIZE)).unwrap();
+        assert!(frame.is_none());
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let mut buf = Cursor::new(Vec::new());
+        block_on(write_frame(&mut buf, &[0; 100])).unwrap();
+        buf.set_position(0);
+        match block_on(read_frame(&mut buf, 99)) {
+            Err(FrameError::TooLarge { size: 100, max: 99 }) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::framing::{read_frame, write_frame};
+use crate::error::{Code, Error};
+use futures::io::{AsyncRead, AsyncWrite};
+
+const REPLY_MESSAGE: u8 = 0;
+const REPLY_ERROR: u8 = 1;
+
+fn code_to_u8(code: Code) -> u8 {
+    use Code::*;
+
+    match code {
+        Canceled => 1,
+        Unknown => 2,
+        InvalidArgument => 3,
+        NotFound => 5,
+        FailedPrecondition => 9,
+        Aborted => 10,
+        Unimplemented => 12,
+        Internal => 13,
+        Unavailable => 14,
+        ResourceExhausted => 8,
+        // When a new case has to be added here, remember to
+        // add the corresponding case in code_from_u8 below.
+    }
+}
+
+fn code_from_u8(code: u8) -> Code {
+    use Code::*;
+
+    match code {
+        1 => Canceled,
+        3 => InvalidArgument,
+        5 => NotFound,
+        8 => ResourceExhausted,
+        9 => FailedPrecondition,
+        10 => Aborted,
+        12 => Unimplemented,
+        13 => Internal,
+        14 => Unavailable,
+        _ => Unknown,
+    }
+}
+
+fn decode<M>(frame: &[u8]) -> Result<M, Error>
+where
+    M: prost::Message + Default,
+{
+    M::decode(frame).map_err(|e| Error::new(Code::InvalidArgument, e))
+}
+
+/// Writes a request message frame.
+pub(super) async fn write_message<W, M>(writer: &mut W, message: &M) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    M: prost::Message,
+{
+    write_frame(writer, &message.encode_to_vec()).await?;
+    Ok(())
+}
+
+/// Reads a request message frame. Resolves to `None` if the
+/// sending half of the stream has been closed.
+pub(super) async fn read_message<R, M>(reader: &mut R, max_size: usize) -> Result<Option<M>, Error>
+where
+    R: AsyncRead + Unpin,
+    M: prost::Message + Default,
+{
+    match read_frame(reader, max_size).await? {
+        Some(frame) => decode(&frame).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Writes a reply frame carrying a message.
+pub(super) async fn write_reply<W, M>(writer: &mut W, message: &M) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    M: prost::Message,
+{
+    let mut frame = Vec::with_capacity(1 + message.encoded_len());
+    frame.push(REPLY_MESSAGE);
+    message
+        .encode(&mut frame)
+        .expect("buffer has enough capacity");
+    write_frame(writer, &frame).await?;
+    Ok(())
+}
+
+/// Writes a reply frame carrying an error. No more replies follow it.
+pub(super) async fn write_error<W>(writer: &mut W, error: &Error) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut frame = vec![REPLY_ERROR, code_to_u8(error.code())];
+    frame.extend_from_slice(error.to_string().as_bytes());
+    write_frame(writer, &frame).await?;
+    Ok(())
+}
+
+/// Reads a reply frame, resolving to the error sent by the peer
+/// if the frame carries an error. Resolves to `None` if the sending
+/// half of the stream has been closed.
+pub(super) async fn read_reply<R, M>(reader: &mut R, max_size: usize) -> Result<Option<M>, Error>
+where
+    R: AsyncRead + Unpin,
+    M: prost::Message + Default,
+{
+    let frame = match read_frame(reader, max_size).await? {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    match frame.split_first() {
+        Some((&REPLY_MESSAGE, data)) => decode(data).map(Some),
+        Some((&REPLY_ERROR, [code, msg @ ..])) => Err(Error::new(
+            code_from_u8(*code),
+            String::from_utf8_lossy(msg).into_owned(),
+        )),
+        _ => Err(Error::new(Code::InvalidArgument, "malformed reply frame")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_round_trip() {
+        use Code::*;
+
+        for code in [
+            Canceled,
+            Unknown,
+            InvalidArgument,
+            NotFound,
+            FailedPrecondition,
+            Aborted,
+            Unimplemented,
+            Internal,
+            Unavailable,
+            ResourceExhausted,
+        ] {
+            assert_eq!(code_from_u8(code_to_u8(code)), code);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Transport abstraction for running the node protocol over multiplexed
+//! byte-stream connections, such as QUIC, as an alternative to the gRPC
+//! mapping in the `grpc` module.
+//!
+//! The protocol makes the following assumptions about the transport:
+//!
+//! * A connection between two peers carries any number of concurrent
+//!   bidirectional streams, which either peer can open.
+//! * Each stream delivers bytes reliably and in order. There is no
+//!   ordering guarantee between different streams, and a stalled stream
+//!   must not block progress on other streams of the same connection.
+//! * Every protocol request, including each subscription, occupies its own
+//!   stream. The opening side writes the [`Method`] code as the first byte,
+//!   followed by length-delimited message frames (see [`write_frame`] and
+//!   [`read_frame`]). Closing the sending half of a stream terminates the
+//!   message sequence in that direction.
+//! * The transport provides the remote peer address; peer authentication
+//!   is performed by the protocol handshake, not by the transport.
+//!
+//! Messages are encoded with the same protobuf definitions as in the gRPC
+//! mapping. The frames sent by the opening side carry the request messages;
+//! a relay subscription is preceded by a frame listing the topics. Each
+//! frame sent back by the serving side starts with a tag byte: 0 for a
+//! response message, or 1 for an error, followed by the error code byte
+//! and the UTF-8 description of the error. No frames follow an error.
+//!
+//! [`Server`] serves a `Node` implementation over the streams of a
+//! connection, and [`Client`] makes requests to such a server.
+
+mod client;
+mod framing;
+mod message;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+pub use client::{Client, ResponseStream, Subscription};
+pub use framing::{read_frame, write_frame, FrameError, DEFAULT_MAX_FRAME_SIZE};
+pub use server::Server;
+
+use crate::data::Peer;
+use crate::error::{Code, Error};
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncWrite};
+
+use std::net::SocketAddr;
+
+/// A multiplexed connection to a peer.
+#[async_trait]
+pub trait Connection: Send + Sync {
+    /// The sending half of a bidirectional stream.
+    type SendStream: AsyncWrite + Send + Unpin + 'static;
+
+    /// The receiving half of a bidirectional stream.
+    type RecvStream: AsyncRead + Send + Unpin + 'static;
+
+    /// Returns the remote peer of this connection.
+    fn peer(&self) -> Peer;
+
+    /// Opens a new bidirectional stream to the peer.
+    async fn open_stream(&self) -> Result<(Self::SendStream, Self::RecvStream), Error>;
+
+    /// Waits for the peer to open a bidirectional stream.
+    /// Resolves to `None` when the connection has been closed.
+    async fn accept_stream(&self) -> Result<Option<(Self::SendStream, Self::RecvStream)>, Error>;
+}
+
+/// Client side of a transport, establishing connections to peers.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// The type of connections established by this transport.
+    type Connection: Connection;
+
+    /// Connects to the peer listening on the given address.
+    async fn connect(&self, addr: SocketAddr) -> Result<Self::Connection, Error>;
+}
+
+/// Server side of a transport, accepting connections from peers.
+#[async_trait]
+pub trait Listener: Send + Sync {
+    /// The type of connections accepted by this listener.
+    type Connection: Connection;
+
+    /// Waits for an incoming connection.
+    async fn accept(&self) -> Result<Self::Connection, Error>;
+}
+
+/// Protocol methods, identified by the first byte written to a stream
+/// by the side that opened it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Method {
+    Handshake = 0,
+    ClientAuth = 1,
+    Tip = 2,
+    Peers = 3,
+    GetBlocks = 4,
+    GetHeaders = 5,
+    GetFragments = 6,
+    PullHeaders = 7,
+    PullBlocks = 8,
+    PullBlocksToTip = 9,
+    PushHeaders = 10,
+    UploadBlocks = 11,
+    BlockSubscription = 12,
+    FragmentSubscription = 13,
+    GossipSubscription = 14,
+    RelaySubscription = 15,
+}
+
+impl Method {
+    /// Returns true if the method establishes a long-lived subscription
+    /// with message sequences in both directions.
+    pub fn is_subscription(self) -> bool {
+        matches!(
+            self,
+            Method::BlockSubscription
+                | Method::FragmentSubscription
+                | Method::GossipSubscription
+                | Method::RelaySubscription
+        )
+    }
+}
+
+impl From<Method> for u8 {
+    #[inline]
+    fn from(method: Method) -> u8 {
+        method as u8
+    }
+}
+
+impl TryFrom<u8> for Method {
+    type Error = Error;
+
+    fn try_from(code: u8) -> Result<Self, Error> {
+        use Method::*;
+
+        let method = match code {
+            0 => Handshake,
+            1 => ClientAuth,
+            2 => Tip,
+            3 => Peers,
+            4 => GetBlocks,
+            5 => GetHeaders,
+            6 => GetFragments,
+            7 => PullHeaders,
+            8 => PullBlocks,
+            9 => PullBlocksToTip,
+            10 => PushHeaders,
+            11 => UploadBlocks,
+            12 => BlockSubscription,
+            13 => FragmentSubscription,
+            14 => GossipSubscription,
+            15 => RelaySubscription,
+            _ => {
+                return Err(Error::new(
+                    Code::Unimplemented,
+                    format!("unknown protocol method code {}", code),
+                ))
+            }
+        };
+        Ok(method)
+    }
+}
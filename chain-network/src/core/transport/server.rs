@@ -0,0 +1,282 @@
+use super::message::{read_message, write_error, write_reply};
+use super::{read_frame, Connection, FrameError, Method, DEFAULT_MAX_FRAME_SIZE};
+use crate::codec::{proto, FromProtobuf, IntoProtobuf};
+use crate::core::server::{
+    BlockService, FragmentService, GossipService, Node, PushStream, RelayService,
+};
+use crate::data::p2p::NodeId;
+use crate::data::{block, fragment, relay, BlockId, Peer};
+use crate::error::{Code, Error};
+use crate::PROTOCOL_VERSION;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures::prelude::*;
+
+/// Serves the node protocol to peers over connections of a `Transport`.
+///
+/// Each stream opened by the peer is dispatched on the `Method` code
+/// it starts with to the services of the `Node` implementation.
+#[derive(Debug)]
+pub struct Server<T> {
+    inner: T,
+    max_frame_size: usize,
+}
+
+impl<T> Server<T>
+where
+    T: Node,
+{
+    pub fn new(inner: T) -> Self {
+        Server {
+            inner,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Sets the limit on the size of message frames received from peers.
+    pub fn max_frame_size(&mut self, size: usize) -> &mut Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Serves the streams opened by the peer on the connection,
+    /// concurrently, until the connection is closed.
+    pub async fn serve_connection<C>(&self, conn: C) -> Result<(), Error>
+    where
+        C: Connection,
+    {
+        let peer = conn.peer();
+        let peer = &peer;
+        stream::unfold(&conn, |conn| async move {
+            match conn.accept_stream().await {
+                Ok(Some(stream)) => Some((Ok(stream), conn)),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), conn)),
+            }
+        })
+        .try_for_each_concurrent(None, |(send, recv)| async move {
+            // A failure on one stream does not affect the others
+            let _ = self.serve_stream(peer, send, recv).await;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn serve_stream<W, R>(&self, peer: &Peer, mut send: W, mut recv: R) -> Result<(), Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let mut code = [0; 1];
+        recv.read_exact(&mut code).await.map_err(FrameError::from)?;
+        let res = match Method::try_from(code[0]) {
+            Ok(method) => self.dispatch(method, peer, &mut send, recv).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            write_error(&mut send, &e).await?;
+        }
+        send.close().await.map_err(FrameError::from)?;
+        Ok(())
+    }
+
+    fn block_service(&self) -> Result<&T::BlockService, Error> {
+        self.inner
+            .block_service()
+            .ok_or_else(|| Error::new(Code::Unimplemented, "not implemented"))
+    }
+
+    fn fragment_service(&self) -> Result<&T::FragmentService, Error> {
+        self.inner
+            .fragment_service()
+            .ok_or_else(|| Error::new(Code::Unimplemented, "not implemented"))
+    }
+
+    fn gossip_service(&self) -> Result<&T::GossipService, Error> {
+        self.inner
+            .gossip_service()
+            .ok_or_else(|| Error::new(Code::Unimplemented, "not implemented"))
+    }
+
+    fn relay_service(&self) -> Result<&T::RelayService, Error> {
+        self.inner
+            .relay_service()
+            .ok_or_else(|| Error::new(Code::Unimplemented, "not implemented"))
+    }
+
+    async fn read_request<R, M>(&self, recv: &mut R) -> Result<M, Error>
+    where
+        R: AsyncRead + Unpin,
+        M: prost::Message + Default,
+    {
+        read_message(recv, self.max_frame_size)
+            .await?
+            .ok_or_else(|| Error::new(Code::InvalidArgument, "the request message is missing"))
+    }
+
+    fn push_stream<R, P, U>(&self, recv: R) -> PushStream<U>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        P: prost::Message + Default + Send,
+        U: FromProtobuf<P> + Send + 'static,
+    {
+        let max_frame_size = self.max_frame_size;
+        stream::unfold(Some(recv), move |recv| async move {
+            let mut recv = recv?;
+            match read_message::<_, P>(&mut recv, max_frame_size).await {
+                Ok(Some(msg)) => Some((U::from_message(msg), Some(recv))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+        .boxed()
+    }
+
+    async fn dispatch<W, R>(
+        &self,
+        method: Method,
+        peer: &Peer,
+        send: &mut W,
+        mut recv: R,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + Send + Unpin,
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        match method {
+            Method::Handshake => {
+                let req: proto::node::HandshakeRequest = self.read_request(&mut recv).await?;
+                let hr = self.inner.handshake(peer.clone(), &req.nonce).await?;
+                let res = proto::node::HandshakeResponse {
+                    version: PROTOCOL_VERSION,
+                    block0: hr.block0_id.as_bytes().into(),
+                    node_id: hr.auth.id().as_bytes().into(),
+                    signature: hr.auth.signature().into(),
+                    nonce: hr.nonce.into(),
+                };
+                write_reply(send, &res).await
+            }
+            Method::ClientAuth => {
+                let req: proto::node::ClientAuthRequest = self.read_request(&mut recv).await?;
+                let node_id = NodeId::try_from(&req.node_id[..])?;
+                let auth = node_id.authenticated(&req.signature)?;
+                self.inner.client_auth(peer.clone(), auth).await?;
+                write_reply(send, &proto::node::ClientAuthResponse {}).await
+            }
+            Method::Tip => {
+                let proto::node::TipRequest {} = self.read_request(&mut recv).await?;
+                let header = self.block_service()?.tip().await?;
+                let res = proto::node::TipResponse {
+                    block_header: header.into(),
+                };
+                write_reply(send, &res).await
+            }
+            Method::Peers => {
+                let req: proto::node::PeersRequest = self.read_request(&mut recv).await?;
+                let peers = self.gossip_service()?.peers(req.limit).await?;
+                let res = proto::node::PeersResponse {
+                    peers: peers
+                        .nodes
+                        .into_vec()
+                        .into_iter()
+                        .map(|node| node.into_bytes())
+                        .collect(),
+                };
+                write_reply(send, &res).await
+            }
+            Method::GetBlocks => {
+                let req: proto::types::BlockIds = self.read_request(&mut recv).await?;
+                let ids = block::try_ids_from_iter(req.ids)?;
+                let stream = self.block_service()?.get_blocks(ids).await?;
+                write_stream(send, stream).await
+            }
+            Method::GetHeaders => {
+                let req: proto::types::BlockIds = self.read_request(&mut recv).await?;
+                let ids = block::try_ids_from_iter(req.ids)?;
+                let stream = self.block_service()?.get_headers(ids).await?;
+                write_stream(send, stream).await
+            }
+            Method::GetFragments => {
+                let req: proto::types::FragmentIds = self.read_request(&mut recv).await?;
+                let ids = fragment::try_ids_from_iter(req.ids)?;
+                let stream = self.fragment_service()?.get_fragments(ids).await?;
+                write_stream(send, stream).await
+            }
+            Method::PullHeaders => {
+                let req: proto::node::PullHeadersRequest = self.read_request(&mut recv).await?;
+                let from = block::try_ids_from_iter(req.from)?;
+                let to = BlockId::try_from(&req.to[..])?;
+                let stream = self.block_service()?.pull_headers(from, to).await?;
+                write_stream(send, stream).await
+            }
+            Method::PullBlocks => {
+                let req: proto::node::PullBlocksRequest = self.read_request(&mut recv).await?;
+                let from = block::try_ids_from_iter(req.from)?;
+                let to = BlockId::try_from(&req.to[..])?;
+                let stream = self.block_service()?.pull_blocks(from, to).await?;
+                write_stream(send, stream).await
+            }
+            Method::PullBlocksToTip => {
+                let req: proto::node::PullBlocksToTipRequest = self.read_request(&mut recv).await?;
+                let from = block::try_ids_from_iter(req.from)?;
+                let stream = self.block_service()?.pull_blocks_to_tip(from).await?;
+                write_stream(send, stream).await
+            }
+            Method::PushHeaders => {
+                let service = self.block_service()?;
+                let stream = self.push_stream::<_, proto::types::Header, _>(recv);
+                service.push_headers(stream).await?;
+                write_reply(send, &proto::node::PushHeadersResponse {}).await
+            }
+            Method::UploadBlocks => {
+                let service = self.block_service()?;
+                let stream = self.push_stream::<_, proto::types::Block, _>(recv);
+                service.upload_blocks(stream).await?;
+                write_reply(send, &proto::node::UploadBlocksResponse {}).await
+            }
+            Method::BlockSubscription => {
+                let service = self.block_service()?;
+                let inbound = self.push_stream::<_, proto::types::Header, _>(recv);
+                let outbound = service.block_subscription(peer.clone(), inbound).await?;
+                write_stream(send, outbound).await
+            }
+            Method::FragmentSubscription => {
+                let service = self.fragment_service()?;
+                let inbound = self.push_stream::<_, proto::types::Fragment, _>(recv);
+                let outbound = service.fragment_subscription(peer.clone(), inbound).await?;
+                write_stream(send, outbound).await
+            }
+            Method::GossipSubscription => {
+                let service = self.gossip_service()?;
+                let inbound = self.push_stream::<_, proto::node::Gossip, _>(recv);
+                let outbound = service.gossip_subscription(peer.clone(), inbound).await?;
+                write_stream(send, outbound).await
+            }
+            Method::RelaySubscription => {
+                let service = self.relay_service()?;
+                let topics = read_frame(&mut recv, self.max_frame_size)
+                    .await?
+                    .and_then(|bytes| relay::decode_topics(&bytes))
+                    .ok_or_else(|| Error::new(Code::InvalidArgument, "invalid relay topics"))?;
+                let inbound = self.push_stream::<_, proto::node::RelayMessage, _>(recv);
+                let outbound = service
+                    .relay_subscription(peer.clone(), topics, inbound)
+                    .await?;
+                write_stream(send, outbound).await
+            }
+        }
+    }
+}
+
+async fn write_stream<W, S, U>(send: &mut W, stream: S) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    S: Stream<Item = Result<U, Error>>,
+    U: IntoProtobuf,
+    U::Message: prost::Message,
+{
+    futures::pin_mut!(stream);
+    while let Some(item) = stream.next().await {
+        write_reply(send, &item?.into_message()).await?;
+    }
+    Ok(())
+}
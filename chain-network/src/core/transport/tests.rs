@@ -0,0 +1,316 @@
+use super::*;
+use crate::core::server::{
+    BlockService, FragmentService, GossipService, Node, PushStream, RelayService,
+};
+use crate::data::{
+    AuthenticatedNodeId, Block, BlockEvent, BlockId, BlockIds, Fragment, FragmentIds, Gossip,
+    HandshakeResponse, Header, RelayMessage, TopicId,
+};
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::lock::Mutex;
+use futures::prelude::*;
+use futures::stream::IntoAsyncRead;
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Writing half of an in-memory byte pipe.
+struct PipeWriter(mpsc::UnboundedSender<io::Result<Vec<u8>>>);
+
+type PipeReader = IntoAsyncRead<mpsc::UnboundedReceiver<io::Result<Vec<u8>>>>;
+
+fn pipe() -> (PipeWriter, PipeReader) {
+    let (tx, rx) = mpsc::unbounded();
+    (PipeWriter(tx), rx.into_async_read())
+}
+
+impl AsyncWrite for PipeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.0.unbounded_send(Ok(buf.to_vec())) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.close_channel();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// One end of an in-memory connection.
+struct MemoryConnection {
+    peer: Peer,
+    open: mpsc::UnboundedSender<(PipeWriter, PipeReader)>,
+    accept: Mutex<mpsc::UnboundedReceiver<(PipeWriter, PipeReader)>>,
+}
+
+fn connection_pair() -> (MemoryConnection, MemoryConnection) {
+    let (tx1, rx1) = mpsc::unbounded();
+    let (tx2, rx2) = mpsc::unbounded();
+    let peer = Peer::from(SocketAddr::from(([127, 0, 0, 1], 3000)));
+    let a = MemoryConnection {
+        peer: peer.clone(),
+        open: tx1,
+        accept: Mutex::new(rx2),
+    };
+    let b = MemoryConnection {
+        peer,
+        open: tx2,
+        accept: Mutex::new(rx1),
+    };
+    (a, b)
+}
+
+#[async_trait]
+impl Connection for MemoryConnection {
+    type SendStream = PipeWriter;
+    type RecvStream = PipeReader;
+
+    fn peer(&self) -> Peer {
+        self.peer.clone()
+    }
+
+    async fn open_stream(&self) -> Result<(PipeWriter, PipeReader), Error> {
+        let (local_send, remote_recv) = pipe();
+        let (remote_send, local_recv) = pipe();
+        self.open
+            .unbounded_send((remote_send, remote_recv))
+            .map_err(|_| Error::new(Code::Unavailable, "connection closed"))?;
+        Ok((local_send, local_recv))
+    }
+
+    async fn accept_stream(&self) -> Result<Option<(PipeWriter, PipeReader)>, Error> {
+        Ok(self.accept.lock().await.next().await)
+    }
+}
+
+type Items<T> = stream::Iter<std::vec::IntoIter<Result<T, Error>>>;
+
+#[derive(Default)]
+struct TestNode {
+    uploaded: Arc<std::sync::Mutex<Vec<Block>>>,
+}
+
+#[async_trait]
+impl Node for TestNode {
+    type BlockService = Self;
+    type FragmentService = Self;
+    type GossipService = Self;
+    type RelayService = Self;
+
+    async fn handshake(&self, _: Peer, _: &[u8]) -> Result<HandshakeResponse, Error> {
+        Err(Error::unimplemented())
+    }
+
+    async fn client_auth(&self, _: Peer, _: AuthenticatedNodeId) -> Result<(), Error> {
+        Err(Error::unimplemented())
+    }
+
+    fn block_service(&self) -> Option<&Self> {
+        Some(self)
+    }
+
+    fn fragment_service(&self) -> Option<&Self> {
+        Some(self)
+    }
+
+    fn gossip_service(&self) -> Option<&Self> {
+        None
+    }
+}
+
+#[async_trait]
+impl BlockService for TestNode {
+    async fn tip(&self) -> Result<Header, Error> {
+        Ok(Header::from_bytes(vec![1, 2, 3]))
+    }
+
+    type GetBlocksStream = Items<Block>;
+
+    async fn get_blocks(&self, ids: BlockIds) -> Result<Self::GetBlocksStream, Error> {
+        let blocks = ids
+            .iter()
+            .map(|id| Ok(Block::from_bytes(id.as_bytes())))
+            .collect::<Vec<_>>();
+        Ok(stream::iter(blocks))
+    }
+
+    type GetHeadersStream = Items<Header>;
+
+    async fn get_headers(&self, _: BlockIds) -> Result<Self::GetHeadersStream, Error> {
+        Err(Error::unimplemented())
+    }
+
+    type PullHeadersStream = Items<Header>;
+
+    async fn pull_headers(&self, _: BlockIds, _: BlockId) -> Result<Items<Header>, Error> {
+        Err(Error::unimplemented())
+    }
+
+    type PullBlocksStream = Items<Block>;
+
+    async fn pull_blocks(&self, _: BlockIds, _: BlockId) -> Result<Items<Block>, Error> {
+        Err(Error::unimplemented())
+    }
+
+    type PullBlocksToTipStream = Items<Block>;
+
+    async fn pull_blocks_to_tip(&self, _: BlockIds) -> Result<Items<Block>, Error> {
+        Err(Error::unimplemented())
+    }
+
+    async fn push_headers(&self, _: PushStream<Header>) -> Result<(), Error> {
+        Err(Error::unimplemented())
+    }
+
+    async fn upload_blocks(&self, stream: PushStream<Block>) -> Result<(), Error> {
+        let blocks: Vec<Block> = stream.try_collect().await?;
+        self.uploaded.lock().unwrap().extend(blocks);
+        Ok(())
+    }
+
+    type SubscriptionStream = Items<BlockEvent>;
+
+    async fn block_subscription(
+        &self,
+        _: Peer,
+        _: PushStream<Header>,
+    ) -> Result<Items<BlockEvent>, Error> {
+        Err(Error::unimplemented())
+    }
+}
+
+#[async_trait]
+impl FragmentService for TestNode {
+    type GetFragmentsStream = Items<Fragment>;
+
+    async fn get_fragments(&self, _: FragmentIds) -> Result<Items<Fragment>, Error> {
+        Err(Error::unimplemented())
+    }
+
+    type SubscriptionStream = Items<Fragment>;
+
+    // Echoes the fragments received from the subscriber
+    async fn fragment_subscription(
+        &self,
+        _: Peer,
+        stream: PushStream<Fragment>,
+    ) -> Result<Items<Fragment>, Error> {
+        let fragments: Vec<_> = stream.collect().await;
+        Ok(stream::iter(fragments))
+    }
+}
+
+#[async_trait]
+impl GossipService for TestNode {
+    async fn peers(&self, _: u32) -> Result<Gossip, Error> {
+        Err(Error::unimplemented())
+    }
+
+    type SubscriptionStream = Items<Gossip>;
+
+    async fn gossip_subscription(
+        &self,
+        _: Peer,
+        _: PushStream<Gossip>,
+    ) -> Result<Items<Gossip>, Error> {
+        Err(Error::unimplemented())
+    }
+}
+
+#[async_trait]
+impl RelayService for TestNode {
+    type SubscriptionStream = Items<RelayMessage>;
+
+    async fn relay_subscription(
+        &self,
+        _: Peer,
+        _: Box<[TopicId]>,
+        _: PushStream<RelayMessage>,
+    ) -> Result<Items<RelayMessage>, Error> {
+        Err(Error::unimplemented())
+    }
+}
+
+fn assert_code<T>(res: Result<T, Error>, code: Code) {
+    match res {
+        Ok(_) => panic!("expected an error with code {:?}", code),
+        Err(e) => assert_eq!(e.code(), code),
+    }
+}
+
+#[test]
+fn requests_over_memory_transport() {
+    let node = TestNode::default();
+    let uploaded = node.uploaded.clone();
+    let server = Server::new(node);
+    let (client_conn, server_conn) = connection_pair();
+    let client = Client::new(client_conn);
+
+    let requests = async move {
+        let tip = client.tip().await.unwrap();
+        assert_eq!(tip.as_bytes(), [1, 2, 3]);
+
+        let ids: BlockIds = vec![
+            BlockId::try_from(&[1; 32][..]).unwrap(),
+            BlockId::try_from(&[2; 32][..]).unwrap(),
+        ]
+        .into();
+        let blocks: Vec<Block> = client
+            .get_blocks(ids.clone())
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].as_bytes(), [1; 32]);
+        assert_eq!(blocks[1].as_bytes(), [2; 32]);
+
+        assert_code(client.get_headers(ids).await, Code::Unimplemented);
+        assert_code(client.peers(10).await, Code::Unimplemented);
+
+        let block = Block::from_bytes(vec![4, 5]);
+        client
+            .upload_blocks(stream::iter(vec![block]))
+            .await
+            .unwrap();
+
+        let fragments = vec![Fragment::from_bytes(vec![6]), Fragment::from_bytes(vec![7])];
+        let echoed: Vec<Fragment> = client
+            .fragment_subscription(stream::iter(fragments))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(echoed.len(), 2);
+        assert_eq!(echoed[1].as_bytes(), [7]);
+
+        let mut relay = client
+            .relay_subscription(&[TopicId::new(1)], stream::empty::<RelayMessage>())
+            .await
+            .unwrap();
+        assert_code(relay.next().await.unwrap(), Code::Unimplemented);
+        assert!(relay.next().await.is_none());
+    };
+
+    let (res, ()) = block_on(future::join(server.serve_connection(server_conn), requests));
+    res.unwrap();
+
+    let uploaded = uploaded.lock().unwrap();
+    assert_eq!(uploaded.len(), 1);
+    assert_eq!(uploaded[0].as_bytes(), [4, 5]);
+}
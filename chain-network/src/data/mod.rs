@@ -3,9 +3,11 @@ pub mod fragment;
 pub mod gossip;
 mod handshake;
 pub mod p2p;
+pub mod relay;
 
 pub use block::{Block, BlockEvent, BlockId, BlockIds, Header};
 pub use fragment::{Fragment, FragmentId, FragmentIds};
 pub use gossip::Gossip;
 pub use handshake::HandshakeResponse;
 pub use p2p::{AuthenticatedNodeId, NodeId, NodeKeyPair, Peer};
+pub use relay::{RelayMessage, TopicId};
@@ -0,0 +1,132 @@
+use std::fmt;
+
+/// Identifier of a relay topic.
+///
+/// Topics partition the opaque messages relayed between peers, so that
+/// applications can piggyback auxiliary gossip on the node protocol
+/// and peers subscribe only to the topics they are able to process.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TopicId(u32);
+
+impl TopicId {
+    #[inline]
+    pub const fn new(id: u32) -> Self {
+        TopicId(id)
+    }
+
+    #[inline]
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for TopicId {
+    #[inline]
+    fn from(id: u32) -> Self {
+        TopicId(id)
+    }
+}
+
+impl fmt::Display for TopicId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// An opaque message relayed between peers on a topic.
+///
+/// The payload is not interpreted by the protocol. The time to live
+/// is the number of further hops the message may be forwarded over.
+#[derive(Clone, Debug)]
+pub struct RelayMessage {
+    topic: TopicId,
+    payload: Box<[u8]>,
+    ttl: u32,
+}
+
+impl RelayMessage {
+    pub fn new<B: Into<Box<[u8]>>>(topic: TopicId, payload: B, ttl: u32) -> Self {
+        RelayMessage {
+            topic,
+            payload: payload.into(),
+            ttl,
+        }
+    }
+
+    #[inline]
+    pub fn topic(&self) -> TopicId {
+        self.topic
+    }
+
+    #[inline]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    #[inline]
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    #[inline]
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload.into()
+    }
+
+    /// Prepares the message to be forwarded to another peer,
+    /// decrementing its time to live.
+    /// Returns `None` if the message has expired and must not be relayed
+    /// further.
+    pub fn forwarded(self) -> Option<Self> {
+        if self.ttl == 0 {
+            return None;
+        }
+        Some(RelayMessage {
+            ttl: self.ttl - 1,
+            ..self
+        })
+    }
+}
+
+/// Key of the request metadata entry listing the subscribed topics.
+pub(crate) const TOPICS_METADATA_KEY: &str = "relay-topics-bin";
+
+/// Encodes a set of topics in the binary form used in subscription requests.
+pub(crate) fn encode_topics(topics: &[TopicId]) -> Vec<u8> {
+    topics.iter().flat_map(|t| t.0.to_be_bytes()).collect()
+}
+
+/// Decodes a set of topics from the binary form used in subscription requests.
+/// Returns `None` if the length of the input is not a multiple of
+/// the topic identifier size.
+pub(crate) fn decode_topics(bytes: &[u8]) -> Option<Box<[TopicId]>> {
+    if bytes.len() % 4 != 0 {
+        return None;
+    }
+    let topics = bytes
+        .chunks_exact(4)
+        .map(|chunk| TopicId(u32::from_be_bytes(chunk.try_into().unwrap())))
+        .collect();
+    Some(topics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarding_decrements_ttl() {
+        let msg = RelayMessage::new(TopicId::new(1), vec![0, 1, 2], 1);
+        let msg = msg.forwarded().unwrap();
+        assert_eq!(msg.ttl(), 0);
+        assert!(msg.forwarded().is_none());
+    }
+
+    #[test]
+    fn topics_round_trip() {
+        let topics = [TopicId::new(0), TopicId::new(42), TopicId::new(u32::MAX)];
+        let decoded = decode_topics(&encode_topics(&topics)).unwrap();
+        assert_eq!(&decoded[..], &topics[..]);
+        assert!(decode_topics(&[0, 1, 2]).is_none());
+    }
+}
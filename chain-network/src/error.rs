@@ -15,6 +15,8 @@ pub enum Code {
     Unimplemented,
     Internal,
     Unavailable,
+    /// The peer is overloaded and requests the client to slow down.
+    ResourceExhausted,
 }
 
 /// Represents errors that can be returned by the node protocol implementation.
@@ -39,6 +41,12 @@ impl Error {
         Error::new(Code::Unimplemented, "not yet implemented")
     }
 
+    /// Creates an error signaling the peer to slow down, because the
+    /// node cannot keep up with the rate of its requests or streamed items.
+    pub fn slow_down() -> Self {
+        Error::new(Code::ResourceExhausted, "the peer is sending too fast")
+    }
+
     pub fn code(&self) -> Code {
         self.code
     }
@@ -62,6 +70,7 @@ impl fmt::Display for Error {
             Code::Unimplemented => "not implemented",
             Code::Internal => "internal processing error",
             Code::Unavailable => "the service is unavailable",
+            Code::ResourceExhausted => "the peer is overloaded, slow down",
         };
         write!(f, "{} ({})", msg, self.source)
     }
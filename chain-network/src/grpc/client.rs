@@ -1,15 +1,19 @@
 use super::convert;
-use super::proto;
 use super::streaming::{InboundStream, OutboundStream};
 
 #[cfg(feature = "legacy")]
 use super::legacy;
 
+use crate::codec::{self, proto};
 use crate::data::block::{Block, BlockEvent, BlockId, BlockIds, Header};
 use crate::data::fragment::{Fragment, FragmentIds};
 use crate::data::p2p::{AuthenticatedNodeId, NodeId};
+use crate::data::relay::{self, RelayMessage, TopicId};
 use crate::data::{Gossip, HandshakeResponse};
 use crate::error::{Error, HandshakeError};
+
+#[cfg(feature = "retry")]
+use crate::core::client::RetryPolicy;
 use crate::PROTOCOL_VERSION;
 use futures::prelude::*;
 use http_body::Body;
@@ -17,7 +21,6 @@ use tonic::body::BoxBody;
 use tonic::client::GrpcService;
 use tonic::codegen::StdError;
 
-#[cfg(feature = "legacy")]
 use tonic::metadata::MetadataValue;
 
 #[cfg(feature = "transport")]
@@ -28,6 +31,7 @@ use tonic::transport;
 pub struct Builder {
     #[cfg(feature = "legacy")]
     legacy_node_id: Option<legacy::NodeId>,
+    stream_window: Option<u32>,
 }
 
 impl Builder {
@@ -35,9 +39,19 @@ impl Builder {
         Builder {
             #[cfg(feature = "legacy")]
             legacy_node_id: None,
+            stream_window: None,
         }
     }
 
+    /// Sets the size of the receive window for each stream of the
+    /// connection established by `connect`, in bytes. This bounds the
+    /// amount of data the server can send ahead of the client consuming
+    /// a response or subscription stream.
+    pub fn stream_window(&mut self, size: u32) -> &mut Self {
+        self.stream_window = Some(size);
+        self
+    }
+
     /// Make the client add "node-id-bin" metadata with the passed value
     /// into subscription requests, for backward compatibility with
     /// jormungandr versions prior to 0.9.
@@ -66,9 +80,12 @@ impl Builder {
         D: TryInto<transport::Endpoint>,
         D::Error: Into<StdError>,
     {
-        let inner = proto::node::node_client::NodeClient::connect(dst).await?;
+        let channel = transport::Endpoint::new(dst)?
+            .initial_stream_window_size(self.stream_window)
+            .connect()
+            .await?;
         Ok(Client {
-            inner,
+            inner: proto::node::node_client::NodeClient::new(channel),
             #[cfg(feature = "legacy")]
             legacy_node_id: self.legacy_node_id,
         })
@@ -91,6 +108,9 @@ pub type FragmentSubscription = InboundStream<proto::types::Fragment, Fragment>;
 /// The inbound subscription stream of P2P gossip.
 pub type GossipSubscription = InboundStream<proto::node::Gossip, Gossip>;
 
+/// The inbound subscription stream of relayed topic messages.
+pub type RelaySubscription = InboundStream<proto::node::RelayMessage, RelayMessage>;
+
 #[cfg(feature = "transport")]
 impl Client<transport::Channel> {
     pub async fn connect<D>(dst: D) -> Result<Self, transport::Error>
@@ -175,7 +195,7 @@ where
     /// modules. This request is typically used during bootstrap from
     /// a trusted peer.
     pub async fn peers(&mut self, limit: u32) -> Result<Gossip, Error> {
-        use crate::grpc::convert::FromProtobuf;
+        use crate::codec::FromProtobuf;
         let req = proto::node::PeersRequest { limit };
         let res = self.inner.peers(req).await?.into_inner();
         let peers = Gossip::from_message(res)?;
@@ -196,7 +216,7 @@ where
         ids: BlockIds,
     ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
         let ids = proto::types::BlockIds {
-            ids: convert::ids_into_repeated_bytes(ids.iter()),
+            ids: codec::ids_into_repeated_bytes(ids.iter()),
         };
         let stream = self.inner.get_blocks(ids).await?.into_inner();
         Ok(InboundStream::new(stream))
@@ -208,7 +228,7 @@ where
         ids: BlockIds,
     ) -> Result<InboundStream<proto::types::Header, Header>, Error> {
         let ids = proto::types::BlockIds {
-            ids: convert::ids_into_repeated_bytes(ids.iter()),
+            ids: codec::ids_into_repeated_bytes(ids.iter()),
         };
         let stream = self.inner.get_headers(ids).await?.into_inner();
         Ok(InboundStream::new(stream))
@@ -220,7 +240,7 @@ where
         ids: FragmentIds,
     ) -> Result<InboundStream<proto::types::Fragment, Fragment>, Error> {
         let ids = proto::types::FragmentIds {
-            ids: convert::ids_into_repeated_bytes(ids.into_vec()),
+            ids: codec::ids_into_repeated_bytes(ids.into_vec()),
         };
         let stream = self.inner.get_fragments(ids).await?.into_inner();
         Ok(InboundStream::new(stream))
@@ -233,7 +253,7 @@ where
         to: BlockId,
     ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
         let req = proto::node::PullBlocksRequest {
-            from: convert::ids_into_repeated_bytes(from.into_vec()),
+            from: codec::ids_into_repeated_bytes(from.into_vec()),
             to: to.as_ref().to_vec(),
         };
         let stream = self.inner.pull_blocks(req).await?.into_inner();
@@ -247,7 +267,7 @@ where
         from: BlockIds,
     ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
         let req = proto::node::PullBlocksToTipRequest {
-            from: convert::ids_into_repeated_bytes(from.into_vec()),
+            from: codec::ids_into_repeated_bytes(from.into_vec()),
         };
         let stream = self.inner.pull_blocks_to_tip(req).await?.into_inner();
         Ok(InboundStream::new(stream))
@@ -264,7 +284,7 @@ where
         to: BlockId,
     ) -> Result<InboundStream<proto::types::Header, Header>, Error> {
         let req = proto::node::PullHeadersRequest {
-            from: convert::ids_into_repeated_bytes(from.into_vec()),
+            from: codec::ids_into_repeated_bytes(from.into_vec()),
             to: to.as_bytes().into(),
         };
         let stream = self.inner.pull_headers(req).await?.into_inner();
@@ -341,4 +361,259 @@ where
         let inbound = self.inner.gossip_subscription(req).await?.into_inner();
         Ok(InboundStream::new(inbound))
     }
+
+    /// Establishes a bidirectional stream for exchanging opaque messages
+    /// on the given topics.
+    ///
+    /// The client can use the stream that the returned future resolves to
+    /// as a long-lived subscription handle.
+    pub async fn relay_subscription<S>(
+        &mut self,
+        topics: &[TopicId],
+        outbound: S,
+    ) -> Result<RelaySubscription, Error>
+    where
+        S: Stream<Item = RelayMessage> + Send + Sync + 'static,
+    {
+        let mut req = self.subscription_request(OutboundStream::new(outbound));
+        let val = MetadataValue::from_bytes(&relay::encode_topics(topics));
+        req.metadata_mut()
+            .insert_bin(relay::TOPICS_METADATA_KEY, val);
+        let inbound = self.inner.relay_subscription(req).await?.into_inner();
+        Ok(InboundStream::new(inbound))
+    }
+}
+
+/// A client applying a retry policy to its requests.
+///
+/// Unary requests and requests for response streams are repeated with
+/// the same parameters. Subscriptions are re-established with a new
+/// outbound stream obtained from the closure passed to the subscription
+/// method on every attempt. Requests uploading a stream to the peer are
+/// not retried; they can be made on the underlying client obtained with
+/// `client_mut`.
+#[cfg(feature = "retry")]
+#[derive(Clone)]
+pub struct RetryingClient<T> {
+    client: Client<T>,
+    policy: RetryPolicy,
+}
+
+#[cfg(feature = "retry")]
+impl<T> RetryingClient<T>
+where
+    T: GrpcService<BoxBody> + Clone,
+    T::ResponseBody: Send + Sync + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    pub fn new(client: Client<T>, policy: RetryPolicy) -> Self {
+        RetryingClient { client, policy }
+    }
+
+    /// Returns the retry policy applied by this client.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Returns the underlying client, for requests that are not retried.
+    pub fn client_mut(&mut self) -> &mut Client<T> {
+        &mut self.client
+    }
+
+    pub fn into_inner(self) -> Client<T> {
+        self.client
+    }
+
+    /// Performs the handshake, retrying if the request fails with
+    /// an error that is retryable according to the policy.
+    pub async fn handshake(&self, nonce: &[u8]) -> Result<HandshakeResponse, HandshakeError> {
+        self.policy
+            .retry_if(
+                || {
+                    let mut client = self.client.clone();
+                    async move { client.handshake(nonce).await }
+                },
+                |e| match e {
+                    HandshakeError::Rpc(e) => self.policy.is_retryable(e),
+                    _ => false,
+                },
+            )
+            .await
+    }
+
+    pub async fn peers(&self, limit: u32) -> Result<Gossip, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                async move { client.peers(limit).await }
+            })
+            .await
+    }
+
+    pub async fn tip(&self) -> Result<Header, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                async move { client.tip().await }
+            })
+            .await
+    }
+
+    pub async fn get_blocks(
+        &self,
+        ids: BlockIds,
+    ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let ids = ids.clone();
+                async move { client.get_blocks(ids).await }
+            })
+            .await
+    }
+
+    pub async fn get_headers(
+        &self,
+        ids: BlockIds,
+    ) -> Result<InboundStream<proto::types::Header, Header>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let ids = ids.clone();
+                async move { client.get_headers(ids).await }
+            })
+            .await
+    }
+
+    pub async fn get_fragments(
+        &self,
+        ids: FragmentIds,
+    ) -> Result<InboundStream<proto::types::Fragment, Fragment>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let ids = ids.clone();
+                async move { client.get_fragments(ids).await }
+            })
+            .await
+    }
+
+    pub async fn pull_blocks(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let from = from.clone();
+                async move { client.pull_blocks(from, to).await }
+            })
+            .await
+    }
+
+    pub async fn pull_blocks_to_tip(
+        &self,
+        from: BlockIds,
+    ) -> Result<InboundStream<proto::types::Block, Block>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let from = from.clone();
+                async move { client.pull_blocks_to_tip(from).await }
+            })
+            .await
+    }
+
+    pub async fn pull_headers(
+        &self,
+        from: BlockIds,
+        to: BlockId,
+    ) -> Result<InboundStream<proto::types::Header, Header>, Error> {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let from = from.clone();
+                async move { client.pull_headers(from, to).await }
+            })
+            .await
+    }
+
+    /// Establishes a block subscription, re-establishing it with a new
+    /// outbound stream produced by `outbound` if an attempt fails.
+    pub async fn block_subscription<F, S>(
+        &self,
+        mut outbound: F,
+    ) -> Result<BlockSubscription, Error>
+    where
+        F: FnMut() -> S,
+        S: Stream<Item = Header> + Send + Sync + 'static,
+    {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let outbound = outbound();
+                async move { client.block_subscription(outbound).await }
+            })
+            .await
+    }
+
+    /// Establishes a fragment subscription, re-establishing it with a new
+    /// outbound stream produced by `outbound` if an attempt fails.
+    pub async fn fragment_subscription<F, S>(
+        &self,
+        mut outbound: F,
+    ) -> Result<FragmentSubscription, Error>
+    where
+        F: FnMut() -> S,
+        S: Stream<Item = Fragment> + Send + Sync + 'static,
+    {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let outbound = outbound();
+                async move { client.fragment_subscription(outbound).await }
+            })
+            .await
+    }
+
+    /// Establishes a gossip subscription, re-establishing it with a new
+    /// outbound stream produced by `outbound` if an attempt fails.
+    pub async fn gossip_subscription<F, S>(
+        &self,
+        mut outbound: F,
+    ) -> Result<GossipSubscription, Error>
+    where
+        F: FnMut() -> S,
+        S: Stream<Item = Gossip> + Send + Sync + 'static,
+    {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let outbound = outbound();
+                async move { client.gossip_subscription(outbound).await }
+            })
+            .await
+    }
+
+    /// Establishes a relay subscription on the given topics, re-establishing
+    /// it with a new outbound stream produced by `outbound` if an attempt
+    /// fails.
+    pub async fn relay_subscription<F, S>(
+        &self,
+        topics: &[TopicId],
+        mut outbound: F,
+    ) -> Result<RelaySubscription, Error>
+    where
+        F: FnMut() -> S,
+        S: Stream<Item = RelayMessage> + Send + Sync + 'static,
+    {
+        self.policy
+            .retry(|| {
+                let mut client = self.client.clone();
+                let outbound = outbound();
+                async move { client.relay_subscription(topics, outbound).await }
+            })
+            .await
+    }
 }
@@ -1,5 +1,3 @@
-mod proto;
-
 pub mod client;
 pub mod server;
 
@@ -1,18 +1,25 @@
-use super::proto;
-use super::streaming::{InboundStream, OutboundTryStream};
+use super::streaming::{InboundStream, OutboundTryStream, ReadAhead};
 
 #[cfg(feature = "legacy")]
 use super::legacy;
 
-use crate::core::server::{BlockService, FragmentService, GossipService, Node};
+use crate::codec::proto;
+use crate::core::server::{
+    BlockService, FlowControl, FragmentService, GossipService, Node, RelayService,
+};
 use crate::data::p2p::NodeId;
-use crate::data::{block, fragment, BlockId, Peer};
+use crate::data::{block, fragment, relay, BlockId, Peer, TopicId};
+use crate::error::Error;
 use crate::PROTOCOL_VERSION;
+use futures::prelude::*;
 use tonic::{Code, Status};
 
 #[cfg(feature = "legacy")]
 use tonic::metadata::MetadataValue;
 
+#[cfg(feature = "transport")]
+use tonic::transport;
+
 use std::net::SocketAddr;
 
 pub type Server<T> = proto::node::node_server::NodeServer<NodeService<T>>;
@@ -22,6 +29,7 @@ pub type Server<T> = proto::node::node_server::NodeServer<NodeService<T>>;
 pub struct Builder {
     #[cfg(feature = "legacy")]
     legacy_node_id: Option<legacy::NodeId>,
+    flow_control: FlowControl,
 }
 
 impl Builder {
@@ -29,9 +37,23 @@ impl Builder {
         Builder {
             #[cfg(feature = "legacy")]
             legacy_node_id: None,
+            flow_control: FlowControl::default(),
         }
     }
 
+    /// Sets the flow control settings for the streams exchanged with peers.
+    pub fn flow_control(&mut self, flow: FlowControl) -> &mut Self {
+        self.flow_control = flow;
+        self
+    }
+
+    /// Applies the transport-level flow control settings to the
+    /// tonic server builder used to serve the node service.
+    #[cfg(feature = "transport")]
+    pub fn configure_transport(&self, server: transport::Server) -> transport::Server {
+        server.initial_stream_window_size(self.flow_control.stream_window)
+    }
+
     /// Make the server add "node-id-bin" metadata with the passed value
     /// into subscription responses, for backward compatibility with
     /// jormungandr versions prior to 0.9.
@@ -45,6 +67,7 @@ impl Builder {
         let service = NodeService {
             #[cfg(feature = "legacy")]
             legacy_node_id: self.legacy_node_id,
+            flow_control: self.flow_control,
             ..NodeService::new(inner)
         };
         Server::new(service)
@@ -56,6 +79,7 @@ pub struct NodeService<T> {
     inner: T,
     #[cfg(feature = "legacy")]
     legacy_node_id: Option<legacy::NodeId>,
+    flow_control: FlowControl,
 }
 
 impl<T> NodeService<T>
@@ -67,6 +91,7 @@ where
             inner,
             #[cfg(feature = "legacy")]
             legacy_node_id: None,
+            flow_control: FlowControl::default(),
         }
     }
 
@@ -88,9 +113,24 @@ where
             .ok_or_else(|| Status::new(Code::Unimplemented, "not implemented"))
     }
 
+    fn relay_service(&self) -> Result<&T::RelayService, Status> {
+        self.inner
+            .relay_service()
+            .ok_or_else(|| Status::new(Code::Unimplemented, "not implemented"))
+    }
+
+    /// Reads the outbound subscription stream ahead of the peer by at most
+    /// the queue size of the flow control settings.
     #[allow(unused_mut)]
     #[allow(clippy::let_and_return)]
-    fn subscription_response<S>(&self, outbound: S) -> tonic::Response<OutboundTryStream<S>> {
+    fn subscription_response<S, U>(
+        &self,
+        outbound: S,
+    ) -> tonic::Response<OutboundTryStream<ReadAhead<S>>>
+    where
+        S: Stream<Item = Result<U, Error>>,
+    {
+        let outbound = ReadAhead::new(outbound, self.flow_control.queue_size);
         let mut res = tonic::Response::new(OutboundTryStream::new(outbound));
         #[cfg(feature = "legacy")]
         if let Some(node_id) = self.legacy_node_id {
@@ -101,6 +141,19 @@ where
     }
 }
 
+fn relay_topics<T>(req: &tonic::Request<T>) -> Result<Box<[TopicId]>, Status> {
+    match req.metadata().get_bin(relay::TOPICS_METADATA_KEY) {
+        Some(val) => {
+            let bytes = val
+                .to_bytes()
+                .map_err(|_| Status::invalid_argument("invalid relay topics metadata"))?;
+            relay::decode_topics(&bytes)
+                .ok_or_else(|| Status::invalid_argument("invalid relay topics metadata"))
+        }
+        None => Err(Status::invalid_argument("relay topics are not specified")),
+    }
+}
+
 fn remote_addr_to_peer(maybe_addr: Option<SocketAddr>) -> Result<Peer, Status> {
     match maybe_addr {
         Some(addr) => Ok(addr.into()),
@@ -279,7 +332,7 @@ where
     }
 
     type BlockSubscriptionStream =
-        OutboundTryStream<<T::BlockService as BlockService>::SubscriptionStream>;
+        OutboundTryStream<ReadAhead<<T::BlockService as BlockService>::SubscriptionStream>>;
 
     async fn block_subscription(
         &self,
@@ -294,7 +347,7 @@ where
     }
 
     type FragmentSubscriptionStream =
-        OutboundTryStream<<T::FragmentService as FragmentService>::SubscriptionStream>;
+        OutboundTryStream<ReadAhead<<T::FragmentService as FragmentService>::SubscriptionStream>>;
 
     async fn fragment_subscription(
         &self,
@@ -311,7 +364,7 @@ where
     }
 
     type GossipSubscriptionStream =
-        OutboundTryStream<<T::GossipService as GossipService>::SubscriptionStream>;
+        OutboundTryStream<ReadAhead<<T::GossipService as GossipService>::SubscriptionStream>>;
 
     async fn gossip_subscription(
         &self,
@@ -324,4 +377,22 @@ where
         let res = self.subscription_response(outbound);
         Ok(res)
     }
+
+    type RelaySubscriptionStream =
+        OutboundTryStream<ReadAhead<<T::RelayService as RelayService>::SubscriptionStream>>;
+
+    async fn relay_subscription(
+        &self,
+        req: tonic::Request<tonic::Streaming<proto::node::RelayMessage>>,
+    ) -> Result<tonic::Response<Self::RelaySubscriptionStream>, tonic::Status> {
+        let service = self.relay_service()?;
+        let peer = remote_addr_to_peer(req.remote_addr())?;
+        let topics = relay_topics(&req)?;
+        let inbound = InboundStream::new(req.into_inner());
+        let outbound = service
+            .relay_subscription(peer, topics, Box::pin(inbound))
+            .await?;
+        let res = self.subscription_response(outbound);
+        Ok(res)
+    }
 }
@@ -1,5 +1,6 @@
+use crate::codec::FromProtobuf;
 use crate::error::Error;
-use crate::grpc::convert::{error_from_grpc, FromProtobuf};
+use crate::grpc::convert::error_from_grpc;
 use futures::prelude::*;
 use pin_project::pin_project;
 use tonic::Streaming;
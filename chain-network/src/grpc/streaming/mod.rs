@@ -2,4 +2,4 @@ mod inbound;
 mod outbound;
 
 pub use inbound::InboundStream;
-pub(super) use outbound::{OutboundStream, OutboundTryStream};
+pub(super) use outbound::{OutboundStream, OutboundTryStream, ReadAhead};
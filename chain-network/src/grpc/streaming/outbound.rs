@@ -1,9 +1,11 @@
+use crate::codec::IntoProtobuf;
 use crate::error::Error;
-use crate::grpc::convert::{error_into_grpc, IntoProtobuf};
+use crate::grpc::convert::error_into_grpc;
 use futures::prelude::*;
 use pin_project::pin_project;
 use tonic::Status;
 
+use std::collections::VecDeque;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -65,3 +67,144 @@ where
         })
     }
 }
+
+/// Reads a subscription stream ahead of the peer by up to `capacity` items.
+///
+/// The items are only read when this stream is polled, so nothing is left
+/// running when it is dropped along with the response. An error item
+/// terminates the stream.
+#[must_use = "streams do nothing unless polled"]
+#[pin_project]
+pub struct ReadAhead<S: Stream> {
+    #[pin]
+    inner: S,
+    buffer: VecDeque<S::Item>,
+    capacity: usize,
+    done: bool,
+}
+
+impl<S: Stream> ReadAhead<S> {
+    pub(crate) fn new(inner: S, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        ReadAhead {
+            inner,
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+            done: false,
+        }
+    }
+}
+
+impl<S, T> Stream for ReadAhead<S>
+where
+    S: Stream<Item = Result<T, Error>>,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        while !*this.done && this.buffer.len() < *this.capacity {
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    *this.done = item.is_err();
+                    this.buffer.push_back(item);
+                }
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => break,
+            }
+        }
+        match this.buffer.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if *this.done => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Code;
+    use futures::executor::block_on;
+    use futures::task::noop_waker;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Counts the items taken from the stream and the drops of the stream
+    struct Source {
+        items: Vec<Result<u32, Error>>,
+        taken: Arc<AtomicUsize>,
+        dropped: Arc<AtomicUsize>,
+    }
+
+    impl Stream for Source {
+        type Item = Result<u32, Error>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            if self.items.is_empty() {
+                // never ends, like an idle subscription
+                return Poll::Pending;
+            }
+            self.taken.fetch_add(1, Ordering::SeqCst);
+            Poll::Ready(Some(self.items.remove(0)))
+        }
+    }
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn source(items: Vec<Result<u32, Error>>) -> (Source, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let taken = Arc::new(AtomicUsize::new(0));
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let source = Source {
+            items,
+            taken: taken.clone(),
+            dropped: dropped.clone(),
+        };
+        (source, taken, dropped)
+    }
+
+    #[test]
+    fn reads_ahead_up_to_capacity() {
+        let (source, taken, _) = source((0..10).map(Ok).collect());
+        let mut stream = ReadAhead::new(source, 3);
+        assert_eq!(block_on(stream.next()).unwrap().unwrap(), 0);
+        assert_eq!(taken.load(Ordering::SeqCst), 3);
+        assert_eq!(block_on(stream.next()).unwrap().unwrap(), 1);
+        assert_eq!(taken.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn error_terminates_the_stream() {
+        let items = vec![Ok(0), Err(Error::slow_down()), Ok(1)];
+        let (source, taken, _) = source(items);
+        let stream = ReadAhead::new(source, 10);
+        let items: Vec<_> = block_on(stream.collect());
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1].as_ref().unwrap_err().code(),
+            Code::ResourceExhausted
+        );
+        assert_eq!(taken.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn dropping_the_stream_drops_an_idle_source() {
+        let (source, _, dropped) = source(vec![Ok(0)]);
+        let mut stream = Box::pin(ReadAhead::new(source, 2));
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(matches!(
+            stream.as_mut().poll_next(&mut cx),
+            Poll::Ready(Some(Ok(0)))
+        ));
+        assert!(stream.as_mut().poll_next(&mut cx).is_pending());
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        drop(stream);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+}
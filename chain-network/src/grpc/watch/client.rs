@@ -1,7 +1,6 @@
+use crate::codec::{self, proto};
 use crate::data::block::{Block, BlockId, Header};
 use crate::error::Error;
-use crate::grpc::convert;
-use crate::grpc::proto;
 use crate::grpc::streaming::InboundStream;
 
 use http_body::Body;
@@ -70,7 +69,7 @@ where
         from: impl IntoIterator<Item = &BlockId>,
     ) -> Result<SyncMultiverseStream, Error> {
         let req = proto::watch::SyncMultiverseRequest {
-            from: convert::ids_into_repeated_bytes(from),
+            from: codec::ids_into_repeated_bytes(from),
         };
         let stream = self.inner.sync_multiverse(req).await?.into_inner();
         Ok(InboundStream::new(stream))
@@ -1,6 +1,6 @@
+use crate::codec::proto;
 use crate::core::watch::server::Watch;
 use crate::data::block;
-use crate::grpc::proto;
 use crate::grpc::streaming::OutboundTryStream;
 
 pub type Server<T> = proto::watch::watch_server::WatchServer<WatchService<T>>;
@@ -1,5 +1,7 @@
 #![warn(clippy::all)]
 
+mod codec;
+
 pub mod core;
 pub mod data;
 pub mod error;
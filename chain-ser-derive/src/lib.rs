@@ -0,0 +1,491 @@
+//! Derive macros for the `Serialize` and `Deserialize` traits of `chain-ser`
+//!
+//! The derived implementations follow the conventions of the binary format
+//! used throughout the chain libraries:
+//!
+//! * the fields of a struct or an enum variant are written in declaration
+//!   order, each with its own `Serialize` implementation;
+//! * an enum value is prefixed with a tag byte identifying the variant.
+//!   The tag is the index of the variant unless set explicitly with
+//!   the `#[chain_ser(tag = N)]` attribute on the variant.
+//!
+//! Integers are written in big-endian and vectors are prefixed with their
+//! length as a big-endian `u64`, per the implementations of the traits
+//! for these types in `chain-ser`.
+//!
+//! `ChainCborSerialize` and `ChainCborDeserialize` derive the structured
+//! canonical CBOR encoding described in the `cbor` module of `chain-ser`,
+//! using the same variant tags.
+//!
+//! The generated code refers to the `chain_ser` crate, which must be a
+//! dependency of the crate using the derives.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam,
+    Generics, Lit, Meta, NestedMeta,
+};
+
+#[proc_macro_derive(ChainSerialize, attributes(chain_ser))]
+pub fn derive_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_serialize(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(ChainDeserialize, attributes(chain_ser))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_deserialize(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(ChainCborSerialize, attributes(chain_ser))]
+pub fn derive_cbor_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_cbor_serialize(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(ChainCborDeserialize, attributes(chain_ser))]
+pub fn derive_cbor_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_cbor_deserialize(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn add_trait_bounds(mut generics: Generics, bound: TokenStream2) -> Generics {
+    for param in &mut generics.params {
+        if let GenericParam::Type(ref mut type_param) = *param {
+            type_param.bounds.push(parse_quote!(#bound));
+        }
+    }
+    generics
+}
+
+/// Reads the tag of an enum variant from the `chain_ser(tag = N)` attribute.
+fn variant_tag(variant: &syn::Variant, index: usize) -> syn::Result<u8> {
+    for attr in &variant.attrs {
+        if !attr.path.is_ident("chain_ser") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected chain_ser(tag = N)")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("tag") => {
+                    return match nv.lit {
+                        Lit::Int(lit) => lit.base10_parse::<u8>(),
+                        lit => Err(syn::Error::new_spanned(lit, "the tag must be a u8 integer")),
+                    };
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "unsupported chain_ser attribute",
+                    ))
+                }
+            }
+        }
+    }
+    u8::try_from(index).map_err(|_| {
+        syn::Error::new_spanned(
+            &variant.ident,
+            "too many variants to assign tags automatically",
+        )
+    })
+}
+
+/// Returns the tags of all variants of the enum, checking they are unique.
+fn variant_tags(data: &syn::DataEnum) -> syn::Result<Vec<u8>> {
+    let mut tags = Vec::with_capacity(data.variants.len());
+    for (index, variant) in data.variants.iter().enumerate() {
+        let tag = variant_tag(variant, index)?;
+        if tags.contains(&tag) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("duplicate tag {}", tag),
+            ));
+        }
+        tags.push(tag);
+    }
+    Ok(tags)
+}
+
+/// Produces the bindings to destructure the fields of a struct or variant.
+fn field_bindings(fields: &Fields) -> (TokenStream2, Vec<syn::Ident>) {
+    match fields {
+        Fields::Named(named) => {
+            let names: Vec<_> = named
+                .named
+                .iter()
+                .map(|f| f.ident.clone().unwrap())
+                .collect();
+            (quote! { { #(#names),* } }, names)
+        }
+        Fields::Unnamed(unnamed) => {
+            let names: Vec<_> = (0..unnamed.unnamed.len())
+                .map(|i| format_ident!("field_{}", i))
+                .collect();
+            (quote! { ( #(#names),* ) }, names)
+        }
+        Fields::Unit => (quote! {}, Vec::new()),
+    }
+}
+
+fn expand_serialize(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(::chain_ser::deser::Serialize),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (serialize_body, size_body) = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, names) = field_bindings(&data.fields);
+            (
+                quote! {
+                    let #name #pattern = self;
+                    #(::chain_ser::deser::Serialize::serialize(#names, __chain_ser_codec)?;)*
+                    Ok(())
+                },
+                quote! {
+                    let #name #pattern = self;
+                    0 #(+ ::chain_ser::deser::Serialize::serialized_size(#names))*
+                },
+            )
+        }
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let mut serialize_arms = Vec::new();
+            let mut size_arms = Vec::new();
+            for (variant, tag) in data.variants.iter().zip(tags) {
+                let ident = &variant.ident;
+                let (pattern, names) = field_bindings(&variant.fields);
+                serialize_arms.push(quote! {
+                    #name::#ident #pattern => {
+                        __chain_ser_codec.put_u8(#tag)?;
+                        #(::chain_ser::deser::Serialize::serialize(#names, __chain_ser_codec)?;)*
+                    }
+                });
+                size_arms.push(quote! {
+                    #name::#ident #pattern => {
+                        1 #(+ ::chain_ser::deser::Serialize::serialized_size(#names))*
+                    }
+                });
+            }
+            (
+                quote! {
+                    match self {
+                        #(#serialize_arms)*
+                    }
+                    Ok(())
+                },
+                quote! {
+                    match self {
+                        #(#size_arms)*
+                    }
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ChainSerialize cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::chain_ser::deser::Serialize for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn serialize<W: ::std::io::Write>(
+                &self,
+                __chain_ser_codec: &mut ::chain_ser::packer::Codec<W>,
+            ) -> ::std::result::Result<(), ::chain_ser::deser::WriteError> {
+                #serialize_body
+            }
+
+            #[allow(unused_variables)]
+            fn serialized_size(&self) -> usize {
+                #size_body
+            }
+        }
+    })
+}
+
+/// Produces the expression constructing a struct or variant by reading
+/// its fields from the codec.
+fn construct(path: TokenStream2, fields: &Fields) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| f.ident.clone().unwrap());
+            quote! {
+                #path {
+                    #(#names: ::chain_ser::deser::Deserialize::deserialize(__chain_ser_codec)?,)*
+                }
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            let reads = unnamed.unnamed.iter().map(|_| {
+                quote! { ::chain_ser::deser::Deserialize::deserialize(__chain_ser_codec)? }
+            });
+            quote! { #path ( #(#reads),* ) }
+        }
+        Fields::Unit => path,
+    }
+}
+
+fn expand_deserialize(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(::chain_ser::deser::Deserialize),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let value = construct(quote!(#name), &data.fields);
+            quote! { Ok(#value) }
+        }
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let value = construct(quote!(#name::#ident), &variant.fields);
+                quote! { #tag => Ok(#value), }
+            });
+            quote! {
+                match __chain_ser_codec.get_u8()? {
+                    #(#arms)*
+                    tag => Err(::chain_ser::deser::ReadError::UnknownTag(tag as u32)),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ChainDeserialize cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::chain_ser::deser::Deserialize for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn deserialize<R: ::std::io::Read>(
+                __chain_ser_codec: &mut ::chain_ser::packer::Codec<R>,
+            ) -> ::std::result::Result<Self, ::chain_ser::deser::ReadError> {
+                #body
+            }
+        }
+    })
+}
+
+/// Returns the names of the fields as CBOR map keys, with the indices of
+/// the fields, sorted in the canonical order of the encoded keys.
+///
+/// Text keys with the same length have encodings of the same length,
+/// sharing the header, so the canonical order of the keys is the order
+/// by length, then bytewise.
+fn canonical_keys(named: &syn::FieldsNamed) -> Vec<(String, usize)> {
+    let mut keys: Vec<_> = named
+        .named
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.ident.as_ref().unwrap().unraw().to_string(), i))
+        .collect();
+    keys.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    keys
+}
+
+/// Produces the statements writing the bound fields of a struct or variant.
+fn cbor_write_fields(fields: &Fields, names: &[syn::Ident]) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let keys = canonical_keys(named);
+            let len = keys.len() as u64;
+            let writes = keys.iter().map(|(key, i)| {
+                let name = &names[*i];
+                quote! {
+                    __chain_ser_serializer.write_text(#key)?;
+                    ::chain_ser::cbor::CborSerialize::serialize_cbor(#name, __chain_ser_serializer)?;
+                }
+            });
+            quote! {
+                __chain_ser_serializer.write_map(::chain_ser::cbor::cbor_event::Len::Len(#len))?;
+                #(#writes)*
+            }
+        }
+        Fields::Unnamed(_) | Fields::Unit => {
+            let len = names.len() as u64;
+            quote! {
+                __chain_ser_serializer.write_array(::chain_ser::cbor::cbor_event::Len::Len(#len))?;
+                #(::chain_ser::cbor::CborSerialize::serialize_cbor(#names, __chain_ser_serializer)?;)*
+            }
+        }
+    }
+}
+
+fn expand_cbor_serialize(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(::chain_ser::cbor::CborSerialize),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, names) = field_bindings(&data.fields);
+            let writes = cbor_write_fields(&data.fields, &names);
+            quote! {
+                let #name #pattern = self;
+                #writes
+            }
+        }
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let (pattern, names) = field_bindings(&variant.fields);
+                let writes = cbor_write_fields(&variant.fields, &names);
+                quote! {
+                    #name::#ident #pattern => {
+                        __chain_ser_serializer.write_array(::chain_ser::cbor::cbor_event::Len::Len(2))?;
+                        __chain_ser_serializer.write_unsigned_integer(#tag as u64)?;
+                        #writes
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ChainCborSerialize cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::chain_ser::cbor::CborSerialize for #name #ty_generics #where_clause {
+            fn serialize_cbor<W: ::std::io::Write>(
+                &self,
+                __chain_ser_serializer: &mut ::chain_ser::cbor::cbor_event::se::Serializer<W>,
+            ) -> ::chain_ser::cbor::cbor_event::Result<()> {
+                #body
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Produces the expression constructing a struct or variant by reading
+/// its fields from the CBOR deserializer.
+fn cbor_construct(path: TokenStream2, fields: &Fields) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let keys = canonical_keys(named);
+            let len = keys.len() as u64;
+            let idents: Vec<_> = named
+                .named
+                .iter()
+                .map(|f| f.ident.clone().unwrap())
+                .collect();
+            let vars: Vec<_> = (0..idents.len())
+                .map(|i| format_ident!("__chain_ser_field_{}", i))
+                .collect();
+            let reads = keys.iter().map(|(key, i)| {
+                let var = &vars[*i];
+                quote! {
+                    ::chain_ser::cbor::expect_key(__chain_ser_raw, #key)?;
+                    let #var = ::chain_ser::cbor::CborDeserialize::deserialize_cbor(__chain_ser_raw)?;
+                }
+            });
+            quote! {{
+                ::chain_ser::cbor::expect_map(__chain_ser_raw, #len)?;
+                #(#reads)*
+                #path { #(#idents: #vars),* }
+            }}
+        }
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len() as u64;
+            let reads = unnamed.unnamed.iter().map(|_| {
+                quote! { ::chain_ser::cbor::CborDeserialize::deserialize_cbor(__chain_ser_raw)? }
+            });
+            quote! {{
+                ::chain_ser::cbor::expect_array(__chain_ser_raw, #len)?;
+                #path ( #(#reads),* )
+            }}
+        }
+        Fields::Unit => quote! {{
+            ::chain_ser::cbor::expect_array(__chain_ser_raw, 0)?;
+            #path
+        }},
+    }
+}
+
+fn expand_cbor_deserialize(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_trait_bounds(
+        input.generics.clone(),
+        quote!(::chain_ser::cbor::CborDeserialize),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let value = cbor_construct(quote!(#name), &data.fields);
+            quote! { Ok(#value) }
+        }
+        Data::Enum(data) => {
+            let tags = variant_tags(data)?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let value = cbor_construct(quote!(#name::#ident), &variant.fields);
+                quote! { #tag => Ok(#value), }
+            });
+            quote! {
+                ::chain_ser::cbor::expect_array(__chain_ser_raw, 2)?;
+                let tag: u8 = ::chain_ser::cbor::CborDeserialize::deserialize_cbor(__chain_ser_raw)?;
+                match tag {
+                    #(#arms)*
+                    tag => Err(::chain_ser::cbor::cbor_event::Error::CustomError(
+                        ::std::format!("unknown tag {}", tag),
+                    )),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ChainCborDeserialize cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::chain_ser::cbor::CborDeserialize for #name #ty_generics #where_clause {
+            fn deserialize_cbor<R: ::std::io::BufRead>(
+                __chain_ser_raw: &mut ::chain_ser::cbor::cbor_event::de::Deserializer<R>,
+            ) -> ::std::result::Result<Self, ::chain_ser::cbor::cbor_event::Error> {
+                #body
+            }
+        }
+    })
+}
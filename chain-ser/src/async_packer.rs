@@ -0,0 +1,229 @@
+//! Asynchronous counterpart of the `packer` module
+//!
+//! `AsyncCodec` reads and writes the same binary data format as `Codec`,
+//! but over `futures::io::AsyncRead` and `AsyncWrite` objects, so that
+//! network services can decode primitive fields as they arrive from the
+//! stream.
+//!
+//! Objects implementing `Deserialize` are not read through this module:
+//! their deserialization is synchronous, so decoding one would require
+//! buffering all of its bytes first. Types read from streams provide
+//! asynchronous decoders written in terms of the primitive accessors,
+//! buffering no more than a bounded, length-prefixed part at a time:
+//! e.g. a block in `chain-impl-mockchain` is read fragment by fragment.
+
+use crate::deser::{ReadError, Serialize, WriteError};
+use crate::packer::{check_len, encode_varint_u64, VarintDecoder, MAX_VARINT_U64_SIZE};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The structure to support asynchronous (de)serialization of the binary
+/// data format used by jormungandr.
+///
+/// ## Reading data
+///
+/// Values are read directly from the inner stream, without reading ahead
+/// past the end of the value.
+///
+/// ## Writing data
+///
+/// Data can be written into any `AsyncWrite` implementor. Call `flush`
+/// when done writing a message if the inner stream is buffered.
+pub struct AsyncCodec<I> {
+    inner: I,
+}
+
+impl<I> AsyncCodec<I> {
+    pub fn new(inner: I) -> Self {
+        AsyncCodec { inner }
+    }
+
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncCodec<R> {
+    #[inline]
+    pub async fn get_u8(&mut self) -> Result<u8, ReadError> {
+        let mut buf = [0u8; 1];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(buf[0])
+    }
+
+    #[inline]
+    pub async fn get_be_u16(&mut self) -> Result<u16, ReadError> {
+        let mut buf = [0u8; 2];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    #[inline]
+    pub async fn get_be_u32(&mut self) -> Result<u32, ReadError> {
+        let mut buf = [0u8; 4];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    #[inline]
+    pub async fn get_be_u64(&mut self) -> Result<u64, ReadError> {
+        let mut buf = [0u8; 8];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    #[inline]
+    pub async fn get_be_u128(&mut self) -> Result<u128, ReadError> {
+        let mut buf = [0u8; 16];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(u128::from_be_bytes(buf))
+    }
+
+    /// Reads an unsigned integer in the LEB128 variable-length encoding.
+    /// See `Codec::get_varint_u64`.
+    pub async fn get_varint_u64(&mut self) -> Result<u64, ReadError> {
+        let mut decoder = VarintDecoder::default();
+        loop {
+            if let Some(value) = decoder.push(self.get_u8().await?)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    #[inline]
+    pub async fn get_bytes(&mut self, n: usize) -> Result<Vec<u8>, ReadError> {
+        let mut buf = vec![0u8; n];
+        self.inner.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[inline]
+    pub async fn copy_to_slice(&mut self, slice: &mut [u8]) -> Result<(), ReadError> {
+        self.inner.read_exact(slice).await?;
+        Ok(())
+    }
+
+    /// Reads a byte vector prefixed by its length as a big-endian `u64`.
+    /// See `Codec::get_len_prefixed_bytes`.
+    pub async fn get_len_prefixed_bytes(&mut self, max: usize) -> Result<Vec<u8>, ReadError> {
+        let len = check_len(self.get_be_u64().await?, max)?;
+        self.get_bytes(len).await
+    }
+
+    /// Reads a byte vector prefixed by its length in the LEB128
+    /// variable-length encoding.
+    /// See `Codec::get_varint_len_prefixed_bytes`.
+    pub async fn get_varint_len_prefixed_bytes(
+        &mut self,
+        max: usize,
+    ) -> Result<Vec<u8>, ReadError> {
+        let len = check_len(self.get_varint_u64().await?, max)?;
+        self.get_bytes(len).await
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncCodec<W> {
+    #[inline]
+    pub async fn put_u8(&mut self, v: u8) -> Result<(), WriteError> {
+        self.inner.write_all(&[v]).await?;
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn put_be_u16(&mut self, v: u16) -> Result<(), WriteError> {
+        self.inner.write_all(&v.to_be_bytes()).await?;
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn put_be_u32(&mut self, v: u32) -> Result<(), WriteError> {
+        self.inner.write_all(&v.to_be_bytes()).await?;
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn put_be_u64(&mut self, v: u64) -> Result<(), WriteError> {
+        self.inner.write_all(&v.to_be_bytes()).await?;
+        Ok(())
+    }
+
+    #[inline]
+    pub async fn put_be_u128(&mut self, v: u128) -> Result<(), WriteError> {
+        self.inner.write_all(&v.to_be_bytes()).await?;
+        Ok(())
+    }
+
+    /// Writes an unsigned integer in the LEB128 variable-length encoding.
+    pub async fn put_varint_u64(&mut self, v: u64) -> Result<(), WriteError> {
+        let mut buf = [0u8; MAX_VARINT_U64_SIZE];
+        let len = encode_varint_u64(v, &mut buf);
+        self.put_bytes(&buf[..len]).await
+    }
+
+    #[inline]
+    pub async fn put_bytes(&mut self, v: &[u8]) -> Result<(), WriteError> {
+        self.inner.write_all(v).await?;
+        Ok(())
+    }
+
+    /// Writes a byte slice prefixed by its length as a big-endian `u64`.
+    pub async fn put_len_prefixed_bytes(&mut self, v: &[u8]) -> Result<(), WriteError> {
+        self.put_be_u64(v.len() as u64).await?;
+        self.put_bytes(v).await
+    }
+
+    /// Writes a byte slice prefixed by its length in the LEB128
+    /// variable-length encoding.
+    pub async fn put_varint_len_prefixed_bytes(&mut self, v: &[u8]) -> Result<(), WriteError> {
+        self.put_varint_u64(v.len() as u64).await?;
+        self.put_bytes(v).await
+    }
+
+    /// Serializes the object and writes the resulting bytes to the stream.
+    pub async fn put_object<T: Serialize>(&mut self, object: &T) -> Result<(), WriteError> {
+        let bytes = object.serialize_as_vec()?;
+        self.put_bytes(&bytes).await
+    }
+
+    /// Flushes the inner stream.
+    pub async fn flush(&mut self) -> Result<(), WriteError> {
+        self.inner.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    #[test]
+    fn compatible_with_sync_codec() {
+        let mut codec = AsyncCodec::new(Cursor::new(Vec::new()));
+        block_on(async {
+            codec.put_u8(1).await.unwrap();
+            codec.put_be_u32(0xdeadbeef).await.unwrap();
+            codec.put_varint_u64(300).await.unwrap();
+            codec.put_len_prefixed_bytes(b"abc").await.unwrap();
+            codec.put_object(&[7u8; 4]).await.unwrap();
+        });
+        let data = codec.into_inner().into_inner();
+
+        let mut sync_codec = crate::packer::Codec::new(data.as_slice());
+        assert_eq!(sync_codec.get_u8().unwrap(), 1);
+        assert_eq!(sync_codec.get_be_u32().unwrap(), 0xdeadbeef);
+        assert_eq!(sync_codec.get_varint_u64().unwrap(), 300);
+        assert_eq!(sync_codec.get_len_prefixed_bytes(3).unwrap(), b"abc");
+
+        let mut codec = AsyncCodec::new(data.as_slice());
+        block_on(async {
+            assert_eq!(codec.get_u8().await.unwrap(), 1);
+            assert_eq!(codec.get_be_u32().await.unwrap(), 0xdeadbeef);
+            assert_eq!(codec.get_varint_u64().await.unwrap(), 300);
+            assert_eq!(codec.get_len_prefixed_bytes(3).await.unwrap(), b"abc");
+            let mut object = [0; 4];
+            codec.copy_to_slice(&mut object).await.unwrap();
+            assert_eq!(object, [7; 4]);
+        });
+    }
+}
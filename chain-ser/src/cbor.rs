@@ -0,0 +1,450 @@
+//! Canonical CBOR encoding
+//!
+//! Types opt into a structured CBOR encoding by implementing
+//! `CborSerialize` and `CborDeserialize`, or by deriving them with
+//! `ChainCborSerialize` and `ChainCborDeserialize` when the `derive` feature
+//! is also enabled. The derived encoding exposes the fields to standard
+//! CBOR tools:
+//!
+//! * the fields of a struct or an enum variant with named fields are
+//!   written as a map from the field names to the values;
+//! * other fields are written as an array, in declaration order;
+//! * an enum value is written as an array of two elements: the tag of
+//!   the variant, per the `#[chain_ser(tag = N)]` attribute or the index
+//!   of the variant, and the fields of the variant.
+//!
+//! Integers are written as CBOR unsigned integers, byte arrays as byte
+//! strings and vectors as arrays. Values that should remain opaque can be
+//! embedded with the `CborBytes` wrapper, which writes the binary
+//! serialization of this crate as a byte string.
+//!
+//! The functions of this module produce and accept only the canonical form
+//! of CBOR as defined in RFC 7049, section 3.9, which is the form used by
+//! Cardano tooling: integers and lengths in their shortest encoding,
+//! definite lengths only, and map keys sorted by the length of their
+//! encoding first, then bytewise.
+
+use crate::deser::{DeserializeFromSlice, ReadError, Serialize, WriteError};
+use cbor_event::{de::Deserializer, se::Serializer, Len, Special};
+
+use std::io::{BufRead, Cursor, Write};
+
+// for the code generated by the derives
+pub use cbor_event;
+
+/// Structured encoding of a value in canonical CBOR.
+pub trait CborSerialize {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()>;
+}
+
+/// Decoding of a value from its structured CBOR encoding.
+pub trait CborDeserialize: Sized {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self>;
+}
+
+impl<T: CborSerialize + ?Sized> CborSerialize for &T {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        (**self).serialize_cbor(serializer)
+    }
+}
+
+macro_rules! cbor_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl CborSerialize for $ty {
+                fn serialize_cbor<W: Write>(
+                    &self,
+                    serializer: &mut Serializer<W>,
+                ) -> cbor_event::Result<()> {
+                    serializer.write_unsigned_integer(*self as u64)?;
+                    Ok(())
+                }
+            }
+
+            impl CborDeserialize for $ty {
+                fn deserialize_cbor<R: BufRead>(
+                    raw: &mut Deserializer<R>,
+                ) -> cbor_event::Result<Self> {
+                    let v = raw.unsigned_integer()?;
+                    <$ty>::try_from(v).map_err(|_| {
+                        cbor_event::Error::CustomError(format!(
+                            "integer {} out of range for {}",
+                            v,
+                            stringify!($ty)
+                        ))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+cbor_unsigned!(u8, u16, u32, u64);
+
+impl CborSerialize for bool {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        serializer.write_special(Special::Bool(*self))?;
+        Ok(())
+    }
+}
+
+impl CborDeserialize for bool {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.bool()
+    }
+}
+
+impl CborSerialize for String {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        serializer.write_text(self)?;
+        Ok(())
+    }
+}
+
+impl CborDeserialize for String {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.text()
+    }
+}
+
+impl<const N: usize> CborSerialize for [u8; N] {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        serializer.write_bytes(self)?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> CborDeserialize for [u8; N] {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        let bytes = raw.bytes()?;
+        <[u8; N]>::try_from(bytes.as_slice()).map_err(|_| {
+            cbor_event::Error::CustomError(format!(
+                "expected a byte string of length {}, got {}",
+                N,
+                bytes.len()
+            ))
+        })
+    }
+}
+
+impl<T: CborSerialize> CborSerialize for [T] {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        serializer.write_array(Len::Len(self.len() as u64))?;
+        for item in self {
+            item.serialize_cbor(serializer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: CborSerialize> CborSerialize for Vec<T> {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        self.as_slice().serialize_cbor(serializer)
+    }
+}
+
+impl<T: CborDeserialize> CborDeserialize for Vec<T> {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        let len = match raw.array()? {
+            Len::Len(len) => len,
+            Len::Indefinite => return Err(indefinite_length()),
+        };
+        // the length is not trusted to preallocate the vector
+        let mut v = Vec::new();
+        for _ in 0..len {
+            v.push(T::deserialize_cbor(raw)?);
+        }
+        Ok(v)
+    }
+}
+
+/// Wrapper embedding a value in CBOR as a byte string containing
+/// the binary serialization of the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CborBytes<T>(pub T);
+
+impl<T: Serialize> CborSerialize for CborBytes<T> {
+    fn serialize_cbor<W: Write>(&self, serializer: &mut Serializer<W>) -> cbor_event::Result<()> {
+        let bytes = self
+            .0
+            .serialize_as_vec()
+            .map_err(|e| cbor_event::Error::CustomError(e.to_string()))?;
+        serializer.write_bytes(&bytes)?;
+        Ok(())
+    }
+}
+
+impl<T: DeserializeFromSlice> CborDeserialize for CborBytes<T> {
+    fn deserialize_cbor<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        let bytes = raw.bytes()?;
+        T::deserialize_from_bytes(&bytes)
+            .map(CborBytes)
+            .map_err(|e| cbor_event::Error::CustomError(e.to_string()))
+    }
+}
+
+/// Reads the header of an array, which must have the given length.
+pub fn expect_array<R: BufRead>(raw: &mut Deserializer<R>, len: u64) -> cbor_event::Result<()> {
+    match raw.array()? {
+        Len::Len(n) if n == len => Ok(()),
+        Len::Len(n) => Err(cbor_event::Error::CustomError(format!(
+            "expected an array of length {}, got {}",
+            len, n
+        ))),
+        Len::Indefinite => Err(indefinite_length()),
+    }
+}
+
+/// Reads the header of a map, which must have the given number of entries.
+pub fn expect_map<R: BufRead>(raw: &mut Deserializer<R>, len: u64) -> cbor_event::Result<()> {
+    match raw.map()? {
+        Len::Len(n) if n == len => Ok(()),
+        Len::Len(n) => Err(cbor_event::Error::CustomError(format!(
+            "expected a map with {} entries, got {}",
+            len, n
+        ))),
+        Len::Indefinite => Err(indefinite_length()),
+    }
+}
+
+/// Reads a text map key, which must be equal to `key`.
+///
+/// Structured decoders read the keys in canonical order, so that any other
+/// key, including a known key out of order, is rejected.
+pub fn expect_key<R: BufRead>(raw: &mut Deserializer<R>, key: &str) -> cbor_event::Result<()> {
+    let got = raw.text()?;
+    if got == key {
+        Ok(())
+    } else {
+        Err(cbor_event::Error::CustomError(format!(
+            "expected map key \"{}\", got \"{}\"",
+            key, got
+        )))
+    }
+}
+
+fn indefinite_length() -> cbor_event::Error {
+    cbor_event::Error::CustomError("indefinite length in canonical CBOR".to_string())
+}
+
+/// Encodes the value in canonical CBOR.
+pub fn to_canonical_cbor<T>(value: &T) -> Result<Vec<u8>, WriteError>
+where
+    T: CborSerialize + ?Sized,
+{
+    let mut serializer = Serializer::new_vec();
+    value.serialize_cbor(&mut serializer).map_err(write_error)?;
+    Ok(serializer.finalize())
+}
+
+/// Decodes a value from a byte slice containing exactly one CBOR item
+/// in canonical form.
+///
+/// The canonical form is verified by encoding the decoded value again
+/// and comparing the result with the input, so any non-canonical
+/// encoding or trailing data is rejected.
+pub fn from_canonical_cbor<T>(bytes: &[u8]) -> Result<T, ReadError>
+where
+    T: CborDeserialize + CborSerialize,
+{
+    let mut raw = Deserializer::from(Cursor::new(bytes));
+    let value = T::deserialize_cbor(&mut raw).map_err(|e| ReadError::InvalidData(e.to_string()))?;
+    let canonical = to_canonical_cbor(&value)
+        .map_err(|e| ReadError::InvalidData(format!("cannot re-encode CBOR: {}", e)))?;
+    if canonical != bytes {
+        return Err(ReadError::StructureInvalid(
+            "CBOR input is not in canonical form".to_string(),
+        ));
+    }
+    Ok(value)
+}
+
+/// Writes a CBOR map with the entries sorted in canonical key order.
+///
+/// Fails if two keys have the same encoding.
+pub fn write_canonical_map<W, K, V, I>(
+    serializer: &mut Serializer<W>,
+    entries: I,
+) -> cbor_event::Result<()>
+where
+    W: Write,
+    K: CborSerialize,
+    V: CborSerialize,
+    I: IntoIterator<Item = (K, V)>,
+{
+    let mut encoded = entries
+        .into_iter()
+        .map(|(k, v)| {
+            let mut key = Serializer::new_vec();
+            k.serialize_cbor(&mut key)?;
+            let mut value = Serializer::new_vec();
+            v.serialize_cbor(&mut value)?;
+            Ok((key.finalize(), value.finalize()))
+        })
+        .collect::<cbor_event::Result<Vec<(Vec<u8>, Vec<u8>)>>>()?;
+    encoded.sort_by(|(a, _), (b, _)| canonical_key_order(a, b));
+    if encoded.windows(2).any(|w| w[0].0 == w[1].0) {
+        return Err(cbor_event::Error::CustomError(
+            "duplicate keys in CBOR map".to_string(),
+        ));
+    }
+    serializer.write_map(Len::Len(encoded.len() as u64))?;
+    for (key, value) in encoded {
+        serializer.write_raw_bytes(&key)?;
+        serializer.write_raw_bytes(&value)?;
+    }
+    Ok(())
+}
+
+/// Orders encoded map keys as required by the canonical CBOR form:
+/// shorter keys first, keys of the same length in bytewise order.
+pub fn canonical_key_order(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn write_error(e: cbor_event::Error) -> WriteError {
+    WriteError::IoError(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        e.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cbor_bytes_round_trip() {
+        let value = CborBytes([1u8, 2, 3]);
+        let bytes = to_canonical_cbor(&value).unwrap();
+        assert_eq!(bytes, [0x43, 1, 2, 3]);
+        let decoded: CborBytes<[u8; 3]> = from_canonical_cbor(&bytes).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn primitives_are_structured() {
+        let bytes = to_canonical_cbor(&vec![1u64, 500]).unwrap();
+        assert_eq!(bytes, [0x82, 0x01, 0x19, 0x01, 0xf4]);
+        assert_eq!(from_canonical_cbor::<Vec<u64>>(&bytes).unwrap(), [1, 500]);
+
+        let bytes = to_canonical_cbor(&[0xaau8; 2]).unwrap();
+        assert_eq!(bytes, [0x42, 0xaa, 0xaa]);
+        assert!(from_canonical_cbor::<[u8; 3]>(&bytes).is_err());
+
+        assert!(from_canonical_cbor::<u8>(&[0x19, 0x01, 0x00]).is_err());
+    }
+
+    #[test]
+    fn non_canonical_input_is_rejected() {
+        // byte string length encoded in an unnecessary extra byte
+        let bytes = [0x58, 0x01, 0xaa];
+        assert!(from_canonical_cbor::<CborBytes<[u8; 1]>>(&bytes).is_err());
+        // trailing data
+        let bytes = [0x41, 0xaa, 0x00];
+        assert!(from_canonical_cbor::<CborBytes<[u8; 1]>>(&bytes).is_err());
+        // indefinite length array
+        let bytes = [0x9f, 0x01, 0xff];
+        assert!(from_canonical_cbor::<Vec<u8>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn map_keys_are_sorted() {
+        let mut serializer = Serializer::new_vec();
+        write_canonical_map(&mut serializer, vec![([2u8], 0u8), ([1u8], 0u8)]).unwrap();
+        let bytes = serializer.finalize();
+        assert_eq!(bytes, [0xa2, 0x41, 1, 0, 0x41, 2, 0]);
+
+        let mut serializer = Serializer::new_vec();
+        assert!(write_canonical_map(&mut serializer, vec![([1u8], 0u8), ([1u8], 1u8)]).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod derive_tests {
+    use super::*;
+    use crate::{ChainCborDeserialize, ChainCborSerialize};
+
+    #[derive(Debug, PartialEq, ChainCborSerialize, ChainCborDeserialize)]
+    struct Named {
+        counter: u64,
+        id: [u8; 2],
+        entries: Vec<u16>,
+    }
+
+    #[derive(Debug, PartialEq, ChainCborSerialize, ChainCborDeserialize)]
+    struct Wrapper<T>(T, u8);
+
+    #[derive(Debug, PartialEq, ChainCborSerialize, ChainCborDeserialize)]
+    enum Message {
+        Ping,
+        Data(Vec<u8>),
+        #[chain_ser(tag = 10)]
+        Header {
+            version: u16,
+            length: u32,
+        },
+    }
+
+    fn roundtrip<T>(value: T) -> Vec<u8>
+    where
+        T: CborSerialize + CborDeserialize + PartialEq + std::fmt::Debug,
+    {
+        let bytes = to_canonical_cbor(&value).unwrap();
+        assert_eq!(from_canonical_cbor::<T>(&bytes).unwrap(), value);
+        bytes
+    }
+
+    #[test]
+    fn named_fields_are_written_as_a_canonical_map() {
+        let bytes = roundtrip(Named {
+            counter: 1,
+            id: [0xaa, 0xbb],
+            entries: vec![2],
+        });
+        assert_eq!(
+            bytes,
+            [
+                0xa3, // map of 3 entries
+                0x62, b'i', b'd', 0x42, 0xaa, 0xbb, // "id": h'aabb'
+                0x67, b'c', b'o', b'u', b'n', b't', b'e', b'r', 0x01, // "counter": 1
+                0x67, b'e', b'n', b't', b'r', b'i', b'e', b's', 0x81, 0x02, // "entries": [2]
+            ]
+        );
+    }
+
+    #[test]
+    fn map_keys_out_of_order_are_rejected() {
+        let bytes = [
+            0xa3, 0x67, b'c', b'o', b'u', b'n', b't', b'e', b'r', 0x01, 0x62, b'i', b'd', 0x42,
+            0xaa, 0xbb, 0x67, b'e', b'n', b't', b'r', b'i', b'e', b's', 0x80,
+        ];
+        assert!(from_canonical_cbor::<Named>(&bytes).is_err());
+    }
+
+    #[test]
+    fn tuple_fields_are_written_as_an_array() {
+        assert_eq!(roundtrip(Wrapper(3u32, 4)), [0x82, 0x03, 0x04]);
+    }
+
+    #[test]
+    fn enum_variants_are_tagged() {
+        assert_eq!(roundtrip(Message::Ping), [0x82, 0x00, 0x80]);
+        assert_eq!(
+            roundtrip(Message::Data(vec![5])),
+            [0x82, 0x01, 0x81, 0x81, 0x05]
+        );
+        assert_eq!(
+            roundtrip(Message::Header {
+                version: 1,
+                length: 2
+            }),
+            [
+                0x82, 0x0a, 0xa2, // tag 10, map of 2 entries
+                0x66, b'l', b'e', b'n', b'g', b't', b'h', 0x02, // "length": 2
+                0x67, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0x01, // "version": 1
+            ]
+        );
+        assert!(from_canonical_cbor::<Message>(&[0x82, 0x02, 0x80]).is_err());
+    }
+}
@@ -18,6 +18,67 @@ pub enum ReadError {
     InvalidData(String),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+    #[error("{source} (at byte offset {offset} in {})", .path.join("."))]
+    Context {
+        /// Offset of the error in the input, in bytes.
+        offset: usize,
+        /// Names of the nested fields being read, outermost first.
+        path: Vec<&'static str>,
+        source: Box<ReadError>,
+    },
+}
+
+impl ReadError {
+    /// Annotates the error as having occurred while reading the named
+    /// field. If the error already has context from a nested field,
+    /// the field name is prepended to the path and the offset is preserved.
+    pub fn in_field(self, field: &'static str, offset: usize) -> Self {
+        match self {
+            ReadError::Context {
+                offset,
+                mut path,
+                source,
+            } => {
+                path.insert(0, field);
+                ReadError::Context {
+                    offset,
+                    path,
+                    source,
+                }
+            }
+            e => ReadError::Context {
+                offset,
+                path: vec![field],
+                source: Box::new(e),
+            },
+        }
+    }
+
+    /// Returns the byte offset of the error, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ReadError::Context { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of field names that were being read when the
+    /// error occurred, outermost first. The path is empty if the error
+    /// has no context.
+    pub fn path(&self) -> &[&'static str] {
+        match self {
+            ReadError::Context { path, .. } => path,
+            _ => &[],
+        }
+    }
+
+    /// Returns the underlying error, stripped of any context.
+    pub fn root_cause(&self) -> &ReadError {
+        match self {
+            ReadError::Context { source, .. } => source,
+            e => e,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -30,9 +91,16 @@ pub enum WriteError {
 pub trait Serialize {
     fn serialize<W: std::io::Write>(&self, codec: &mut Codec<W>) -> Result<(), WriteError>;
 
-    /// Default implementation, not efficient, not recommended to use it
+    /// Returns the number of bytes written by `serialize`.
+    ///
+    /// The default implementation runs the serialization into a writer
+    /// that only counts the bytes, without allocating. Implementors that can
+    /// compute the size directly (e.g. fixed-size types) should override it.
     fn serialized_size(&self) -> usize {
-        self.serialize_as_vec().unwrap().len()
+        let mut codec = Codec::new(ByteCounter::default());
+        self.serialize(&mut codec)
+            .expect("serialization into a byte counter should not fail");
+        codec.into_inner().count()
     }
 
     /// Convenience method to serialize into a byte vector.
@@ -47,9 +115,45 @@ impl<T: Serialize> Serialize for &T {
     fn serialize<W: std::io::Write>(&self, codec: &mut Codec<W>) -> Result<(), WriteError> {
         (*self).serialize(codec)
     }
+
+    fn serialized_size(&self) -> usize {
+        (*self).serialized_size()
+    }
+}
+
+/// A writer discarding the data and counting the number of bytes written.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ByteCounter {
+    count: usize,
+}
+
+impl ByteCounter {
+    /// Returns the number of bytes written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl std::io::Write for ByteCounter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Define that an object that can be read from an `std::io::Read` object.
+///
+/// This is the primary deserialization trait: implementing it makes the
+/// type readable from any source, including byte slices through the
+/// blanket implementation of `DeserializeFromSlice`. Types should only
+/// implement `DeserializeFromSlice` directly if they need slice-specific
+/// functionality of `Codec` (e.g. to borrow or measure the input).
 pub trait Deserialize: Sized {
     fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError>;
 
@@ -68,6 +172,19 @@ pub trait DeserializeFromSlice: Sized {
     fn deserialize_validate_from_slice(codec: &mut Codec<&[u8]>) -> Result<(), ReadError> {
         Self::deserialize_from_slice(codec).map(|_| ())
     }
+
+    /// Convenience method to read the object from a byte slice that
+    /// should contain exactly one serialized object.
+    ///
+    /// Returns `ReadError::UnconsumedData` if there are bytes left over.
+    fn deserialize_from_bytes(bytes: &[u8]) -> Result<Self, ReadError> {
+        let mut codec = Codec::new(bytes);
+        let object = Self::deserialize_from_slice(&mut codec)?;
+        if codec.has_bytes_left() {
+            return Err(ReadError::UnconsumedData(codec.bytes_left()));
+        }
+        Ok(object)
+    }
 }
 
 impl<T: Deserialize> DeserializeFromSlice for T {
@@ -99,3 +216,239 @@ impl<const N: usize> Serialize for [u8; N] {
         std::mem::size_of::<[u8; N]>()
     }
 }
+
+macro_rules! impl_be_integer {
+    ($ty:ty, $get:ident, $put:ident) => {
+        impl Serialize for $ty {
+            fn serialize<W: std::io::Write>(&self, codec: &mut Codec<W>) -> Result<(), WriteError> {
+                codec.$put(*self)
+            }
+
+            fn serialized_size(&self) -> usize {
+                std::mem::size_of::<$ty>()
+            }
+        }
+
+        impl Deserialize for $ty {
+            fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
+                codec.$get()
+            }
+        }
+    };
+}
+
+impl_be_integer!(u8, get_u8, put_u8);
+impl_be_integer!(u16, get_be_u16, put_be_u16);
+impl_be_integer!(u32, get_be_u32, put_be_u32);
+impl_be_integer!(u64, get_be_u64, put_be_u64);
+impl_be_integer!(u128, get_be_u128, put_be_u128);
+
+/// Vectors are written as the number of elements, a big-endian `u64`,
+/// followed by the elements themselves.
+impl<T: Serialize> Serialize for Vec<T> {
+    fn serialize<W: std::io::Write>(&self, codec: &mut Codec<W>) -> Result<(), WriteError> {
+        codec.put_be_u64(self.len() as u64)?;
+        for item in self {
+            item.serialize(codec)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.iter().fold(std::mem::size_of::<u64>(), |acc, item| {
+            acc + item.serialized_size()
+        })
+    }
+}
+
+impl<T: Deserialize> Deserialize for Vec<T> {
+    fn deserialize<R: std::io::Read>(codec: &mut Codec<R>) -> Result<Self, ReadError> {
+        let len = codec.get_be_u64()?;
+        // the length comes from untrusted input: let the vector grow as
+        // the elements are actually read rather than preallocating it
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(T::deserialize(codec)?);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Composite {
+        tag: u8,
+        data: Vec<u8>,
+    }
+
+    impl Serialize for Composite {
+        fn serialize<W: std::io::Write>(&self, codec: &mut Codec<W>) -> Result<(), WriteError> {
+            codec.put_u8(self.tag)?;
+            codec.put_len_prefixed_bytes(&self.data)
+        }
+    }
+
+    #[test]
+    fn default_serialized_size_matches_output() {
+        let object = Composite {
+            tag: 1,
+            data: vec![0; 42],
+        };
+        let size = object.serialize_as_vec().unwrap().len();
+        assert_eq!(object.serialized_size(), size);
+        assert_eq!((&object).serialized_size(), size);
+        assert_eq!([0u8; 16].serialized_size(), 16);
+    }
+
+    #[test]
+    fn scoped_errors_carry_offset_and_path() {
+        let bytes = [0u8, 1, 2, 3, 4];
+        let mut codec = Codec::new(&bytes[..]);
+        let err = codec
+            .scoped("outer", |codec| {
+                codec.get_be_u16()?;
+                codec.scoped("inner", |codec| {
+                    codec.get_u8()?;
+                    codec.get_be_u32()
+                })
+            })
+            .unwrap_err();
+        assert_eq!(err.offset(), Some(3));
+        assert_eq!(err.path(), ["outer", "inner"]);
+        assert!(matches!(err.root_cause(), ReadError::IoError(_)));
+        assert_eq!(codec.bytes_consumed(), 3);
+    }
+
+    #[test]
+    fn seeking_keeps_offsets_relative_to_the_buffer() {
+        let bytes = [0u8, 1, 2, 3, 4];
+        let mut codec = Codec::new(std::io::Cursor::new(&bytes[..]));
+        codec.get_be_u32().unwrap();
+        codec.set_position(2);
+        assert_eq!(codec.bytes_consumed(), 2);
+        let err = codec
+            .scoped("field", |codec| codec.get_be_u32())
+            .unwrap_err();
+        assert_eq!(err.offset(), Some(2));
+    }
+
+    #[test]
+    fn deserialize_from_bytes_checks_unconsumed_data() {
+        let bytes = [1u8, 2, 3, 4];
+        let array = <[u8; 4]>::deserialize_from_bytes(&bytes).unwrap();
+        assert_eq!(array, bytes);
+        match <[u8; 3]>::deserialize_from_bytes(&bytes) {
+            Err(ReadError::UnconsumedData(1)) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn integers_and_vectors_roundtrip() {
+        let values: Vec<u32> = vec![1, 2, 0xdead_beef];
+        let bytes = values.serialize_as_vec().unwrap();
+        assert_eq!(bytes.len(), values.serialized_size());
+        assert_eq!(&bytes[..8], &3u64.to_be_bytes());
+        assert_eq!(Vec::<u32>::deserialize_from_bytes(&bytes).unwrap(), values);
+    }
+
+    #[test]
+    fn vector_with_oversized_count_fails() {
+        let bytes = u64::MAX.to_be_bytes();
+        assert!(Vec::<u8>::deserialize_from_bytes(&bytes).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod derive_tests {
+    use super::*;
+    use crate::{ChainDeserialize, ChainSerialize};
+
+    #[derive(Debug, PartialEq, ChainSerialize, ChainDeserialize)]
+    struct Named {
+        id: [u8; 4],
+        counter: u64,
+        entries: Vec<u16>,
+    }
+
+    #[derive(Debug, PartialEq, ChainSerialize, ChainDeserialize)]
+    struct Wrapper<T>(T, u8);
+
+    #[derive(Debug, PartialEq, ChainSerialize, ChainDeserialize)]
+    enum Message {
+        Ping,
+        Data(Vec<u8>),
+        #[chain_ser(tag = 10)]
+        Header {
+            version: u16,
+            length: u32,
+        },
+    }
+
+    // field names must not clash with the generated code
+    #[derive(Debug, PartialEq, ChainSerialize, ChainDeserialize)]
+    struct Shadowing {
+        codec: u8,
+        field_0: u8,
+    }
+
+    fn roundtrip<T>(value: T) -> Vec<u8>
+    where
+        T: Serialize + Deserialize + PartialEq + std::fmt::Debug,
+    {
+        let bytes = value.serialize_as_vec().unwrap();
+        assert_eq!(bytes.len(), value.serialized_size());
+        assert_eq!(T::deserialize_from_bytes(&bytes).unwrap(), value);
+        bytes
+    }
+
+    #[test]
+    fn struct_fields_are_written_in_order() {
+        let bytes = roundtrip(Named {
+            id: [1, 2, 3, 4],
+            counter: 5,
+            entries: vec![6],
+        });
+        let mut expected = vec![1, 2, 3, 4];
+        expected.extend_from_slice(&5u64.to_be_bytes());
+        expected.extend_from_slice(&1u64.to_be_bytes());
+        expected.extend_from_slice(&[0, 6]);
+        assert_eq!(bytes, expected);
+
+        roundtrip(Wrapper(
+            Named {
+                id: [0; 4],
+                counter: 0,
+                entries: Vec::new(),
+            },
+            7,
+        ));
+
+        assert_eq!(
+            roundtrip(Shadowing {
+                codec: 1,
+                field_0: 2
+            }),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn enum_variants_are_tagged() {
+        assert_eq!(roundtrip(Message::Ping), [0]);
+        assert_eq!(roundtrip(Message::Data(Vec::new()))[0], 1);
+        assert_eq!(
+            roundtrip(Message::Header {
+                version: 1,
+                length: 2
+            }),
+            [10, 0, 1, 0, 0, 0, 2]
+        );
+        match Message::deserialize_from_bytes(&[2]) {
+            Err(ReadError::UnknownTag(2)) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
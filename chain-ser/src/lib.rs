@@ -1,3 +1,16 @@
+// allows the derived implementations, which refer to `::chain_ser`,
+// to be used within this crate
+extern crate self as chain_ser;
+
 pub mod abor;
+#[cfg(feature = "async")]
+pub mod async_packer;
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod deser;
 pub mod packer;
+
+#[cfg(all(feature = "derive", feature = "cbor"))]
+pub use chain_ser_derive::{ChainCborDeserialize, ChainCborSerialize};
+#[cfg(feature = "derive")]
+pub use chain_ser_derive::{ChainDeserialize, ChainSerialize};
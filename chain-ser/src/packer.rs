@@ -3,7 +3,7 @@
 //! This will allow us to expose some standard way of serializing
 //! data.
 
-use crate::deser::{ReadError, WriteError};
+use crate::deser::{ReadError, Serialize, WriteError};
 use std::num::{NonZeroU32, NonZeroU64};
 
 /// The structure to support (de)serialization of binary data format used by
@@ -18,18 +18,59 @@ use std::num::{NonZeroU32, NonZeroU64};
 /// ## Writing data
 ///
 /// Data can be written into any `std::io::Write` implementor.
+///
+/// ## Error context
+///
+/// The codec counts the bytes consumed while reading. Parsing code can
+/// wrap the reading of a field with `scoped` to annotate errors with the
+/// byte offset at which they occurred and the path of nested fields
+/// that were being read.
 pub struct Codec<I> {
     inner: I,
+    // offset of the start of `inner` in the whole input
+    base: usize,
+    consumed: usize,
 }
 
 impl<I> Codec<I> {
     pub fn new(inner: I) -> Self {
-        Codec { inner }
+        Self::with_offset(inner, 0)
+    }
+
+    /// Creates a codec reading a part of a larger input, starting at
+    /// `offset` in it. The offsets of errors and the value of
+    /// `bytes_consumed` are then relative to the start of the larger input.
+    pub fn with_offset(inner: I, offset: usize) -> Self {
+        Codec {
+            inner,
+            base: offset,
+            consumed: offset,
+        }
     }
 
     pub fn into_inner(self) -> I {
         self.inner
     }
+
+    /// Returns the number of bytes read so far through this codec.
+    #[inline]
+    pub fn bytes_consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Reads a field with the function `f`, annotating a failure with
+    /// the name of the field and the byte offset of the error.
+    ///
+    /// Scopes can be nested: the error then carries the offset where
+    /// it was detected by the innermost scope and the full path of
+    /// field names, outermost first.
+    pub fn scoped<T, F>(&mut self, field: &'static str, f: F) -> Result<T, ReadError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ReadError>,
+    {
+        let res = f(self);
+        res.map_err(|e| e.in_field(field, self.consumed))
+    }
 }
 
 impl Codec<()> {
@@ -72,6 +113,12 @@ impl Codec<()> {
     pub const fn i128_size() -> usize {
         std::mem::size_of::<i128>()
     }
+
+    /// Returns the size of the LEB128 variable-length encoding of `v`.
+    pub const fn varint_u64_size(v: u64) -> usize {
+        let bits = 64 - (v | 1).leading_zeros() as usize;
+        (bits + 6) / 7
+    }
 }
 
 impl Codec<&[u8]> {
@@ -82,12 +129,14 @@ impl Codec<&[u8]> {
         }
         let res = &self.inner[..n];
         self.inner = &self.inner[n..];
+        self.consumed += n;
         Ok(res)
     }
 
     #[inline]
     pub fn skip_bytes(&mut self, pos: usize) {
         self.inner = &self.inner[pos..];
+        self.consumed += pos;
     }
 
     #[inline]
@@ -102,72 +151,80 @@ impl Codec<&[u8]> {
 }
 
 impl<R: std::io::Read> Codec<R> {
+    #[inline]
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ReadError> {
+        self.inner.read_exact(buf)?;
+        self.consumed += buf.len();
+        Ok(())
+    }
+
     #[inline]
     pub fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, ReadError> {
         let res = self.inner.read_to_end(buf)?;
+        self.consumed += res;
         Ok(res)
     }
 
     #[inline]
     pub fn get_u8(&mut self) -> Result<u8, ReadError> {
         let mut buf = [0u8; 1];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(buf[0])
     }
 
     #[inline]
     pub fn get_be_u16(&mut self) -> Result<u16, ReadError> {
         let mut buf = [0u8; 2];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u16::from_be_bytes(buf))
     }
 
     #[inline]
     pub fn get_le_u16(&mut self) -> Result<u16, ReadError> {
         let mut buf = [0u8; 2];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u16::from_le_bytes(buf))
     }
 
     #[inline]
     pub fn get_be_u32(&mut self) -> Result<u32, ReadError> {
         let mut buf = [0u8; 4];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u32::from_be_bytes(buf))
     }
 
     #[inline]
     pub fn get_le_u32(&mut self) -> Result<u32, ReadError> {
         let mut buf = [0u8; 4];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u32::from_le_bytes(buf))
     }
 
     #[inline]
     pub fn get_be_u64(&mut self) -> Result<u64, ReadError> {
         let mut buf = [0u8; 8];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u64::from_be_bytes(buf))
     }
 
     #[inline]
     pub fn get_le_u64(&mut self) -> Result<u64, ReadError> {
         let mut buf = [0u8; 8];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u64::from_le_bytes(buf))
     }
 
     #[inline]
     pub fn get_be_u128(&mut self) -> Result<u128, ReadError> {
         let mut buf = [0u8; 16];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u128::from_be_bytes(buf))
     }
 
     #[inline]
     pub fn get_le_u128(&mut self) -> Result<u128, ReadError> {
         let mut buf = [0u8; 16];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u128::from_le_bytes(buf))
     }
 
@@ -188,13 +245,47 @@ impl<R: std::io::Read> Codec<R> {
     #[inline]
     pub fn get_bytes(&mut self, n: usize) -> Result<Vec<u8>, ReadError> {
         let mut buf = vec![0u8; n];
-        self.inner.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(buf)
     }
 
+    /// Reads an unsigned integer in the LEB128 variable-length encoding.
+    ///
+    /// Only the canonical (shortest) encoding of a value is accepted,
+    /// so that every value has exactly one binary representation.
+    pub fn get_varint_u64(&mut self) -> Result<u64, ReadError> {
+        let mut decoder = VarintDecoder::default();
+        loop {
+            if let Some(value) = decoder.push(self.get_u8()?)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads a byte vector prefixed by its length in the LEB128
+    /// variable-length encoding, as written by
+    /// `put_varint_len_prefixed_bytes`.
+    ///
+    /// The length is checked against `max` before any memory is allocated,
+    /// failing with `ReadError::SizeTooBig` if it is exceeded.
+    pub fn get_varint_len_prefixed_bytes(&mut self, max: usize) -> Result<Vec<u8>, ReadError> {
+        let len = check_len(self.get_varint_u64()?, max)?;
+        self.get_bytes(len)
+    }
+
+    /// Reads a byte vector prefixed by its length as a big-endian `u64`,
+    /// as written by `put_len_prefixed_bytes`.
+    ///
+    /// The length is checked against `max` before any memory is allocated,
+    /// failing with `ReadError::SizeTooBig` if it is exceeded.
+    pub fn get_len_prefixed_bytes(&mut self, max: usize) -> Result<Vec<u8>, ReadError> {
+        let len = check_len(self.get_be_u64()?, max)?;
+        self.get_bytes(len)
+    }
+
     #[inline]
     pub fn copy_to_slice(&mut self, slice: &mut [u8]) -> Result<(), ReadError> {
-        self.inner.read_exact(slice)?;
+        self.read_exact(slice)?;
         Ok(())
     }
 }
@@ -259,6 +350,167 @@ impl<W: std::io::Write> Codec<W> {
         self.inner.write_all(v)?;
         Ok(())
     }
+
+    /// Writes an unsigned integer in the LEB128 variable-length encoding,
+    /// taking from 1 to 10 bytes depending on the magnitude of the value.
+    pub fn put_varint_u64(&mut self, v: u64) -> Result<(), WriteError> {
+        let mut buf = [0u8; MAX_VARINT_U64_SIZE];
+        let len = encode_varint_u64(v, &mut buf);
+        self.put_bytes(&buf[..len])
+    }
+
+    /// Writes a byte slice prefixed by its length in the LEB128
+    /// variable-length encoding.
+    pub fn put_varint_len_prefixed_bytes(&mut self, v: &[u8]) -> Result<(), WriteError> {
+        self.put_varint_u64(v.len() as u64)?;
+        self.put_bytes(v)
+    }
+
+    /// Writes a byte slice prefixed by its length as a big-endian `u64`.
+    pub fn put_len_prefixed_bytes(&mut self, v: &[u8]) -> Result<(), WriteError> {
+        self.put_be_u64(v.len() as u64)?;
+        self.put_bytes(v)
+    }
+}
+
+impl Codec<Vec<u8>> {
+    /// Creates a codec writing into a vector preallocated to hold
+    /// `estimate` bytes, e.g. the `serialized_size` of the object to write,
+    /// so that the output does not need to grow while writing.
+    pub fn with_capacity_estimate(estimate: usize) -> Self {
+        Codec::new(Vec::with_capacity(estimate))
+    }
+}
+
+impl<W: std::io::Write> Codec<W> {
+    /// Writes all the buffers in order, as if by consecutive calls to
+    /// `put_bytes`, using vectored writes when the writer supports them.
+    ///
+    /// This avoids copying a header and a body into an intermediate
+    /// buffer before handing them to e.g. a socket.
+    pub fn put_bytes_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), WriteError> {
+        use std::io::{ErrorKind, IoSlice};
+
+        const MAX_SLICES: usize = 16;
+
+        let mut index = 0;
+        let mut offset = 0;
+        while index < bufs.len() {
+            if offset == bufs[index].len() {
+                index += 1;
+                offset = 0;
+                continue;
+            }
+            let pending = &bufs[index..bufs.len().min(index + MAX_SLICES)];
+            let mut slices = [IoSlice::new(&[]); MAX_SLICES];
+            for (slice, buf) in slices.iter_mut().zip(pending) {
+                *slice = IoSlice::new(buf);
+            }
+            slices[0] = IoSlice::new(&bufs[index][offset..]);
+            let mut written = match self.inner.write_vectored(&slices[..pending.len()]) {
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    )
+                    .into())
+                }
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while written > 0 {
+                let remaining = bufs[index].len() - offset;
+                if written < remaining {
+                    offset += written;
+                    break;
+                }
+                written -= remaining;
+                index += 1;
+                offset = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A reusable output buffer for serialization.
+///
+/// Serializing with `Serialize::serialize_as_vec` allocates a new vector
+/// on every call. In hot paths, a `WriteBuffer` can be kept around instead:
+/// the buffer is cleared before each serialization but keeps its
+/// allocated capacity, so after warming up no allocation takes place.
+#[derive(Debug, Default, Clone)]
+pub struct WriteBuffer {
+    buf: Vec<u8>,
+}
+
+impl WriteBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        WriteBuffer {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Serializes the object in place of the current contents of the
+    /// buffer and returns the serialized bytes.
+    pub fn serialize<T: Serialize + ?Sized>(&mut self, object: &T) -> Result<&[u8], WriteError> {
+        self.buf.clear();
+        self.append(object)
+    }
+
+    /// Serializes the object after the current contents of the buffer
+    /// and returns the newly written bytes.
+    pub fn append<T: Serialize + ?Sized>(&mut self, object: &T) -> Result<&[u8], WriteError> {
+        let start = self.buf.len();
+        let res = object.serialize(&mut Codec::new(&mut self.buf));
+        if let Err(e) = res {
+            self.buf.truncate(start);
+            return Err(e);
+        }
+        Ok(&self.buf[start..])
+    }
+
+    /// Gives a codec appending to the buffer, for writing data
+    /// that is not a single serializable object.
+    pub fn codec(&mut self) -> Codec<&mut Vec<u8>> {
+        Codec::new(&mut self.buf)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Empties the buffer, keeping its allocated capacity.
+    pub fn clear(&mut self) {
+        self.buf.clear()
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+impl AsRef<[u8]> for WriteBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf
+    }
 }
 
 impl<T> Codec<std::io::Cursor<T>> {
@@ -267,8 +519,192 @@ impl<T> Codec<std::io::Cursor<T>> {
         self.inner.position() as usize
     }
 
+    /// Moves the read cursor to `pos`. The count of consumed bytes is
+    /// set to `pos` as well, so that offsets in errors and the value of
+    /// `bytes_consumed` stay relative to the start of the buffer (or to
+    /// the start of the larger input, see `with_offset`).
     #[inline]
     pub fn set_position(&mut self, pos: usize) {
-        self.inner.set_position(pos as u64)
+        self.inner.set_position(pos as u64);
+        self.consumed = self.base + pos;
+    }
+}
+
+pub(crate) const MAX_VARINT_U64_SIZE: usize = 10;
+
+/// Incremental decoder of the LEB128 encoding of a `u64` value.
+#[derive(Default)]
+pub(crate) struct VarintDecoder {
+    value: u64,
+    shift: u32,
+}
+
+impl VarintDecoder {
+    /// Feeds the next byte of the encoding. Returns the decoded value
+    /// if the byte is the last one in the encoding.
+    pub(crate) fn push(&mut self, byte: u8) -> Result<Option<u64>, ReadError> {
+        if self.shift == 63 && byte > 1 {
+            return Err(ReadError::StructureInvalid(
+                "varint overflows u64".to_string(),
+            ));
+        }
+        self.value |= ((byte & 0x7f) as u64) << self.shift;
+        if byte & 0x80 != 0 {
+            self.shift += 7;
+            return Ok(None);
+        }
+        if byte == 0 && self.shift > 0 {
+            return Err(ReadError::StructureInvalid(
+                "non-canonical varint encoding".to_string(),
+            ));
+        }
+        Ok(Some(self.value))
+    }
+}
+
+/// Writes the LEB128 encoding of `v` into the buffer,
+/// returning the number of bytes used.
+pub(crate) fn encode_varint_u64(mut v: u64, buf: &mut [u8; MAX_VARINT_U64_SIZE]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            buf[len] = byte;
+            return len + 1;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+/// Checks a length prefix read from the input against the maximum
+/// allowed by the caller.
+pub(crate) fn check_len(len: u64, max: usize) -> Result<usize, ReadError> {
+    match usize::try_from(len) {
+        Ok(len) if len <= max => Ok(len),
+        _ => Err(ReadError::SizeTooBig(
+            max,
+            usize::try_from(len).unwrap_or(usize::MAX),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn len_prefixed_bytes_round_trip() {
+        let mut codec = Codec::new(Vec::new());
+        codec.put_len_prefixed_bytes(&[1, 2, 3]).unwrap();
+        codec.put_len_prefixed_bytes(&[]).unwrap();
+        let data = codec.into_inner();
+        assert_eq!(data.len(), 8 + 3 + 8);
+
+        let mut codec = Codec::new(data.as_slice());
+        assert_eq!(codec.get_len_prefixed_bytes(3).unwrap(), [1, 2, 3]);
+        assert!(codec.get_len_prefixed_bytes(0).unwrap().is_empty());
+        assert!(!codec.has_bytes_left());
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let values = [0, 1, 127, 128, 300, 16383, 16384, u32::MAX as u64, u64::MAX];
+        let mut codec = Codec::new(Vec::new());
+        for &v in &values {
+            codec.put_varint_u64(v).unwrap();
+        }
+        let data = codec.into_inner();
+        let expected_size: usize = values.iter().map(|&v| Codec::varint_u64_size(v)).sum();
+        assert_eq!(data.len(), expected_size);
+        let mut codec = Codec::new(data.as_slice());
+        for &v in &values {
+            assert_eq!(codec.get_varint_u64().unwrap(), v);
+        }
+        assert!(!codec.has_bytes_left());
+    }
+
+    #[test]
+    fn varint_encoding() {
+        let mut codec = Codec::new(Vec::new());
+        codec.put_varint_u64(300).unwrap();
+        assert_eq!(codec.into_inner(), [0xac, 0x02]);
+        assert_eq!(Codec::varint_u64_size(0), 1);
+        assert_eq!(Codec::varint_u64_size(127), 1);
+        assert_eq!(Codec::varint_u64_size(128), 2);
+        assert_eq!(Codec::varint_u64_size(u64::MAX), 10);
+    }
+
+    #[test]
+    fn varint_rejects_invalid_encodings() {
+        // non-canonical encoding of 0
+        let mut codec = Codec::new(&[0x80u8, 0x00][..]);
+        assert!(codec.get_varint_u64().is_err());
+        // value exceeding u64::MAX
+        let mut codec =
+            Codec::new(&[0xffu8, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02][..]);
+        assert!(codec.get_varint_u64().is_err());
+        // truncated input
+        let mut codec = Codec::new(&[0x80u8][..]);
+        assert!(codec.get_varint_u64().is_err());
+    }
+
+    #[test]
+    fn len_prefixed_bytes_over_limit() {
+        let mut codec = Codec::new(Vec::new());
+        codec.put_be_u64(u64::MAX).unwrap();
+        let data = codec.into_inner();
+        let mut codec = Codec::new(data.as_slice());
+        match codec.get_len_prefixed_bytes(1024) {
+            Err(ReadError::SizeTooBig(1024, _)) => {}
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    /// Accepts at most 3 bytes per write call, to exercise partial writes.
+    struct Trickle(Vec<u8>);
+
+    impl std::io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn put_bytes_vectored_handles_partial_writes() {
+        let bufs: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; i as usize % 5]).collect();
+        let slices: Vec<&[u8]> = bufs.iter().map(Vec::as_slice).collect();
+        let expected = bufs.concat();
+
+        let mut codec = Codec::new(Trickle(Vec::new()));
+        codec.put_bytes_vectored(&slices).unwrap();
+        assert_eq!(codec.into_inner().0, expected);
+
+        let mut codec = Codec::with_capacity_estimate(expected.len());
+        codec.put_bytes_vectored(&slices).unwrap();
+        let data = codec.into_inner();
+        assert_eq!(data, expected);
+        assert!(data.capacity() >= expected.len());
+    }
+
+    #[test]
+    fn write_buffer_reuses_allocation() {
+        let mut buffer = WriteBuffer::with_capacity(16);
+        assert_eq!(buffer.serialize(&[1u8, 2, 3]).unwrap(), [1, 2, 3]);
+        let capacity = buffer.capacity();
+        assert_eq!(buffer.serialize(&[4u8, 5]).unwrap(), [4, 5]);
+        assert_eq!(buffer.append(&[6u8]).unwrap(), [6]);
+        assert_eq!(buffer.as_slice(), [4, 5, 6]);
+        assert_eq!(buffer.capacity(), capacity);
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
     }
 }
@@ -0,0 +1,226 @@
+//! Portable archive of a range of blocks, independent of the layout of the
+//! storage, to be used for backups or to bootstrap a node offline.
+//!
+//! All integers are little endian. The archive is a header followed by a
+//! sequence of records:
+//!
+//! ```ignore
+//! header:       MAGIC (8 bytes) ++ VERSION (1 byte) ++ id_length (u32)
+//! block record: RECORD_BLOCK ++ id ++ parent_id ++ chain_length (u32)
+//!               ++ has_date (1 byte) ++ [date (8 bytes)] ++ block_length (u32)
+//!               ++ block ++ crc32
+//! end record:   RECORD_END ++ block_count (u64) ++ crc32
+//! ```
+//!
+//! The checksum of a record covers all its fields but the record type.
+
+use crate::{BlockDate, BlockInfo, BlockStore, Error};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"CHAINARC";
+const VERSION: u8 = 1;
+
+const RECORD_END: u8 = 0;
+const RECORD_BLOCK: u8 = 1;
+
+pub(crate) fn export<W: Write>(
+    store: &BlockStore,
+    mut writer: W,
+    from: &[u8],
+    to: &[u8],
+) -> Result<u64, Error> {
+    let distance = store.is_ancestor(from, to)?.ok_or(Error::NotAncestor)?;
+    if store.get_block_info(from)?.chain_length() < store.pruned_before()? {
+        return Err(Error::BlockPruned);
+    }
+
+    let mut block_infos = Vec::with_capacity(distance as usize + 1);
+    block_infos.push(store.get_block_info(to)?);
+    for _ in 0..distance {
+        let parent_id = block_infos.last().unwrap().parent_id().clone();
+        block_infos.push(store.get_block_info(parent_id.as_ref())?);
+    }
+
+    let id_length = store.id_length() as u32;
+    writer.write_all(MAGIC).map_err(Error::ArchiveIo)?;
+    writer.write_all(&[VERSION]).map_err(Error::ArchiveIo)?;
+    writer
+        .write_all(&id_length.to_le_bytes())
+        .map_err(Error::ArchiveIo)?;
+
+    for block_info in block_infos.iter().rev() {
+        let id = block_info.id().as_ref();
+        let block = store.get_block(id)?;
+        let block = block.as_ref();
+
+        let mut record = Vec::new();
+        record.extend_from_slice(id);
+        record.extend_from_slice(block_info.parent_id().as_ref());
+        record.extend_from_slice(&block_info.chain_length().to_le_bytes());
+        match store.get_block_date(id)? {
+            Some(date) => {
+                record.push(1);
+                record.extend_from_slice(&date.serialize());
+            }
+            None => record.push(0),
+        }
+        record.extend_from_slice(&(block.len() as u32).to_le_bytes());
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&record);
+        hasher.update(block);
+
+        writer
+            .write_all(&[RECORD_BLOCK])
+            .map_err(Error::ArchiveIo)?;
+        writer.write_all(&record).map_err(Error::ArchiveIo)?;
+        writer.write_all(block).map_err(Error::ArchiveIo)?;
+        writer
+            .write_all(&hasher.finalize().to_le_bytes())
+            .map_err(Error::ArchiveIo)?;
+    }
+
+    let block_count = block_infos.len() as u64;
+    let block_count_bytes = block_count.to_le_bytes();
+    writer.write_all(&[RECORD_END]).map_err(Error::ArchiveIo)?;
+    writer
+        .write_all(&block_count_bytes)
+        .map_err(Error::ArchiveIo)?;
+    writer
+        .write_all(&crc32fast::hash(&block_count_bytes).to_le_bytes())
+        .map_err(Error::ArchiveIo)?;
+    writer.flush().map_err(Error::ArchiveIo)?;
+
+    Ok(block_count)
+}
+
+pub(crate) fn import<R: Read>(store: &BlockStore, reader: R) -> Result<u64, Error> {
+    let mut reader = ArchiveReader {
+        reader,
+        hasher: crc32fast::Hasher::new(),
+    };
+
+    let mut magic = [0u8; 8];
+    reader.read_bytes(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidArchive("not a block archive"));
+    }
+    if reader.read_u8()? != VERSION {
+        return Err(Error::InvalidArchive("unsupported version"));
+    }
+    let id_length = reader.read_u32()? as usize;
+    if id_length != store.id_length() {
+        return Err(Error::InvalidArchive("unexpected length of block IDs"));
+    }
+
+    let mut block_count = 0;
+    let mut imported = 0;
+
+    loop {
+        let record_type = reader.read_u8()?;
+        reader.hasher = crc32fast::Hasher::new();
+
+        match record_type {
+            RECORD_BLOCK => {
+                let mut id = vec![0u8; id_length];
+                reader.read_bytes(&mut id)?;
+                let mut parent_id = vec![0u8; id_length];
+                reader.read_bytes(&mut parent_id)?;
+                let chain_length = reader.read_u32()?;
+                let date = match reader.read_u8()? {
+                    0 => None,
+                    1 => {
+                        let mut date = [0u8; BlockDate::SIZE];
+                        reader.read_bytes(&mut date)?;
+                        Some(BlockDate::deserialize(&date))
+                    }
+                    _ => return Err(Error::InvalidArchive("invalid date flag")),
+                };
+                let block_length = reader.read_u32()?;
+                let block = reader.read_vec(block_length as usize)?;
+                reader.check_checksum()?;
+
+                block_count += 1;
+                if store.block_exists(&id)? {
+                    continue;
+                }
+
+                let mut block_info = BlockInfo::new(id, parent_id, chain_length);
+                if let Some(date) = date {
+                    block_info = block_info.with_date(date);
+                }
+                store.put_block(&block, block_info)?;
+                imported += 1;
+            }
+            RECORD_END => {
+                let expected_block_count = reader.read_u64()?;
+                reader.check_checksum()?;
+                if expected_block_count != block_count {
+                    return Err(Error::InvalidArchive("unexpected number of blocks"));
+                }
+                return Ok(imported);
+            }
+            _ => return Err(Error::InvalidArchive("unknown record type")),
+        }
+    }
+}
+
+// Reads the fields of a record, computing their checksum on the way.
+struct ArchiveReader<R> {
+    reader: R,
+    hasher: crc32fast::Hasher,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.reader.read_exact(buf).map_err(Error::ArchiveIo)?;
+        self.hasher.update(buf);
+        Ok(())
+    }
+
+    // Reads a field of the given length. The buffer grows with the data
+    // actually read, so that a corrupted length does not cause a huge
+    // allocation before the checksum is verified.
+    fn read_vec(&mut self, length: usize) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut buf)
+            .map_err(Error::ArchiveIo)?;
+        if buf.len() != length {
+            return Err(Error::ArchiveIo(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+        self.hasher.update(&buf);
+        Ok(buf)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        let mut bytes = [0u8; 1];
+        self.read_bytes(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0u8; 4];
+        self.read_bytes(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0u8; 8];
+        self.read_bytes(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn check_checksum(&mut self) -> Result<(), Error> {
+        let expected = std::mem::take(&mut self.hasher).finalize();
+        let mut checksum = [0u8; 4];
+        self.reader
+            .read_exact(&mut checksum)
+            .map_err(Error::ArchiveIo)?;
+        if u32::from_le_bytes(checksum) != expected {
+            return Err(Error::ArchiveChecksum);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+//! Asynchronous facade over the block storage.
+//!
+//! The storage operations are blocking, so they have to be kept off the
+//! threads of an asynchronous runtime. `AsyncBlockStore` runs them on the
+//! blocking thread pool of `tokio`, and thus must be used within a `tokio`
+//! runtime.
+
+use crate::{BlockInfo, BlockStore, Error, Value};
+use futures::{channel::mpsc, SinkExt, Stream};
+use std::ops::Range;
+
+// Number of blocks read ahead of the consumer of a block stream.
+const STREAM_BUFFER_SIZE: usize = 16;
+
+/// Asynchronous handle to a `BlockStore`. See the methods of the same name of
+/// `BlockStore` for the documentation of the operations.
+#[derive(Clone)]
+pub struct AsyncBlockStore {
+    inner: BlockStore,
+}
+
+impl AsyncBlockStore {
+    pub fn new(store: BlockStore) -> Self {
+        Self { inner: store }
+    }
+
+    /// The underlying store, for the operations that do not need to be run
+    /// off the runtime threads.
+    pub fn inner(&self) -> &BlockStore {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> BlockStore {
+        self.inner
+    }
+
+    async fn run<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&BlockStore) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let store = self.inner.clone();
+        match tokio::task::spawn_blocking(move || f(&store)).await {
+            Ok(result) => result,
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            Err(_) => Err(Error::TaskCancelled),
+        }
+    }
+
+    pub async fn put_block(&self, block: Vec<u8>, block_info: BlockInfo) -> Result<(), Error> {
+        self.run(move |store| store.put_block(&block, block_info))
+            .await
+    }
+
+    pub async fn get_block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        let block_id = block_id.to_vec();
+        self.run(move |store| store.get_block(&block_id)).await
+    }
+
+    pub async fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
+        let block_id = block_id.to_vec();
+        self.run(move |store| store.get_block_info(&block_id)).await
+    }
+
+    pub async fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
+        self.run(move |store| store.get_blocks_by_chain_length(chain_length))
+            .await
+    }
+
+    pub async fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        let tag_name = tag_name.to_string();
+        let block_id = block_id.to_vec();
+        self.run(move |store| store.put_tag(&tag_name, &block_id))
+            .await
+    }
+
+    pub async fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
+        let tag_name = tag_name.to_string();
+        self.run(move |store| store.get_tag(&tag_name)).await
+    }
+
+    pub async fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
+        self.run(|store| store.get_tips_ids()).await
+    }
+
+    pub async fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error> {
+        let tip_id = tip_id.to_vec();
+        self.run(move |store| store.prune_branch(&tip_id)).await
+    }
+
+    pub async fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error> {
+        let block_id = block_id.to_vec();
+        self.run(move |store| store.block_exists(&block_id)).await
+    }
+
+    pub async fn is_ancestor(
+        &self,
+        ancestor_id: &[u8],
+        descendant_id: &[u8],
+    ) -> Result<Option<u32>, Error> {
+        let ancestor_id = ancestor_id.to_vec();
+        let descendant_id = descendant_id.to_vec();
+        self.run(move |store| store.is_ancestor(&ancestor_id, &descendant_id))
+            .await
+    }
+
+    pub async fn get_nth_ancestor(
+        &self,
+        block_id: &[u8],
+        distance: u32,
+    ) -> Result<BlockInfo, Error> {
+        let block_id = block_id.to_vec();
+        self.run(move |store| store.get_nth_ancestor(&block_id, distance))
+            .await
+    }
+
+    pub async fn find_lowest_common_ancestor(
+        &self,
+        block1: &[u8],
+        block2: &[u8],
+    ) -> Result<Option<BlockInfo>, Error> {
+        let block1 = block1.to_vec();
+        let block2 = block2.to_vec();
+        self.run(move |store| store.find_lowest_common_ancestor(&block1, &block2))
+            .await
+    }
+
+    pub async fn flush_to_permanent_store(
+        &self,
+        to_block: &[u8],
+        min_number: usize,
+    ) -> Result<usize, Error> {
+        let to_block = to_block.to_vec();
+        self.run(move |store| store.flush_to_permanent_store(&to_block, min_number))
+            .await
+    }
+
+    /// Stream the blocks as `BlockStore::iter` would iterate over them. The
+    /// blocks are read on the blocking thread pool, a few blocks ahead of the
+    /// consumer of the stream. Reading stops when the stream is dropped.
+    pub async fn stream(
+        &self,
+        to_block: &[u8],
+        distance: u32,
+    ) -> Result<impl Stream<Item = Result<Value, Error>>, Error> {
+        let to_block = to_block.to_vec();
+        let iter = self
+            .run(move |store| store.iter(&to_block, distance))
+            .await?;
+        Ok(spawn_stream(iter))
+    }
+
+    /// Stream the blocks as `BlockStore::iter_range` would iterate over them.
+    /// See `stream`.
+    pub async fn stream_range(
+        &self,
+        tip_id: &[u8],
+        chain_lengths: Range<u32>,
+    ) -> Result<impl Stream<Item = Result<Value, Error>>, Error> {
+        let tip_id = tip_id.to_vec();
+        let iter = self
+            .run(move |store| store.iter_range(&tip_id, chain_lengths))
+            .await?;
+        Ok(spawn_stream(iter))
+    }
+}
+
+impl From<BlockStore> for AsyncBlockStore {
+    fn from(store: BlockStore) -> Self {
+        Self::new(store)
+    }
+}
+
+fn spawn_stream<I>(iter: I) -> impl Stream<Item = Result<Value, Error>>
+where
+    I: Iterator<Item = Result<Value, Error>> + Send + 'static,
+{
+    let (mut sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+    tokio::task::spawn_blocking(move || {
+        for item in iter {
+            // the only possible error is the stream being dropped
+            if futures::executor::block_on(sender.send(item)).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{Block, BlockId};
+    use futures::StreamExt;
+
+    async fn prepare_store(n: usize) -> (AsyncBlockStore, Vec<Block>) {
+        let store =
+            AsyncBlockStore::new(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap());
+        let mut blocks = vec![Block::genesis(None)];
+        for _ in 1..n {
+            blocks.push(blocks.last().unwrap().make_child(None));
+        }
+        for block in blocks.iter() {
+            let block_info = BlockInfo::new(
+                block.id.serialize_as_vec(),
+                block.parent.serialize_as_vec(),
+                block.chain_length,
+            );
+            store
+                .put_block(block.serialize_as_vec(), block_info)
+                .await
+                .unwrap();
+        }
+        (store, blocks)
+    }
+
+    #[tokio::test]
+    async fn read_write() {
+        let (store, blocks) = prepare_store(8).await;
+
+        for block in blocks.iter() {
+            let block_id = block.id.serialize_as_vec();
+            assert_eq!(
+                block.serialize_as_value(),
+                store.get_block(&block_id).await.unwrap()
+            );
+            assert!(store.block_exists(&block_id).await.unwrap());
+        }
+        assert!(matches!(
+            store.get_block(&BlockId(0).serialize_as_vec()).await,
+            Err(Error::BlockNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn stream_blocks() {
+        const BLOCK_NUM: usize = 64;
+
+        let (store, blocks) = prepare_store(BLOCK_NUM).await;
+        store
+            .flush_to_permanent_store(&blocks[BLOCK_NUM / 2].id.serialize_as_vec(), 1)
+            .await
+            .unwrap();
+
+        let tip_id = blocks.last().unwrap().id.serialize_as_vec();
+        let streamed: Vec<_> = store
+            .stream(&tip_id, BLOCK_NUM as u32)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let expected: Vec<_> = blocks.iter().map(Block::serialize_as_value).collect();
+        assert_eq!(expected, streamed);
+
+        let streamed: Vec<_> = store
+            .stream_range(&tip_id, 10..20)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(&expected[10..20], &streamed[..]);
+
+        // dropping the stream early stops reading
+        let first = store
+            .stream(&tip_id, BLOCK_NUM as u32)
+            .await
+            .unwrap()
+            .next()
+            .await;
+        assert_eq!(expected[0], first.unwrap().unwrap());
+    }
+}
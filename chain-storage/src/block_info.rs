@@ -1,6 +1,42 @@
 use crate::{Error, Value};
 use std::io::{Read, Write};
 
+/// The date of a block in the block chain: the epoch, and the slot within
+/// this epoch. It is only used for indexing and the storage does not check
+/// that the dates are consistent with the chain lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockDate {
+    pub epoch: u32,
+    pub slot_id: u32,
+}
+
+impl BlockDate {
+    pub(crate) const SIZE: usize = 8;
+
+    pub fn new(epoch: u32, slot_id: u32) -> Self {
+        Self { epoch, slot_id }
+    }
+
+    // big endian, so that the keys of the date index are sorted by date
+    pub(crate) fn serialize(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..4].copy_from_slice(&self.epoch.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.slot_id.to_be_bytes());
+        bytes
+    }
+
+    pub(crate) fn deserialize(bytes: &[u8]) -> Self {
+        let mut epoch_bytes = [0u8; 4];
+        epoch_bytes.copy_from_slice(&bytes[..4]);
+        let mut slot_id_bytes = [0u8; 4];
+        slot_id_bytes.copy_from_slice(&bytes[4..Self::SIZE]);
+        Self {
+            epoch: u32::from_be_bytes(epoch_bytes),
+            slot_id: u32::from_be_bytes(slot_id_bytes),
+        }
+    }
+}
+
 /// A structure that holds the information about a blocks, that is needed to
 /// maintain consistency of the storage. This include the ID of the blocks, the
 /// ID of its parent and the length of the block chain for the given block.
@@ -9,6 +45,8 @@ pub struct BlockInfo {
     id: Value,
     parent_id: Value,
     chain_length: u32,
+    // Only used to index the block when it is written, see `with_date`.
+    date: Option<BlockDate>,
     // These two fields are used internally by the volatile storage only. Their
     // purpose is to store the number of blocks that maintain this block as a
     // parent + the number of tags for this block. A block CANNOT be removed
@@ -27,11 +65,24 @@ impl BlockInfo {
             id: id.into(),
             parent_id: parent_id.into(),
             chain_length,
+            date: None,
             parent_ref_count: 0,
             tags_ref_count: 0,
         }
     }
 
+    /// Set the date of the block, so that it can be looked up by date once
+    /// written to the storage. The date is not stored as a part of the block
+    /// information, use `BlockStore::get_block_date` to read it back.
+    pub fn with_date(mut self, date: BlockDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub(crate) fn date(&self) -> Option<BlockDate> {
+        self.date
+    }
+
     pub fn id(&self) -> &Value {
         &self.id
     }
@@ -114,6 +165,7 @@ impl BlockInfo {
             id: id.into(),
             parent_id: parent_id.into(),
             chain_length,
+            date: None,
             parent_ref_count,
             tags_ref_count,
         })
@@ -0,0 +1,161 @@
+use crate::{BlockInfo, BlockStore, Error, Value};
+use std::ops::Range;
+
+/// Iterator over the blocks returned by the iteration methods of
+/// `BlockStorage`.
+pub type BlockIterator = Box<dyn Iterator<Item = Result<Value, Error>>>;
+
+/// The operations of a block storage, implemented by `BlockStore` and by the
+/// pure in-memory `MemoryBlockStore`, so that higher layers can be tested
+/// without a database. See `BlockStore` for the documentation of the
+/// operations.
+pub trait BlockStorage {
+    fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error>;
+
+    fn get_block(&self, block_id: &[u8]) -> Result<Value, Error>;
+
+    fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error>;
+
+    fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error>;
+
+    fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error>;
+
+    fn create_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error>;
+
+    fn move_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error>;
+
+    fn delete_tag(&self, tag_name: &str) -> Result<(), Error>;
+
+    fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error>;
+
+    fn get_tags(&self) -> Result<Vec<(String, Value)>, Error>;
+
+    fn get_tips_ids(&self) -> Result<Vec<Value>, Error>;
+
+    fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error>;
+
+    fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error>;
+
+    fn is_ancestor(&self, ancestor_id: &[u8], descendant_id: &[u8]) -> Result<Option<u32>, Error>;
+
+    fn get_nth_ancestor(&self, block_id: &[u8], distance: u32) -> Result<BlockInfo, Error>;
+
+    fn find_lowest_common_ancestor(
+        &self,
+        block1: &[u8],
+        block2: &[u8],
+    ) -> Result<Option<BlockInfo>, Error>;
+
+    fn flush_to_permanent_store(&self, to_block: &[u8], min_number: usize) -> Result<usize, Error>;
+
+    fn iter(&self, to_block: &[u8], distance: u32) -> Result<BlockIterator, Error>;
+
+    fn iter_reverse(&self, from_block: &[u8], max_distance: u32) -> Result<BlockIterator, Error>;
+
+    fn iter_range(&self, tip_id: &[u8], chain_lengths: Range<u32>) -> Result<BlockIterator, Error> {
+        let tip = self.get_block_info(tip_id)?;
+        let end = std::cmp::min(chain_lengths.end, tip.chain_length() + 1);
+
+        if chain_lengths.start >= end {
+            return self.iter(tip_id, 0);
+        }
+
+        let to_block = self.get_nth_ancestor(tip_id, tip.chain_length() + 1 - end)?;
+        self.iter(to_block.id().as_ref(), end - chain_lengths.start)
+    }
+
+    fn iter_range_by_tag(
+        &self,
+        tag_name: &str,
+        chain_lengths: Range<u32>,
+    ) -> Result<BlockIterator, Error> {
+        let tip_id = self.get_tag(tag_name)?.ok_or(Error::TagNotFound)?;
+        self.iter_range(tip_id.as_ref(), chain_lengths)
+    }
+}
+
+impl BlockStorage for BlockStore {
+    fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error> {
+        BlockStore::put_block(self, block, block_info)
+    }
+
+    fn get_block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        BlockStore::get_block(self, block_id)
+    }
+
+    fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
+        BlockStore::get_block_info(self, block_id)
+    }
+
+    fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
+        BlockStore::get_blocks_by_chain_length(self, chain_length)
+    }
+
+    fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        BlockStore::put_tag(self, tag_name, block_id)
+    }
+
+    fn create_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        BlockStore::create_tag(self, tag_name, block_id)
+    }
+
+    fn move_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        BlockStore::move_tag(self, tag_name, block_id)
+    }
+
+    fn delete_tag(&self, tag_name: &str) -> Result<(), Error> {
+        BlockStore::delete_tag(self, tag_name)
+    }
+
+    fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
+        BlockStore::get_tag(self, tag_name)
+    }
+
+    fn get_tags(&self) -> Result<Vec<(String, Value)>, Error> {
+        BlockStore::get_tags(self)
+    }
+
+    fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
+        BlockStore::get_tips_ids(self)
+    }
+
+    fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error> {
+        BlockStore::prune_branch(self, tip_id)
+    }
+
+    fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error> {
+        BlockStore::block_exists(self, block_id)
+    }
+
+    fn is_ancestor(&self, ancestor_id: &[u8], descendant_id: &[u8]) -> Result<Option<u32>, Error> {
+        BlockStore::is_ancestor(self, ancestor_id, descendant_id)
+    }
+
+    fn get_nth_ancestor(&self, block_id: &[u8], distance: u32) -> Result<BlockInfo, Error> {
+        BlockStore::get_nth_ancestor(self, block_id, distance)
+    }
+
+    fn find_lowest_common_ancestor(
+        &self,
+        block1: &[u8],
+        block2: &[u8],
+    ) -> Result<Option<BlockInfo>, Error> {
+        BlockStore::find_lowest_common_ancestor(self, block1, block2)
+    }
+
+    fn flush_to_permanent_store(&self, to_block: &[u8], min_number: usize) -> Result<usize, Error> {
+        BlockStore::flush_to_permanent_store(self, to_block, min_number)
+    }
+
+    fn iter(&self, to_block: &[u8], distance: u32) -> Result<BlockIterator, Error> {
+        Ok(Box::new(BlockStore::iter(self, to_block, distance)?))
+    }
+
+    fn iter_reverse(&self, from_block: &[u8], max_distance: u32) -> Result<BlockIterator, Error> {
+        Ok(Box::new(BlockStore::iter_reverse(
+            self,
+            from_block,
+            max_distance,
+        )?))
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    permanent_store::PermanentStore, BlockInfo, ConsistencyFailure, Error, StorageIterator, Value,
+    archive, permanent_store::PermanentStore, BlockDate, BlockInfo, ConsistencyFailure, Error,
+    ReverseStorageIterator, StorageIterator, Value,
 };
 use sled::{
     transaction::{
@@ -7,7 +8,11 @@ use sled::{
     },
     Tree,
 };
-use std::path::Path;
+use std::{
+    io::{Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 #[derive(Clone)]
 pub struct BlockStore {
@@ -20,9 +25,31 @@ pub struct BlockStore {
     chain_length_index_tree: Tree,
     branches_tips_tree: Tree,
     tags_tree: Tree,
+    dates_tree: Tree,
+    date_index_tree: Tree,
 
     // needs to be kept so that the database is always closed correctly
-    _db: sled::Db,
+    db: sled::Db,
+    // `None` for the in-memory storage
+    volatile_path: Option<PathBuf>,
+}
+
+// How a tag is written, depending on whether it already exists.
+#[derive(Clone, Copy)]
+pub(crate) enum TagWrite {
+    Put,
+    Create,
+    Move,
+}
+
+/// What was done by `BlockStore::compact`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// Number of dead branches removed from the volatile storage
+    pub pruned_branches: usize,
+    /// Number of bytes of disk space reclaimed, as far as it could be
+    /// measured once the storage was written to disk
+    pub reclaimed_bytes: u64,
 }
 
 enum RemoveTipResult {
@@ -60,6 +87,9 @@ mod tree {
     // Correspondence between IDs and chain lengths of blocks stored in the
     // permanent storage.
     pub const PERMANENT_STORE_BLOCKS: &str = "permanent_store";
+    // Information about the permanent storage as a whole, e.g. up to which
+    // chain length its blocks were pruned.
+    pub const PERMANENT_STORE_METADATA: &str = "permanent_store_metadata";
     // Block information (see `BlockInfo`) for volatile storage.
     pub const INFO: &str = "info";
     // Maintains conversion from chain length to block IDs. This tree has empty
@@ -74,6 +104,13 @@ mod tree {
     pub const BRANCHES_TIPS: &str = "branches_tips";
     // Converts a tag name to a block ID.
     pub const TAGS: &str = "tags";
+    // Dates of the blocks, for the blocks written with a date.
+    pub const DATES: &str = "dates";
+    // Maintains conversion from block dates to block IDs, in the same way as
+    // `CHAIN_LENGTH_INDEX`: keys are in the form of `bytes(date) ++ block_id`.
+    // Unlike the chain length index, it covers the blocks of both the volatile
+    // and the permanent storage.
+    pub const DATE_INDEX: &str = "date_to_block_ids";
 }
 
 impl BlockStore {
@@ -96,12 +133,15 @@ impl BlockStore {
         let volatile_path = path.as_ref().join("volatile");
         let permanent_path = path.as_ref().join("permanent");
 
-        let volatile = sled::open(volatile_path)?;
+        finish_volatile_rewrite(&volatile_path)?;
+        let volatile = sled::open(&volatile_path)?;
 
         let block_id_index = volatile.open_tree(tree::PERMANENT_STORE_BLOCKS)?;
-        let permanent = PermanentStore::file(permanent_path, block_id_index, root_id.clone())?;
+        let metadata = volatile.open_tree(tree::PERMANENT_STORE_METADATA)?;
+        let permanent =
+            PermanentStore::file(permanent_path, block_id_index, metadata, root_id.clone())?;
 
-        Self::new(root_id, volatile, permanent)
+        Self::new(root_id, volatile, Some(volatile_path), permanent)
     }
 
     /// Open a temporary in-memory database.
@@ -116,14 +156,16 @@ impl BlockStore {
             .open()
             .map_err(|err| Error::Open(err.into()))?;
         let block_id_index = volatile.open_tree(tree::PERMANENT_STORE_BLOCKS)?;
-        let permanent = PermanentStore::memory(block_id_index, root_id.clone())?;
+        let metadata = volatile.open_tree(tree::PERMANENT_STORE_METADATA)?;
+        let permanent = PermanentStore::memory(block_id_index, metadata, root_id.clone())?;
 
-        Self::new(root_id, volatile, permanent)
+        Self::new(root_id, volatile, None, permanent)
     }
 
     fn new<I: Into<Value>>(
         root_id: I,
         volatile: sled::Db,
+        volatile_path: Option<PathBuf>,
         permanent: PermanentStore,
     ) -> Result<Self, Error> {
         let root_id = root_id.into();
@@ -134,6 +176,8 @@ impl BlockStore {
         let chain_length_index_tree = volatile.open_tree(tree::CHAIN_LENGTH_INDEX)?;
         let branches_tips_tree = volatile.open_tree(tree::BRANCHES_TIPS)?;
         let tags_tree = volatile.open_tree(tree::TAGS)?;
+        let dates_tree = volatile.open_tree(tree::DATES)?;
+        let date_index_tree = volatile.open_tree(tree::DATE_INDEX)?;
 
         Ok(Self {
             permanent,
@@ -145,8 +189,11 @@ impl BlockStore {
             chain_length_index_tree,
             branches_tips_tree,
             tags_tree,
+            dates_tree,
+            date_index_tree,
 
-            _db: volatile,
+            db: volatile,
+            volatile_path,
         })
     }
 
@@ -157,7 +204,8 @@ impl BlockStore {
     ///
     /// * `block` - a serialized representation of a block.
     /// * `block_info` - block metadata for internal needs (indexing, linking
-    ///   between blocks, etc)
+    ///   between blocks, etc). If it has a date (see `BlockInfo::with_date`),
+    ///   the block can be looked up by date.
     pub fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error> {
         if self.block_exists(block_info.id().as_ref())? {
             return Err(Error::BlockAlreadyPresent);
@@ -172,20 +220,26 @@ impl BlockStore {
             &self.info_tree,
             &self.chain_length_index_tree,
             &self.branches_tips_tree,
+            &self.dates_tree,
+            &self.date_index_tree,
         )
-            .transaction(|(blocks, info, chain_length_to_block_ids, tips)| {
-                put_block_impl(
-                    blocks,
-                    info,
-                    chain_length_to_block_ids,
-                    tips,
-                    block,
-                    &block_info,
-                    self.root_id.as_ref(),
-                    self.id_length,
-                    parent_in_permanent_store,
-                )
-            })
+            .transaction(
+                |(blocks, info, chain_length_to_block_ids, tips, dates, date_to_block_ids)| {
+                    put_block_impl(
+                        blocks,
+                        info,
+                        chain_length_to_block_ids,
+                        tips,
+                        dates,
+                        date_to_block_ids,
+                        block,
+                        &block_info,
+                        self.root_id.as_ref(),
+                        self.id_length,
+                        parent_in_permanent_store,
+                    )
+                },
+            )
             .map_err(Into::into)
     }
 
@@ -235,7 +289,7 @@ impl BlockStore {
     /// chain length in the permanent storage, only this block is returned.
     /// Other branches are considered to be ready of removal if there are any.
     pub fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
-        if let Some(block) = self.permanent.get_block_by_chain_length(chain_length) {
+        if let Some(block) = self.permanent.get_block_by_chain_length(chain_length)? {
             return Ok(vec![block]);
         }
 
@@ -253,9 +307,92 @@ impl BlockStore {
             .map_err(Into::into)
     }
 
+    /// Get the date of the block, if it was written with one.
+    pub fn get_block_date(&self, block_id: &[u8]) -> Result<Option<BlockDate>, Error> {
+        self.dates_tree
+            .get(block_id)
+            .map(|maybe_date| maybe_date.map(|date| BlockDate::deserialize(&date)))
+            .map_err(Into::into)
+    }
+
+    /// Get the serialized blocks with the given date, among the blocks written
+    /// with a date. As with `get_blocks_by_chain_length`, if there is a block
+    /// with this date in the permanent storage, only this block is returned.
+    pub fn get_blocks_by_date(&self, date: BlockDate) -> Result<Vec<Value>, Error> {
+        self.get_blocks_by_date_prefix(&date.serialize())
+    }
+
+    /// Get the serialized blocks of the given epoch, ordered by date, among
+    /// the blocks written with a date. The blocks of all the branches are
+    /// returned, except for the dates which have a block in the permanent
+    /// storage (see `get_blocks_by_date`).
+    pub fn blocks_in_epoch(&self, epoch: u32) -> Result<Vec<Value>, Error> {
+        self.get_blocks_by_date_prefix(&epoch.to_be_bytes())
+    }
+
+    fn get_blocks_by_date_prefix(&self, prefix: &[u8]) -> Result<Vec<Value>, Error> {
+        let keys = self
+            .date_index_tree
+            .scan_prefix(prefix)
+            .keys()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut blocks = Vec::new();
+        let mut rest = &keys[..];
+
+        // the keys are sorted, so the blocks with the same date are adjacent
+        while let Some(first) = rest.first() {
+            let date = &first[..BlockDate::SIZE];
+            let same_date_count = rest
+                .iter()
+                .take_while(|key| &key[..BlockDate::SIZE] == date)
+                .count();
+            let (same_date, tail) = rest.split_at(same_date_count);
+            rest = tail;
+
+            let mut ids = Vec::with_capacity(same_date.len());
+            let mut permanent_id = None;
+            for key in same_date {
+                let id = block_id_from_date_index(key);
+                if self.permanent.contains_key(id)? {
+                    permanent_id = Some(id);
+                    break;
+                }
+                ids.push(id);
+            }
+
+            match permanent_id {
+                Some(id) => blocks.push(self.get_block(id)?),
+                None => {
+                    for id in ids {
+                        blocks.push(self.get_block(id)?);
+                    }
+                }
+            }
+        }
+
+        Ok(blocks)
+    }
+
     /// Add a tag for a given block. The block id can be later retrieved by this
-    /// tag.
+    /// tag. If the tag already exists, it is moved to the given block.
     pub fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Put)
+    }
+
+    /// Add a new tag for a given block, failing with `TagAlreadyExists` if
+    /// there is already a tag with this name.
+    pub fn create_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Create)
+    }
+
+    /// Move an existing tag to the given block, e.g. when the tip of a branch
+    /// changes. Fails with `TagNotFound` if there is no tag with this name.
+    pub fn move_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Move)
+    }
+
+    fn write_tag(&self, tag_name: &str, block_id: &[u8], mode: TagWrite) -> Result<(), Error> {
         let permanent_store_index = self.permanent.block_id_index();
 
         (&self.info_tree, &self.tags_tree, permanent_store_index)
@@ -267,11 +404,54 @@ impl BlockStore {
                     tag_name,
                     block_id,
                     self.id_length,
+                    mode,
                 )
             })
             .map_err(Into::into)
     }
 
+    /// Remove a tag. The block it referred to can then be removed along with
+    /// its branch. Fails with `TagNotFound` if there is no tag with this name.
+    pub fn delete_tag(&self, tag_name: &str) -> Result<(), Error> {
+        let permanent_store_index = self.permanent.block_id_index();
+
+        (&self.info_tree, &self.tags_tree, permanent_store_index)
+            .transaction(move |(info, tags, permanent_store_index)| {
+                let block_id = tags.remove(tag_name)?.ok_or(Error::TagNotFound)?;
+                remove_tag_ref(info, permanent_store_index, &block_id, self.id_length)
+            })
+            .map_err(Into::into)
+    }
+
+    /// Get all the tags, with the ID of the block they refer to, ordered by
+    /// tag name.
+    pub fn get_tags(&self) -> Result<Vec<(String, Value)>, Error> {
+        self.tags_tree
+            .iter()
+            .map(|tag_result| {
+                tag_result.map(|(tag_name, block_id)| {
+                    (
+                        String::from_utf8_lossy(&tag_name).into_owned(),
+                        Value::volatile(block_id),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Find the lowest common ancestor of the blocks referred to by two tags,
+    /// e.g. the fork point of two branches. See `find_lowest_common_ancestor`.
+    pub fn find_tags_common_ancestor(
+        &self,
+        tag_name1: &str,
+        tag_name2: &str,
+    ) -> Result<Option<BlockInfo>, Error> {
+        let block1 = self.get_tag(tag_name1)?.ok_or(Error::TagNotFound)?;
+        let block2 = self.get_tag(tag_name2)?.ok_or(Error::TagNotFound)?;
+        self.find_lowest_common_ancestor(block1.as_ref(), block2.as_ref())
+    }
+
     /// Get the block ID for the given tag.
     pub fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
         self.tags_tree
@@ -302,10 +482,20 @@ impl BlockStore {
             &self.info_tree,
             &self.chain_length_index_tree,
             &self.branches_tips_tree,
+            &self.dates_tree,
+            &self.date_index_tree,
             permanent_store_index,
         )
             .transaction(
-                |(blocks, info, chain_length_to_block_ids, tips, permanent_store_index)| {
+                |(
+                    blocks,
+                    info,
+                    chain_length_to_block_ids,
+                    tips,
+                    dates,
+                    date_to_block_ids,
+                    permanent_store_index,
+                )| {
                     let mut result = RemoveTipResult::NextTip {
                         id: Vec::from(tip_id),
                     };
@@ -316,6 +506,8 @@ impl BlockStore {
                             info,
                             chain_length_to_block_ids,
                             tips,
+                            dates,
+                            date_to_block_ids,
                             permanent_store_index,
                             id,
                             self.root_id.as_ref(),
@@ -637,6 +829,190 @@ impl BlockStore {
         Ok(block_infos.len())
     }
 
+    /// Remove the blocks below the given chain length from the permanent
+    /// storage to reclaim disk space, e.g. to only keep a given depth of the
+    /// chain. Only blocks in the permanent storage are pruned, the volatile
+    /// storage is left untouched.
+    ///
+    /// The metadata of the pruned blocks is kept, so that the chain can still
+    /// be navigated, and so is the part of each block selected by `retain`,
+    /// e.g. the block header. This part is then returned instead of the whole
+    /// block when reading it. If nothing is retained (`retain` returns an
+    /// empty slice), reading the block fails with `Error::BlockPruned`.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks that were pruned.
+    pub fn prune_before<F>(&self, chain_length: u32, retain: F) -> Result<usize, Error>
+    where
+        F: FnMut(&[u8]) -> &[u8],
+    {
+        self.permanent.prune_before(chain_length, retain)
+    }
+
+    /// The chain length below which the blocks were pruned by `prune_before`,
+    /// 0 if no block was pruned.
+    pub fn pruned_before(&self) -> Result<u32, Error> {
+        self.permanent.pruned_before()
+    }
+
+    /// Write the blocks from `from` to `to` (both included) to a portable
+    /// archive, along with their metadata. `from` has to be an ancestor of
+    /// `to`, and fails with `Error::BlockPruned` if `from` was pruned by
+    /// `prune_before`. The writer is not buffered by this function.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks written to the archive.
+    pub fn export<W: Write>(&self, writer: W, from: &[u8], to: &[u8]) -> Result<u64, Error> {
+        archive::export(self, writer, from, to)
+    }
+
+    /// Write the blocks of an archive produced by `export` to the store. The
+    /// parent of the first block of the archive must exist, and the blocks
+    /// already present in the store are skipped. The checksums of the archive
+    /// are verified as it is read, so an invalid archive can still result in
+    /// some of its blocks being written.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks written to the store.
+    pub fn import<R: Read>(&self, reader: R) -> Result<u64, Error> {
+        archive::import(self, reader)
+    }
+
+    pub(crate) fn id_length(&self) -> usize {
+        self.id_length
+    }
+
+    /// Remove the dead branches from the volatile storage and rewrite the
+    /// storage files to reclaim the space left by the removed data. The
+    /// permanent storage is only rewritten if some branches were removed.
+    ///
+    /// A branch is dead when it forks before the last block of the permanent
+    /// storage, and thus can never be moved to the permanent storage. Dead
+    /// branches are removed as with `prune_branch`, so the tagged blocks and
+    /// their ancestors are kept.
+    ///
+    /// The volatile storage is replaced, so this fails with `StoreInUse` if
+    /// there are other handles to the storage, including iterators.
+    pub fn compact(&mut self) -> Result<CompactionStats, Error> {
+        if self.permanent.handle_count() > 1 {
+            return Err(Error::StoreInUse);
+        }
+
+        let size_before = self.size_on_disk()?;
+
+        let permanent_block_count = self.permanent.block_count();
+        let mut pruned_branches = 0;
+
+        for tip_id in self.get_tips_ids()? {
+            if self.is_dead_branch(tip_id.as_ref(), permanent_block_count)? {
+                self.prune_branch(tip_id.as_ref())?;
+                if !self.block_exists(tip_id.as_ref())? {
+                    pruned_branches += 1;
+                }
+            }
+        }
+
+        if pruned_branches > 0 {
+            self.permanent.defragment()?;
+        }
+        self.rewrite_volatile()?;
+
+        let size_after = self.size_on_disk()?;
+
+        Ok(CompactionStats {
+            pruned_branches,
+            reclaimed_bytes: size_before.saturating_sub(size_after),
+        })
+    }
+
+    // Copy the trees of the volatile storage to a new database which then
+    // replaces it, leaving out the space of the removed data.
+    fn rewrite_volatile(&mut self) -> Result<(), Error> {
+        let volatile = match &self.volatile_path {
+            Some(path) => {
+                let rewrite_path = path.with_extension("rewrite");
+                if rewrite_path.exists() {
+                    std::fs::remove_dir_all(&rewrite_path).map_err(Error::VolatileStoreReplace)?;
+                }
+                let new_volatile = sled::open(&rewrite_path)?;
+                copy_volatile(&self.db, &new_volatile)?;
+                new_volatile.flush()?;
+                drop(new_volatile);
+
+                // the new database is complete once the old one is moved
+                // away, see `finish_volatile_rewrite`
+                std::fs::rename(path, path.with_extension("old"))
+                    .map_err(Error::VolatileStoreReplace)?;
+                std::fs::rename(&rewrite_path, path).map_err(Error::VolatileStoreReplace)?;
+                sled::open(path)?
+            }
+            None => {
+                let new_volatile = sled::Config::new()
+                    .temporary(true)
+                    .open()
+                    .map_err(|err| Error::Open(err.into()))?;
+                copy_volatile(&self.db, &new_volatile)?;
+                new_volatile
+            }
+        };
+
+        let block_id_index = volatile.open_tree(tree::PERMANENT_STORE_BLOCKS)?;
+        let metadata = volatile.open_tree(tree::PERMANENT_STORE_METADATA)?;
+        let permanent = self.permanent.with_volatile(block_id_index, metadata);
+
+        let volatile_path = self.volatile_path.take();
+        *self = Self::new(
+            self.root_id.clone(),
+            volatile,
+            volatile_path.clone(),
+            permanent,
+        )?;
+
+        if let Some(path) = volatile_path {
+            std::fs::remove_dir_all(path.with_extension("old"))
+                .map_err(Error::VolatileStoreReplace)?;
+        }
+
+        Ok(())
+    }
+
+    // Walk down the branch to the block it is attached to in the permanent
+    // storage, which has to be the last one for the branch to be alive.
+    fn is_dead_branch(&self, tip_id: &[u8], permanent_block_count: u32) -> Result<bool, Error> {
+        let last_permanent = match permanent_block_count.checked_sub(1) {
+            Some(chain_length) => chain_length,
+            None => return Ok(false),
+        };
+
+        if self.permanent.contains_key(tip_id)? {
+            return Ok(false);
+        }
+
+        let mut block_info = self.get_block_info_volatile(tip_id)?;
+        loop {
+            if block_info.chain_length() <= last_permanent {
+                return Ok(true);
+            }
+
+            let parent_id = block_info.parent_id().as_ref();
+            if let Some(parent_info) = self.permanent.get_block_info(parent_id)? {
+                return Ok(parent_info.chain_length() != last_permanent);
+            }
+            if parent_id == self.root_id.as_ref() {
+                return Ok(true);
+            }
+
+            block_info = self.get_block_info_volatile(parent_id)?;
+        }
+    }
+
+    fn size_on_disk(&self) -> Result<u64, Error> {
+        Ok(self.db.size_on_disk()? + self.permanent.blocks_file_size()?)
+    }
+
     /// Iterate to the given block starting from the block at the given
     /// `distance - 1`. `distance == 1` means that only `to_block` will be
     /// iterated. `distance == 0` means empty iterator.
@@ -653,6 +1029,97 @@ impl BlockStore {
             self.blocks_tree.clone(),
         )
     }
+
+    /// Iterate over the blocks from the given block backwards, to the first
+    /// block of the chain or until `max_distance` blocks were returned.
+    /// `max_distance == 1` means that only `from_block` will be iterated.
+    pub fn iter_reverse(
+        &self,
+        from_block: &[u8],
+        max_distance: u32,
+    ) -> Result<ReverseStorageIterator, Error> {
+        if !self.block_exists(from_block)? {
+            return Err(Error::BlockNotFound);
+        }
+
+        Ok(ReverseStorageIterator::new(
+            Value::from(from_block.to_vec()),
+            max_distance,
+            self.root_id.clone(),
+            self.permanent.clone(),
+            self.info_tree.clone(),
+            self.blocks_tree.clone(),
+        ))
+    }
+
+    /// Iterate over the blocks of the branch ending with `tip_id`, with chain
+    /// lengths in the given range, in ascending order of chain length.
+    ///
+    /// The range is truncated to the chain length of the tip, so streaming the
+    /// blocks up to the tip does not require knowing its chain length first.
+    pub fn iter_range(
+        &self,
+        tip_id: &[u8],
+        chain_lengths: Range<u32>,
+    ) -> Result<impl Iterator<Item = Result<Value, Error>>, Error> {
+        let tip = self.get_block_info(tip_id)?;
+        let end = std::cmp::min(chain_lengths.end, tip.chain_length() + 1);
+
+        if chain_lengths.start >= end {
+            return self.iter(tip_id, 0);
+        }
+
+        let to_block = self.get_nth_ancestor(tip_id, tip.chain_length() + 1 - end)?;
+        self.iter(to_block.id().as_ref(), end - chain_lengths.start)
+    }
+
+    /// Iterate over the blocks of the branch whose tip is tagged with
+    /// `tag_name`, with chain lengths in the given range. See `iter_range`.
+    pub fn iter_range_by_tag(
+        &self,
+        tag_name: &str,
+        chain_lengths: Range<u32>,
+    ) -> Result<impl Iterator<Item = Result<Value, Error>>, Error> {
+        let tip_id = self.get_tag(tag_name)?.ok_or(Error::TagNotFound)?;
+        self.iter_range(tip_id.as_ref(), chain_lengths)
+    }
+}
+
+// Copy all the trees of a volatile database to an empty one.
+fn copy_volatile(from: &sled::Db, to: &sled::Db) -> Result<(), Error> {
+    let replace_error = |err: sled::Error| Error::VolatileStoreReplace(err.into());
+
+    for name in from.tree_names() {
+        let from_tree = from.open_tree(&name).map_err(replace_error)?;
+        let to_tree = to.open_tree(&name).map_err(replace_error)?;
+        for entry in from_tree.iter() {
+            let (key, value) = entry.map_err(replace_error)?;
+            to_tree.insert(key, value).map_err(replace_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Complete or roll back a rewrite of the volatile storage interrupted by a
+// crash. The rewritten database is only complete once the old one has been
+// moved away.
+fn finish_volatile_rewrite(path: &Path) -> Result<(), Error> {
+    let rewrite_path = path.with_extension("rewrite");
+    let old_path = path.with_extension("old");
+
+    if rewrite_path.exists() {
+        if path.exists() {
+            std::fs::remove_dir_all(&rewrite_path).map_err(Error::VolatileStoreReplace)?;
+        } else {
+            std::fs::rename(&rewrite_path, path).map_err(Error::VolatileStoreReplace)?;
+        }
+    }
+    if old_path.exists() {
+        std::fs::remove_dir_all(&old_path).map_err(Error::VolatileStoreReplace)?;
+    }
+
+    Ok(())
 }
 
 #[inline]
@@ -662,6 +1129,8 @@ fn put_block_impl(
     info: &TransactionalTree,
     chain_length_to_block_ids: &TransactionalTree,
     tips: &TransactionalTree,
+    dates: &TransactionalTree,
+    date_to_block_ids: &TransactionalTree,
     block: &[u8],
     block_info: &BlockInfo,
     root_id: &[u8],
@@ -702,6 +1171,11 @@ fn put_block_impl(
         &[],
     )?;
 
+    if let Some(date) = block_info.date() {
+        dates.insert(block_info.id().as_ref(), &date.serialize()[..])?;
+        date_to_block_ids.insert(build_date_index(date, block_info.id().as_ref()), &[])?;
+    }
+
     blocks.insert(block_info.id().as_ref(), block)?;
 
     info.insert(block_info.id().as_ref(), block_info.serialize()?)?;
@@ -717,6 +1191,7 @@ fn put_tag_impl(
     tag_name: &str,
     block_id: &[u8],
     id_size: usize,
+    mode: TagWrite,
 ) -> Result<(), ConflictableTransactionError<Error>> {
     if let Some(info_bin) = info.get(block_id)? {
         let mut block_info = BlockInfo::deserialize(&info_bin[..], id_size, block_id.to_vec())?;
@@ -732,14 +1207,36 @@ fn put_tag_impl(
 
     let maybe_old_block_id = tags.insert(tag_name, block_id)?;
 
+    match (mode, &maybe_old_block_id) {
+        (TagWrite::Create, Some(_)) => return Err(Error::TagAlreadyExists.into()),
+        (TagWrite::Move, None) => return Err(Error::TagNotFound.into()),
+        _ => (),
+    }
+
     if let Some(old_block_id) = maybe_old_block_id {
-        let info_bin = info
-            .get(old_block_id.clone())?
-            .ok_or(ConsistencyFailure::TaggedBlock)?;
-        let mut block_info = BlockInfo::deserialize(&info_bin[..], id_size, old_block_id.to_vec())?;
-        block_info.remove_tag_ref();
-        let info_bin = block_info.serialize()?;
-        info.insert(block_info.id().as_ref(), info_bin)?;
+        remove_tag_ref(info, permanent_store_index, &old_block_id, id_size)?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn remove_tag_ref(
+    info: &TransactionalTree,
+    permanent_store_index: &TransactionalTree,
+    block_id: &[u8],
+    id_size: usize,
+) -> Result<(), ConflictableTransactionError<Error>> {
+    match info.get(block_id)? {
+        Some(info_bin) => {
+            let mut block_info = BlockInfo::deserialize(&info_bin[..], id_size, block_id.to_vec())?;
+            block_info.remove_tag_ref();
+            let info_bin = block_info.serialize()?;
+            info.insert(block_info.id().as_ref(), info_bin)?;
+        }
+        // blocks of the permanent storage are not reference counted
+        None if permanent_store_index.get(block_id)?.is_some() => (),
+        None => return Err(ConsistencyFailure::TaggedBlock.into()),
     }
 
     Ok(())
@@ -752,6 +1249,8 @@ fn remove_tip_impl(
     info: &TransactionalTree,
     chain_length_to_block_ids: &TransactionalTree,
     tips: &TransactionalTree,
+    dates: &TransactionalTree,
+    date_to_block_ids: &TransactionalTree,
     permanent_store_index: &TransactionalTree,
     block_id: &[u8],
     root_id: &[u8],
@@ -783,6 +1282,10 @@ fn remove_tip_impl(
 
     tips.remove(block_id)?;
 
+    if let Some(date) = dates.remove(block_id)? {
+        date_to_block_ids.remove(build_date_index(BlockDate::deserialize(&date), block_id))?;
+    }
+
     if block_info.parent_id().as_ref() == root_id {
         return Ok(RemoveTipResult::Done);
     }
@@ -845,3 +1348,15 @@ fn build_chain_length_index(chain_length: u32, block_id: &[u8]) -> Vec<u8> {
 fn block_id_from_chain_length_index(index: &[u8]) -> &[u8] {
     &index[std::mem::size_of::<u32>()..]
 }
+
+#[inline]
+fn build_date_index(date: BlockDate, block_id: &[u8]) -> Vec<u8> {
+    let mut date_index = date.serialize().to_vec();
+    date_index.extend_from_slice(block_id);
+    date_index
+}
+
+#[inline]
+fn block_id_from_date_index(index: &[u8]) -> &[u8] {
+    &index[BlockDate::SIZE..]
+}
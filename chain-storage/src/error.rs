@@ -16,6 +16,10 @@ pub enum Error {
     MissingParent,
     #[error("branch with the requested tip does not exist")]
     BranchNotFound,
+    #[error("tag not found")]
+    TagNotFound,
+    #[error("tag already exists")]
+    TagAlreadyExists,
     #[error("failed to serialize block metadata")]
     BlockInfoSerialize(#[source] std::io::Error),
     #[error("failed to deserialize block metadata")]
@@ -26,6 +30,24 @@ pub enum Error {
         "cannot iterate over blocks because the provided distance is bigger than the chain length"
     )]
     CannotIterate,
+    #[error("the block was pruned from the storage")]
+    BlockPruned,
+    #[error("failed to replace the blocks file of the permanent store")]
+    PermanentStoreReplace(#[source] std::io::Error),
+    #[error("failed to replace the volatile store")]
+    VolatileStoreReplace(#[source] std::io::Error),
+    #[error("the storage is used by other handles")]
+    StoreInUse,
+    #[error("the first block of the range is not an ancestor of the last one")]
+    NotAncestor,
+    #[error("failed to read or write the block archive")]
+    ArchiveIo(#[source] std::io::Error),
+    #[error("invalid block archive: {0}")]
+    InvalidArchive(&'static str),
+    #[error("checksum mismatch in the block archive")]
+    ArchiveChecksum,
+    #[error("the storage operation was cancelled")]
+    TaskCancelled,
 }
 
 #[derive(Debug, Error)]
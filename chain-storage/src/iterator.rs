@@ -14,6 +14,7 @@ enum IteratorState {
         iter: data_pile::SeqNoIter,
         current_length: u32,
         stop_at_length: u32,
+        pruned_before: u32,
     },
     Volatile {
         ids: Vec<Value>,
@@ -42,14 +43,12 @@ impl StorageIterator {
 
         let from_length = to_info.chain_length() + 1 - distance;
 
-        let state = if permanent_store
-            .get_block_by_chain_length(from_length)
-            .is_some()
-        {
+        let state = if permanent_store.contains_chain_length(from_length) {
             IteratorState::Permanent {
                 iter: permanent_store.iter(from_length)?,
                 current_length: from_length,
                 stop_at_length: to_info.chain_length(),
+                pruned_before: permanent_store.pruned_before()?,
             }
         } else {
             IteratorState::Volatile {
@@ -75,13 +74,18 @@ impl Iterator for StorageIterator {
                 iter,
                 current_length,
                 stop_at_length,
+                pruned_before,
             } => {
-                if current_length == stop_at_length {
+                if current_length > stop_at_length {
                     return None;
                 }
                 match iter.next() {
                     Some(item) => {
+                        let pruned = item.as_ref().is_empty() && *current_length < *pruned_before;
                         *current_length += 1;
+                        if pruned {
+                            return Some(Err(Error::BlockPruned));
+                        }
                         Some(Ok(Value::permanent(item)))
                     }
                     None => {
@@ -142,3 +146,120 @@ fn gather_blocks_ids(
 
     Ok(ids)
 }
+
+/// Iterator over blocks, from the given block backwards to the first block
+/// of the chain. The volatile storage is walked by following the parent of
+/// each block, and the permanent storage is read directly by chain length.
+pub struct ReverseStorageIterator {
+    state: ReverseIteratorState,
+    remaining: u32,
+    root_id: Value,
+    permanent_store: PermanentStore,
+    block_info: Tree,
+    blocks: Tree,
+}
+
+enum ReverseIteratorState {
+    Volatile { next_id: Value },
+    Permanent { next_length: u32 },
+    Done,
+}
+
+impl ReverseStorageIterator {
+    pub(crate) fn new(
+        from: Value,
+        max_distance: u32,
+        root_id: Value,
+        permanent_store: PermanentStore,
+        block_info: Tree,
+        blocks: Tree,
+    ) -> Self {
+        Self {
+            state: ReverseIteratorState::Volatile { next_id: from },
+            remaining: max_distance,
+            root_id,
+            permanent_store,
+            block_info,
+            blocks,
+        }
+    }
+
+    fn next_block(&mut self) -> Result<Option<Value>, Error> {
+        match &self.state {
+            ReverseIteratorState::Volatile { next_id } => {
+                if next_id == &self.root_id {
+                    self.state = ReverseIteratorState::Done;
+                    return Ok(None);
+                }
+
+                let info_bin = match self.block_info.get(next_id.as_ref())? {
+                    Some(info_bin) => info_bin,
+                    None => {
+                        // the rest of the chain is in the permanent storage
+                        let info = self
+                            .permanent_store
+                            .get_block_info(next_id.as_ref())?
+                            .ok_or(ConsistencyFailure::MissingParentBlock)?;
+                        self.state = ReverseIteratorState::Permanent {
+                            next_length: info.chain_length(),
+                        };
+                        return self.next_block();
+                    }
+                };
+
+                let info = BlockInfo::deserialize(
+                    info_bin.as_ref(),
+                    next_id.as_ref().len(),
+                    next_id.clone(),
+                )?;
+                let block = self
+                    .blocks
+                    .get(next_id.as_ref())?
+                    .ok_or(ConsistencyFailure::BlockInfo)?;
+
+                self.state = ReverseIteratorState::Volatile {
+                    next_id: info.parent_id().clone(),
+                };
+                Ok(Some(Value::volatile(block)))
+            }
+            ReverseIteratorState::Permanent { next_length } => {
+                let next_length = *next_length;
+                let block = self
+                    .permanent_store
+                    .get_block_by_chain_length(next_length)?
+                    .ok_or(ConsistencyFailure::MissingPermanentBlock)?;
+
+                self.state = match next_length.checked_sub(1) {
+                    Some(next_length) => ReverseIteratorState::Permanent { next_length },
+                    None => ReverseIteratorState::Done,
+                };
+                Ok(Some(block))
+            }
+            ReverseIteratorState::Done => Ok(None),
+        }
+    }
+}
+
+impl Iterator for ReverseStorageIterator {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        match self.next_block() {
+            Ok(Some(block)) => {
+                self.remaining -= 1;
+                Some(Ok(block))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.state = ReverseIteratorState::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for ReverseStorageIterator {}
@@ -135,10 +135,15 @@
 //! └── volatile        - volatile storage
 //! ```
 
+mod archive;
+#[cfg(feature = "async")]
+mod async_store;
 mod block_info;
+mod block_storage;
 mod block_store;
 mod error;
 mod iterator;
+mod memory_store;
 mod permanent_store;
 #[cfg(any(test, feature = "with-bench"))]
 pub mod test_utils;
@@ -146,8 +151,12 @@ pub mod test_utils;
 mod tests;
 mod value;
 
-pub use block_info::BlockInfo;
-pub use block_store::BlockStore;
+#[cfg(feature = "async")]
+pub use async_store::AsyncBlockStore;
+pub use block_info::{BlockDate, BlockInfo};
+pub use block_storage::{BlockIterator, BlockStorage};
+pub use block_store::{BlockStore, CompactionStats};
 pub use error::{ConsistencyFailure, Error};
-pub use iterator::StorageIterator;
+pub use iterator::{ReverseStorageIterator, StorageIterator};
+pub use memory_store::MemoryBlockStore;
 pub use value::Value;
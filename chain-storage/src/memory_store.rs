@@ -0,0 +1,470 @@
+use crate::{
+    block_store::TagWrite, BlockInfo, BlockIterator, BlockStorage, ConsistencyFailure, Error, Value,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// A block storage kept entirely in memory, with the same semantics as
+/// `BlockStore` (branches, tags, moving blocks to the permanent storage),
+/// but without any file or database. It is meant for testing the code using
+/// the storage through the `BlockStorage` trait.
+#[derive(Clone)]
+pub struct MemoryBlockStore {
+    root_id: Value,
+    state: Arc<RwLock<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    // the volatile storage
+    blocks: HashMap<Vec<u8>, (Value, BlockInfo)>,
+    chain_length_index: BTreeMap<u32, BTreeSet<Vec<u8>>>,
+    tips: BTreeSet<Vec<u8>>,
+    tags: BTreeMap<String, Vec<u8>>,
+    // the permanent storage, indexed by chain length
+    permanent_blocks: Vec<(Vec<u8>, Value)>,
+    permanent_index: HashMap<Vec<u8>, u32>,
+}
+
+impl MemoryBlockStore {
+    /// Create an empty storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_id` - the ID of the root block which the first block in this
+    ///   block chain should refer to as a parent.
+    pub fn new<I: Into<Value>>(root_id: I) -> Self {
+        Self {
+            root_id: root_id.into(),
+            state: Default::default(),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, MemoryState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, MemoryState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_tag(&self, tag_name: &str, block_id: &[u8], mode: TagWrite) -> Result<(), Error> {
+        let mut state = self.write();
+
+        if !state.permanent_index.contains_key(block_id) && !state.blocks.contains_key(block_id) {
+            return Err(Error::BlockNotFound);
+        }
+
+        match (mode, state.tags.contains_key(tag_name)) {
+            (TagWrite::Create, true) => return Err(Error::TagAlreadyExists),
+            (TagWrite::Move, false) => return Err(Error::TagNotFound),
+            _ => (),
+        }
+
+        if let Some((_, block_info)) = state.blocks.get_mut(block_id) {
+            block_info.add_tag_ref();
+        }
+
+        if let Some(old_block_id) = state.tags.insert(tag_name.to_string(), block_id.to_vec()) {
+            state.remove_tag_ref(&old_block_id);
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoryState {
+    fn permanent_block_info(&self, block_id: &[u8], root_id: &Value) -> Option<BlockInfo> {
+        let chain_length = *self.permanent_index.get(block_id)?;
+        let parent_id = match chain_length.checked_sub(1) {
+            Some(parent_length) => {
+                Value::from(self.permanent_blocks[parent_length as usize].0.clone())
+            }
+            None => root_id.clone(),
+        };
+        Some(BlockInfo::new(block_id.to_vec(), parent_id, chain_length))
+    }
+
+    fn block_info(&self, block_id: &[u8], root_id: &Value) -> Result<BlockInfo, Error> {
+        if let Some(block_info) = self.permanent_block_info(block_id, root_id) {
+            return Ok(block_info);
+        }
+
+        self.blocks
+            .get(block_id)
+            .map(|(_, block_info)| block_info.clone())
+            .ok_or(Error::BlockNotFound)
+    }
+
+    fn block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        if let Some(chain_length) = self.permanent_index.get(block_id) {
+            return Ok(self.permanent_blocks[*chain_length as usize].1.clone());
+        }
+
+        self.blocks
+            .get(block_id)
+            .map(|(block, _)| block.clone())
+            .ok_or(Error::BlockNotFound)
+    }
+
+    fn remove_tag_ref(&mut self, block_id: &[u8]) {
+        // blocks of the permanent storage are not reference counted
+        if let Some((_, block_info)) = self.blocks.get_mut(block_id) {
+            block_info.remove_tag_ref();
+        }
+    }
+
+    fn remove_volatile(&mut self, block_id: &[u8], chain_length: u32) {
+        self.blocks.remove(block_id);
+        if let Some(ids) = self.chain_length_index.get_mut(&chain_length) {
+            ids.remove(block_id);
+            if ids.is_empty() {
+                self.chain_length_index.remove(&chain_length);
+            }
+        }
+    }
+}
+
+impl BlockStorage for MemoryBlockStore {
+    fn put_block(&self, block: &[u8], block_info: BlockInfo) -> Result<(), Error> {
+        let mut state = self.write();
+        let id = block_info.id().as_ref().to_vec();
+        let parent_id = block_info.parent_id().as_ref();
+
+        if state.permanent_index.contains_key(&id) || state.blocks.contains_key(&id) {
+            return Err(Error::BlockAlreadyPresent);
+        }
+
+        if parent_id != self.root_id.as_ref() && !state.permanent_index.contains_key(parent_id) {
+            let (_, parent_info) = state
+                .blocks
+                .get_mut(parent_id)
+                .ok_or(Error::MissingParent)?;
+            parent_info.add_parent_ref();
+        }
+
+        state.tips.remove(parent_id);
+        state.tips.insert(id.clone());
+        state
+            .chain_length_index
+            .entry(block_info.chain_length())
+            .or_default()
+            .insert(id.clone());
+        state
+            .blocks
+            .insert(id, (Value::from(block.to_vec()), block_info));
+
+        Ok(())
+    }
+
+    fn get_block(&self, block_id: &[u8]) -> Result<Value, Error> {
+        self.read().block(block_id)
+    }
+
+    fn get_block_info(&self, block_id: &[u8]) -> Result<BlockInfo, Error> {
+        self.read().block_info(block_id, &self.root_id)
+    }
+
+    fn get_blocks_by_chain_length(&self, chain_length: u32) -> Result<Vec<Value>, Error> {
+        let state = self.read();
+
+        if let Some((_, block)) = state.permanent_blocks.get(chain_length as usize) {
+            return Ok(vec![block.clone()]);
+        }
+
+        state
+            .chain_length_index
+            .get(&chain_length)
+            .into_iter()
+            .flatten()
+            .map(|id| {
+                state
+                    .blocks
+                    .get(id)
+                    .map(|(block, _)| block.clone())
+                    .ok_or_else(|| ConsistencyFailure::ChainLength.into())
+            })
+            .collect()
+    }
+
+    fn put_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Put)
+    }
+
+    fn create_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Create)
+    }
+
+    fn move_tag(&self, tag_name: &str, block_id: &[u8]) -> Result<(), Error> {
+        self.write_tag(tag_name, block_id, TagWrite::Move)
+    }
+
+    fn delete_tag(&self, tag_name: &str) -> Result<(), Error> {
+        let mut state = self.write();
+        let block_id = state.tags.remove(tag_name).ok_or(Error::TagNotFound)?;
+        state.remove_tag_ref(&block_id);
+        Ok(())
+    }
+
+    fn get_tag(&self, tag_name: &str) -> Result<Option<Value>, Error> {
+        Ok(self
+            .read()
+            .tags
+            .get(tag_name)
+            .map(|block_id| Value::from(block_id.clone())))
+    }
+
+    fn get_tags(&self) -> Result<Vec<(String, Value)>, Error> {
+        Ok(self
+            .read()
+            .tags
+            .iter()
+            .map(|(tag_name, block_id)| (tag_name.clone(), Value::from(block_id.clone())))
+            .collect())
+    }
+
+    fn get_tips_ids(&self) -> Result<Vec<Value>, Error> {
+        Ok(self
+            .read()
+            .tips
+            .iter()
+            .map(|id| Value::from(id.clone()))
+            .collect())
+    }
+
+    fn prune_branch(&self, tip_id: &[u8]) -> Result<(), Error> {
+        let mut state = self.write();
+
+        if !state.tips.contains(tip_id) {
+            return Err(Error::BranchNotFound);
+        }
+
+        let mut block_id = tip_id.to_vec();
+
+        loop {
+            if state.permanent_index.contains_key(&block_id) {
+                return Ok(());
+            }
+
+            let block_info = state
+                .blocks
+                .get(&block_id)
+                .map(|(_, block_info)| block_info.clone())
+                .ok_or(ConsistencyFailure::BlockInfo)?;
+
+            if block_info.ref_count() != 0 {
+                return Ok(());
+            }
+
+            state.remove_volatile(&block_id, block_info.chain_length());
+            state.tips.remove(&block_id);
+
+            let parent_id = block_info.parent_id().as_ref();
+
+            if parent_id == self.root_id.as_ref() {
+                return Ok(());
+            }
+
+            if let Some(parent_length) = state.permanent_index.get(parent_id).copied() {
+                // the parent becomes a tip if this was its only child
+                let next_length = parent_length + 1;
+                if state.permanent_blocks.len() <= next_length as usize
+                    && !state.chain_length_index.contains_key(&next_length)
+                {
+                    state.tips.insert(parent_id.to_vec());
+                }
+                return Ok(());
+            }
+
+            let (_, parent_info) = state
+                .blocks
+                .get_mut(parent_id)
+                .ok_or(ConsistencyFailure::MissingParentBlock)?;
+            parent_info.remove_parent_ref();
+
+            // if the block is inside another branch it cannot be a tip
+            if parent_info.parent_ref_count() != 0 {
+                return Ok(());
+            }
+
+            let parent_referenced = parent_info.ref_count() != 0;
+            state.tips.insert(parent_id.to_vec());
+
+            // a referenced block cannot be removed
+            if parent_referenced {
+                return Ok(());
+            }
+
+            block_id = parent_id.to_vec();
+        }
+    }
+
+    fn block_exists(&self, block_id: &[u8]) -> Result<bool, Error> {
+        let state = self.read();
+        Ok(state.permanent_index.contains_key(block_id) || state.blocks.contains_key(block_id))
+    }
+
+    fn is_ancestor(&self, ancestor_id: &[u8], descendant_id: &[u8]) -> Result<Option<u32>, Error> {
+        let state = self.read();
+        let descendant = state.block_info(descendant_id, &self.root_id)?;
+
+        if ancestor_id == descendant_id {
+            return Ok(Some(0));
+        }
+
+        if ancestor_id == self.root_id.as_ref() {
+            return Ok(Some(descendant.chain_length()));
+        }
+
+        let ancestor = state.block_info(ancestor_id, &self.root_id)?;
+
+        // blocks of the permanent storage are ancestors of all the later blocks
+        if state.permanent_index.contains_key(ancestor_id) {
+            if ancestor.chain_length() < descendant.chain_length() {
+                return Ok(Some(descendant.chain_length() - ancestor.chain_length()));
+            }
+            return Ok(None);
+        }
+
+        let mut current = descendant;
+        let mut distance = 0;
+
+        while current.chain_length() > ancestor.chain_length() {
+            current = match state.blocks.get(current.parent_id().as_ref()) {
+                Some((_, block_info)) => block_info.clone(),
+                None => return Ok(None),
+            };
+            distance += 1;
+        }
+
+        if current.id() == ancestor.id() {
+            Ok(Some(distance))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn get_nth_ancestor(&self, block_id: &[u8], distance: u32) -> Result<BlockInfo, Error> {
+        let state = self.read();
+        let mut current = state.block_info(block_id, &self.root_id)?;
+
+        if distance > current.chain_length() {
+            return Err(Error::CannotIterate);
+        }
+
+        for _ in 0..distance {
+            current = state.block_info(current.parent_id().as_ref(), &self.root_id)?;
+        }
+
+        Ok(current)
+    }
+
+    fn find_lowest_common_ancestor(
+        &self,
+        block1: &[u8],
+        block2: &[u8],
+    ) -> Result<Option<BlockInfo>, Error> {
+        let state = self.read();
+        let mut current1 = state.block_info(block1, &self.root_id)?;
+        let mut current2 = state.block_info(block2, &self.root_id)?;
+
+        // let current1 be the block deeper in the chain
+        if current1.chain_length() > current2.chain_length() {
+            std::mem::swap(&mut current1, &mut current2);
+        }
+
+        while current2.chain_length() > current1.chain_length() {
+            current2 = state.block_info(current2.parent_id().as_ref(), &self.root_id)?;
+        }
+
+        while current2.id() != current1.id() && current1.chain_length() > 0 {
+            current1 = state.block_info(current1.parent_id().as_ref(), &self.root_id)?;
+            current2 = state.block_info(current2.parent_id().as_ref(), &self.root_id)?;
+        }
+
+        if current1.id() != current2.id() {
+            return Ok(None);
+        }
+
+        Ok(Some(current1))
+    }
+
+    fn flush_to_permanent_store(&self, to_block: &[u8], min_number: usize) -> Result<usize, Error> {
+        assert!(min_number > 0);
+
+        let mut state = self.write();
+
+        let block_info = match state.blocks.get(to_block) {
+            Some((_, block_info)) => block_info.clone(),
+            None => return Ok(0),
+        };
+
+        match block_info.chain_length().checked_sub(min_number as u32) {
+            Some(length) if state.chain_length_index.contains_key(&length) => (),
+            _ => return Ok(0),
+        }
+
+        let mut block_infos = vec![block_info];
+        while let Some((_, block_info)) = state
+            .blocks
+            .get(block_infos.last().unwrap().parent_id().as_ref())
+        {
+            block_infos.push(block_info.clone());
+        }
+
+        if block_infos.len() < min_number {
+            return Ok(0);
+        }
+
+        for block_info in block_infos.iter().rev() {
+            let id = block_info.id().as_ref().to_vec();
+            let (block, _) = state.blocks[&id].clone();
+            let chain_length = state.permanent_blocks.len() as u32;
+            state.remove_volatile(&id, block_info.chain_length());
+            state.permanent_index.insert(id.clone(), chain_length);
+            state.permanent_blocks.push((id, block));
+        }
+
+        Ok(block_infos.len())
+    }
+
+    // the blocks are collected while the storage is locked, so that the
+    // iterator does not hold the lock
+    fn iter(&self, to_block: &[u8], distance: u32) -> Result<BlockIterator, Error> {
+        let state = self.read();
+        let mut current = state.block_info(to_block, &self.root_id)?;
+
+        if current.chain_length() + 1 < distance {
+            return Err(Error::CannotIterate);
+        }
+
+        let mut blocks = Vec::with_capacity(distance as usize);
+        for i in 0..distance {
+            blocks.push(state.block(current.id().as_ref()));
+            if i + 1 < distance {
+                current = state.block_info(current.parent_id().as_ref(), &self.root_id)?;
+            }
+        }
+        blocks.reverse();
+
+        Ok(Box::new(blocks.into_iter()))
+    }
+
+    fn iter_reverse(&self, from_block: &[u8], max_distance: u32) -> Result<BlockIterator, Error> {
+        let state = self.read();
+        let mut current = state.block_info(from_block, &self.root_id)?;
+
+        let mut blocks = Vec::new();
+        while (blocks.len() as u32) < max_distance {
+            blocks.push(state.block(current.id().as_ref()));
+            if current.parent_id().as_ref() == self.root_id.as_ref() {
+                break;
+            }
+            current = state.block_info(current.parent_id().as_ref(), &self.root_id)?;
+        }
+
+        Ok(Box::new(blocks.into_iter()))
+    }
+}
@@ -1,11 +1,22 @@
 use crate::{BlockInfo, ConsistencyFailure, Error, Value};
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard},
+};
+
+// Key of the chain length below which the blocks were pruned, in the metadata
+// tree.
+const PRUNED_BEFORE_KEY: &[u8] = b"pruned_before";
 
 #[derive(Clone)]
 pub(crate) struct PermanentStore {
-    blocks: data_pile::Database,
+    // The blocks file is replaced when pruning, hence the lock shared by all
+    // the handles to the store.
+    blocks: Arc<RwLock<data_pile::Database>>,
+    blocks_path: Option<PathBuf>,
     chain_length_index: data_pile::Database,
     block_id_index: sled::Tree,
+    metadata: sled::Tree,
     root_id: Value,
 }
 
@@ -13,6 +24,7 @@ impl PermanentStore {
     pub fn file<P: AsRef<Path>, I: Into<Value>>(
         path: P,
         block_id_index: sled::Tree,
+        metadata: sled::Tree,
         root_id: I,
     ) -> Result<PermanentStore, Error> {
         std::fs::create_dir_all(&path).map_err(Error::Open)?;
@@ -20,21 +32,24 @@ impl PermanentStore {
         let blocks_path = path.as_ref().join("blocks");
         let chain_length_index_path = path.as_ref().join("chain_length");
 
-        let blocks = data_pile::Database::file(blocks_path)?;
+        let blocks = data_pile::Database::file(&blocks_path)?;
         let chain_length_index = data_pile::Database::file(chain_length_index_path)?;
 
         let root_id = root_id.into();
 
         Ok(Self {
-            blocks,
+            blocks: Arc::new(RwLock::new(blocks)),
+            blocks_path: Some(blocks_path),
             chain_length_index,
             block_id_index,
+            metadata,
             root_id,
         })
     }
 
     pub fn memory<I: Into<Value>>(
         block_id_index: sled::Tree,
+        metadata: sled::Tree,
         root_id: I,
     ) -> Result<PermanentStore, Error> {
         let blocks = data_pile::Database::memory()?;
@@ -43,23 +58,60 @@ impl PermanentStore {
         let root_id = root_id.into();
 
         Ok(Self {
-            blocks,
+            blocks: Arc::new(RwLock::new(blocks)),
+            blocks_path: None,
             chain_length_index,
             block_id_index,
+            metadata,
             root_id,
         })
     }
 
-    pub fn get_block_by_chain_length(&self, chain_length: u32) -> Option<Value> {
-        self.blocks
+    /// A handle to the same storage, using the given trees of a new volatile
+    /// database.
+    pub fn with_volatile(&self, block_id_index: sled::Tree, metadata: sled::Tree) -> Self {
+        Self {
+            block_id_index,
+            metadata,
+            ..self.clone()
+        }
+    }
+
+    /// Number of handles to the storage, including the ones held by iterators.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.blocks)
+    }
+
+    fn blocks(&self) -> RwLockReadGuard<'_, data_pile::Database> {
+        self.blocks.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the block at the given chain length. Fails with `BlockPruned` if
+    /// nothing of the block was retained when pruning it.
+    pub fn get_block_by_chain_length(&self, chain_length: u32) -> Result<Option<Value>, Error> {
+        let block = match self.blocks().get_by_seqno(chain_length as usize) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        if block.as_ref().is_empty() && chain_length < self.pruned_before()? {
+            return Err(Error::BlockPruned);
+        }
+
+        Ok(Some(Value::permanent(block)))
+    }
+
+    pub fn contains_chain_length(&self, chain_length: u32) -> bool {
+        self.chain_length_index
             .get_by_seqno(chain_length as usize)
-            .map(Value::permanent)
+            .is_some()
     }
 
     pub fn get_block(&self, block_id: &[u8]) -> Result<Option<Value>, Error> {
-        self.get_chain_length(block_id).map(|maybe_chain_length| {
-            maybe_chain_length.and_then(|chain_length| self.get_block_by_chain_length(chain_length))
-        })
+        match self.get_chain_length(block_id)? {
+            Some(chain_length) => self.get_block_by_chain_length(chain_length),
+            None => Ok(None),
+        }
     }
 
     pub fn get_block_info(&self, block_id: &[u8]) -> Result<Option<BlockInfo>, Error> {
@@ -138,7 +190,7 @@ impl PermanentStore {
             "the number of ids should be equal to the number of blocks"
         );
 
-        self.blocks
+        self.blocks()
             .append(blocks)
             .map_err(Error::PermanentBackendError)?;
 
@@ -156,11 +208,150 @@ impl PermanentStore {
     }
 
     pub fn iter(&self, chain_length: u32) -> Result<data_pile::SeqNoIter, Error> {
-        self.blocks
+        self.blocks()
             .iter_from_seqno(chain_length as usize)
             .ok_or(Error::BlockNotFound)
     }
 
+    /// The chain length below which the blocks were pruned, 0 if the store
+    /// was never pruned.
+    pub fn pruned_before(&self) -> Result<u32, Error> {
+        let bytes = match self.metadata.get(PRUNED_BEFORE_KEY)? {
+            Some(bytes) => bytes,
+            None => return Ok(0),
+        };
+
+        let mut chain_length_bytes = [0u8; 4];
+        chain_length_bytes.copy_from_slice(bytes.as_ref());
+        Ok(u32::from_le_bytes(chain_length_bytes))
+    }
+
+    /// Replace the blocks below the given chain length by the part of them
+    /// selected by `retain`, and rewrite the blocks file to reclaim the space.
+    /// Blocks pruned before are kept as they are.
+    ///
+    /// Returns the number of blocks pruned.
+    pub fn prune_before<F>(&self, chain_length: u32, mut retain: F) -> Result<usize, Error>
+    where
+        F: FnMut(&[u8]) -> &[u8],
+    {
+        // appending blocks while the file is rewritten would lose them
+        let mut blocks = self.blocks.write().unwrap_or_else(PoisonError::into_inner);
+
+        let pruned_before = self.pruned_before()?;
+        if chain_length <= pruned_before || blocks.get_by_seqno(pruned_before as usize).is_none() {
+            return Ok(0);
+        }
+
+        let records: Vec<_> = blocks
+            .iter_from_seqno(0)
+            .ok_or(Error::BlockNotFound)?
+            .collect();
+        let prune_range = pruned_before as usize..chain_length as usize;
+        let retained: Vec<&[u8]> = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                if prune_range.contains(&i) {
+                    retain(record.as_ref())
+                } else {
+                    record.as_ref()
+                }
+            })
+            .collect();
+        let pruned = std::cmp::min(chain_length as usize, records.len()) - pruned_before as usize;
+
+        // Record the new boundary first: if the file is not replaced because of
+        // a crash, the blocks are still there in full, which is harmless.
+        let new_pruned_before = pruned_before + pruned as u32;
+        self.metadata
+            .insert(PRUNED_BEFORE_KEY, &new_pruned_before.to_le_bytes()[..])?;
+        self.metadata.flush()?;
+
+        self.rewrite_blocks(&mut blocks, &retained)?;
+
+        Ok(pruned)
+    }
+
+    /// Rewrite the blocks file, so that it does not use more space than
+    /// needed by its content.
+    pub fn defragment(&self) -> Result<(), Error> {
+        let mut blocks = self.blocks.write().unwrap_or_else(PoisonError::into_inner);
+
+        let records: Vec<_> = match blocks.iter_from_seqno(0) {
+            Some(iter) => iter.collect(),
+            None => return Ok(()),
+        };
+        let records: Vec<&[u8]> = records.iter().map(|record| record.as_ref()).collect();
+
+        self.rewrite_blocks(&mut blocks, &records)
+    }
+
+    // Replace the blocks file by a new one with the given content.
+    fn rewrite_blocks(
+        &self,
+        blocks: &mut data_pile::Database,
+        records: &[&[u8]],
+    ) -> Result<(), Error> {
+        let new_blocks = match &self.blocks_path {
+            Some(path) => {
+                let rewrite_path = path.with_extension("rewrite");
+                if rewrite_path.exists() {
+                    std::fs::remove_file(&rewrite_path).map_err(Error::PermanentStoreReplace)?;
+                }
+                data_pile::Database::file(rewrite_path)?
+            }
+            None => data_pile::Database::memory()?,
+        };
+        new_blocks.append(records)?;
+
+        *blocks = match &self.blocks_path {
+            Some(path) => {
+                drop(new_blocks);
+                std::fs::rename(path.with_extension("rewrite"), path)
+                    .map_err(Error::PermanentStoreReplace)?;
+                data_pile::Database::file(path)?
+            }
+            None => new_blocks,
+        };
+
+        Ok(())
+    }
+
+    /// Size of the blocks file in bytes, 0 for an in-memory store.
+    pub fn blocks_file_size(&self) -> Result<u64, Error> {
+        match &self.blocks_path {
+            Some(path) => std::fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .map_err(Error::Open),
+            None => Ok(0),
+        }
+    }
+
+    /// Number of blocks in the permanent storage, which is also the chain
+    /// length of the next block to be added.
+    pub fn block_count(&self) -> u32 {
+        if !self.contains_chain_length(0) {
+            return 0;
+        }
+
+        // find an upper bound, then search the last stored chain length
+        let mut high = 1u32;
+        while self.contains_chain_length(high) {
+            high = high.saturating_mul(2);
+        }
+        let mut low = high / 2;
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if self.contains_chain_length(middle) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        low + 1
+    }
+
     pub fn block_id_index(&self) -> &sled::Tree {
         &self.block_id_index
     }
@@ -1,6 +1,6 @@
 use crate::{
     test_utils::{Block, BlockId},
-    BlockInfo, BlockStore, Error, Value,
+    BlockDate, BlockInfo, BlockIterator, BlockStorage, BlockStore, Error, MemoryBlockStore, Value,
 };
 use rand_core::{OsRng, RngCore};
 use std::{collections::HashSet, iter::FromIterator};
@@ -801,6 +801,172 @@ fn permanent_store_get_by_chain_length() {
     );
 }
 
+// id, parent id and chain length of the test blocks
+const BLOCK_HEADER_LENGTH: usize = 20;
+
+#[test]
+fn permanent_store_prune_keep_headers() {
+    const PRUNE_BEFORE: usize = 100;
+
+    let (file, store, blocks) = prepare_permament_store();
+
+    let pruned = store
+        .prune_before(PRUNE_BEFORE as u32, |block| &block[..BLOCK_HEADER_LENGTH])
+        .unwrap();
+    assert_eq!(PRUNE_BEFORE, pruned);
+    assert_eq!(PRUNE_BEFORE as u32, store.pruned_before().unwrap());
+
+    // pruning again up to the same chain length has no effect
+    assert_eq!(
+        0,
+        store
+            .prune_before(PRUNE_BEFORE as u32, |block| &block[..0])
+            .unwrap()
+    );
+
+    let check = |store: &BlockStore| {
+        for (i, block) in blocks.iter().enumerate() {
+            let block_id = block.id.serialize_as_vec();
+            let block_info = store.get_block_info(&block_id).unwrap();
+            assert_eq!(block.chain_length, block_info.chain_length());
+
+            let expected = block.serialize_as_vec();
+            let expected = if i < PRUNE_BEFORE {
+                &expected[..BLOCK_HEADER_LENGTH]
+            } else {
+                &expected[..]
+            };
+            assert_eq!(expected, store.get_block(&block_id).unwrap().as_ref());
+        }
+    };
+
+    check(&store);
+
+    let mut archive = Vec::new();
+    assert!(matches!(
+        store.export(
+            &mut archive,
+            &blocks[PRUNE_BEFORE - 1].id.serialize_as_vec(),
+            &blocks[PRUNE_BEFORE].id.serialize_as_vec(),
+        ),
+        Err(Error::BlockPruned)
+    ));
+    store
+        .export(
+            &mut archive,
+            &blocks[PRUNE_BEFORE].id.serialize_as_vec(),
+            &blocks[PRUNE_BEFORE + 1].id.serialize_as_vec(),
+        )
+        .unwrap();
+
+    drop(store);
+    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+    check(&store);
+
+    // blocks can still be flushed to the permanent storage after pruning
+    store
+        .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK_2].id.serialize_as_vec(), 1)
+        .unwrap();
+    assert_eq!(
+        vec![blocks[FLUSH_TO_BLOCK_2].serialize_as_value()],
+        store
+            .get_blocks_by_chain_length(blocks[FLUSH_TO_BLOCK_2].chain_length)
+            .unwrap()
+    );
+}
+
+#[test]
+fn permanent_store_prune_everything() {
+    const PRUNE_BEFORE: usize = 100;
+
+    let (_file, store, blocks) = prepare_permament_store();
+
+    store
+        .prune_before(PRUNE_BEFORE as u32, |block| &block[..0])
+        .unwrap();
+
+    assert!(matches!(
+        store.get_block(&blocks[PRUNE_BEFORE - 1].id.serialize_as_vec()),
+        Err(Error::BlockPruned)
+    ));
+    assert!(matches!(
+        store.get_blocks_by_chain_length(0),
+        Err(Error::BlockPruned)
+    ));
+    assert_eq!(
+        blocks[PRUNE_BEFORE].serialize_as_value(),
+        store
+            .get_block(&blocks[PRUNE_BEFORE].id.serialize_as_vec())
+            .unwrap()
+    );
+
+    let mut iter = store
+        .iter(
+            &blocks[PRUNE_BEFORE].id.serialize_as_vec(),
+            PRUNE_BEFORE as u32 + 1,
+        )
+        .unwrap();
+    assert!(matches!(iter.next(), Some(Err(Error::BlockPruned))));
+
+    // only the permanent storage is pruned
+    let pruned = store
+        .prune_before(BLOCK_NUM_PERMANENT_TEST as u32, |block| &block[..0])
+        .unwrap();
+    assert_eq!(FLUSH_TO_BLOCK + 1 - PRUNE_BEFORE, pruned);
+    assert_eq!(
+        blocks[BLOCK_NUM_PERMANENT_TEST - 1].serialize_as_value(),
+        store
+            .get_block(&blocks[BLOCK_NUM_PERMANENT_TEST - 1].id.serialize_as_vec())
+            .unwrap()
+    );
+}
+
+#[test]
+fn memory_store_prune() {
+    const TEST_BLOCK_NUM: usize = 32;
+    const PRUNE_BEFORE: usize = 8;
+
+    let store = BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap();
+    let mut blocks = vec![Block::genesis(None)];
+    for _ in 1..TEST_BLOCK_NUM {
+        blocks.push(blocks.last().unwrap().make_child(None));
+    }
+    for block in blocks.iter() {
+        let block_info = BlockInfo::new(
+            block.id.serialize_as_vec(),
+            block.parent.serialize_as_vec(),
+            block.chain_length,
+        );
+        store
+            .put_block(&block.serialize_as_vec(), block_info)
+            .unwrap();
+    }
+    store
+        .flush_to_permanent_store(&blocks[TEST_BLOCK_NUM - 1].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    store
+        .prune_before(PRUNE_BEFORE as u32, |block| &block[..BLOCK_HEADER_LENGTH])
+        .unwrap();
+
+    for (i, block) in store
+        .iter(
+            &blocks[TEST_BLOCK_NUM - 1].id.serialize_as_vec(),
+            TEST_BLOCK_NUM as u32,
+        )
+        .unwrap()
+        .enumerate()
+    {
+        let expected = blocks[i].serialize_as_vec();
+        let expected = if i < PRUNE_BEFORE {
+            &expected[..BLOCK_HEADER_LENGTH]
+        } else {
+            &expected[..]
+        };
+        assert_eq!(expected, block.unwrap().as_ref());
+    }
+}
+
 #[test]
 fn iterator_only_volatile_storage() {
     const TEST_BLOCK_NUM: usize = 32;
@@ -863,3 +1029,761 @@ fn iterator_only_permanent_storage() {
         assert_eq!(blocks[i].serialize_as_value(), block.unwrap());
     }
 }
+
+#[test]
+fn iterator_to_permanent_block() {
+    const TEST_BLOCK_NUM: usize = 32;
+    const TO_BLOCK: usize = 8;
+
+    let (_file, store, blocks) = prepare_and_fill_store(TEST_BLOCK_NUM);
+
+    store
+        .flush_to_permanent_store(&blocks[TEST_BLOCK_NUM - 1].id.serialize_as_vec()[..], 1)
+        .unwrap();
+
+    let actual: Vec<_> = store
+        .iter(&blocks[TO_BLOCK].id.serialize_as_vec()[..], 2)
+        .unwrap()
+        .map(|block| block.unwrap())
+        .collect();
+    assert_eq!(
+        vec![
+            blocks[TO_BLOCK - 1].serialize_as_value(),
+            blocks[TO_BLOCK].serialize_as_value()
+        ],
+        actual
+    );
+}
+
+#[test]
+fn iterator_range() {
+    const FLUSH_AT: usize = 20;
+
+    let (_file, store, main_branch, second_branch) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    store
+        .flush_to_permanent_store(&main_branch[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    let main_tip = main_branch.last().unwrap().id.serialize_as_vec();
+    let collect_range = |tip: &[u8], range: std::ops::Range<u32>| -> Vec<Value> {
+        store
+            .iter_range(tip, range)
+            .unwrap()
+            .map(|block| block.unwrap())
+            .collect()
+    };
+    let expected =
+        |blocks: &[Block]| -> Vec<Value> { blocks.iter().map(Block::serialize_as_value).collect() };
+
+    // across the permanent and the volatile storage
+    assert_eq!(
+        expected(&main_branch[10..40]),
+        collect_range(&main_tip, 10..40)
+    );
+    // truncated to the tip
+    assert_eq!(
+        expected(&main_branch[90..]),
+        collect_range(&main_tip, 90..1000)
+    );
+    assert!(collect_range(&main_tip, 40..40).is_empty());
+    assert!(collect_range(&main_tip, 1000..2000).is_empty());
+
+    // on the second branch, up to and past the bifurcation point
+    let second_tip = second_branch.last().unwrap().id.serialize_as_vec();
+    store.put_tag("second", &second_tip).unwrap();
+    let start = BIFURCATION_POINT as u32 - 5;
+    let actual: Vec<_> = store
+        .iter_range_by_tag("second", start..start + 10)
+        .unwrap()
+        .map(|block| block.unwrap())
+        .collect();
+    let mut expected_blocks = expected(&main_branch[start as usize..BIFURCATION_POINT]);
+    expected_blocks.extend(expected(&second_branch[..5]));
+    assert_eq!(expected_blocks, actual);
+
+    assert!(matches!(
+        store.iter_range_by_tag("missing", 0..10),
+        Err(Error::TagNotFound)
+    ));
+}
+
+#[test]
+fn blocks_by_date() {
+    const SLOTS_PER_EPOCH: u32 = 10;
+    const FLUSH_AT: usize = 20;
+
+    let date_of = |block: &Block| {
+        BlockDate::new(
+            block.chain_length / SLOTS_PER_EPOCH,
+            block.chain_length % SLOTS_PER_EPOCH,
+        )
+    };
+    let put_blocks = |store: &BlockStore, blocks: &[Block]| {
+        for block in blocks {
+            let block_info = BlockInfo::new(
+                block.id.serialize_as_vec(),
+                block.parent.serialize_as_vec(),
+                block.chain_length,
+            )
+            .with_date(date_of(block));
+            store
+                .put_block(&block.serialize_as_vec(), block_info)
+                .unwrap();
+        }
+    };
+
+    let store = BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap();
+    let mut main_branch = vec![Block::genesis(None)];
+    for _ in 1..MAIN_BRANCH_LEN {
+        main_branch.push(main_branch.last().unwrap().make_child(None));
+    }
+    let mut second_branch = vec![main_branch[BIFURCATION_POINT].make_child(None)];
+    for _ in 1..SECOND_BRANCH_LEN {
+        second_branch.push(second_branch.last().unwrap().make_child(None));
+    }
+    put_blocks(&store, &main_branch);
+    put_blocks(&store, &second_branch);
+
+    store
+        .flush_to_permanent_store(&main_branch[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    for block in main_branch.iter().chain(second_branch.iter()) {
+        assert_eq!(
+            Some(date_of(block)),
+            store.get_block_date(&block.id.serialize_as_vec()).unwrap()
+        );
+    }
+
+    // in the permanent storage
+    assert_eq!(
+        vec![main_branch[15].serialize_as_value()],
+        store.get_blocks_by_date(date_of(&main_branch[15])).unwrap()
+    );
+    // on two branches of the volatile storage
+    let date = date_of(&second_branch[0]);
+    let expected: HashSet<_> = HashSet::from_iter(vec![
+        main_branch[BIFURCATION_POINT + 1].serialize_as_value(),
+        second_branch[0].serialize_as_value(),
+    ]);
+    let actual = HashSet::from_iter(store.get_blocks_by_date(date).unwrap());
+    assert_eq!(expected, actual);
+    assert!(store
+        .get_blocks_by_date(BlockDate::new(1000, 0))
+        .unwrap()
+        .is_empty());
+
+    let expected: Vec<_> = main_branch[10..20]
+        .iter()
+        .map(Block::serialize_as_value)
+        .collect();
+    assert_eq!(expected, store.blocks_in_epoch(1).unwrap());
+    assert_eq!(
+        2 * SLOTS_PER_EPOCH as usize,
+        store.blocks_in_epoch(6).unwrap().len()
+    );
+
+    // the dates of the blocks of a pruned branch are removed
+    store
+        .prune_branch(&second_branch.last().unwrap().id.serialize_as_vec())
+        .unwrap();
+    assert_eq!(
+        vec![main_branch[BIFURCATION_POINT + 1].serialize_as_value()],
+        store.get_blocks_by_date(date).unwrap()
+    );
+    assert!(store
+        .get_block_date(&second_branch[0].id.serialize_as_vec())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn export_import() {
+    const FLUSH_AT: usize = 10;
+    const EXPORT_FROM: usize = 5;
+
+    let (_file, store, blocks) = prepare_and_fill_store(32);
+    store
+        .flush_to_permanent_store(&blocks[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    let mut archive = Vec::new();
+    let exported = store
+        .export(
+            &mut archive,
+            &blocks[0].id.serialize_as_vec(),
+            &blocks[EXPORT_FROM].id.serialize_as_vec(),
+        )
+        .unwrap();
+    assert_eq!(EXPORT_FROM as u64 + 1, exported);
+
+    let mut second_archive = Vec::new();
+    store
+        .export(
+            &mut second_archive,
+            &blocks[EXPORT_FROM].id.serialize_as_vec(),
+            &blocks.last().unwrap().id.serialize_as_vec(),
+        )
+        .unwrap();
+
+    let imported_store = BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap();
+    assert!(matches!(
+        imported_store.import(&second_archive[..]),
+        Err(Error::MissingParent)
+    ));
+    assert_eq!(
+        EXPORT_FROM as u64 + 1,
+        imported_store.import(&archive[..]).unwrap()
+    );
+    // the first block of the second archive is already present
+    assert_eq!(
+        (blocks.len() - EXPORT_FROM - 1) as u64,
+        imported_store.import(&second_archive[..]).unwrap()
+    );
+
+    for block in blocks.iter() {
+        let block_id = block.id.serialize_as_vec();
+        let block_info = imported_store.get_block_info(&block_id).unwrap();
+        assert_eq!(block.chain_length, block_info.chain_length());
+        assert_eq!(
+            block.serialize_as_value(),
+            imported_store.get_block(&block_id).unwrap()
+        );
+    }
+}
+
+#[test]
+fn import_invalid_archive() {
+    let (_file, store, blocks) = prepare_and_fill_store(4);
+
+    let mut archive = Vec::new();
+    store
+        .export(
+            &mut archive,
+            &blocks[0].id.serialize_as_vec(),
+            &blocks[3].id.serialize_as_vec(),
+        )
+        .unwrap();
+
+    let import = |archive: &[u8]| {
+        let store = BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap();
+        store.import(archive)
+    };
+
+    let mut corrupted = archive.clone();
+    let last_block_byte = archive.len() - 1 - 8 - 4 - 4 - 1;
+    corrupted[last_block_byte] ^= 1;
+    assert!(matches!(import(&corrupted), Err(Error::ArchiveChecksum)));
+
+    assert!(matches!(
+        import(&archive[..archive.len() - 1]),
+        Err(Error::ArchiveIo(_))
+    ));
+
+    // a corrupted block length is reported as a truncated archive
+    let id_length = BlockId(0).serialize_as_vec().len();
+    let date_flag_byte = 8 + 1 + 4 + 1 + 2 * id_length + 4;
+    let mut block_length_byte = date_flag_byte + 1;
+    if archive[date_flag_byte] == 1 {
+        block_length_byte += 8;
+    }
+    let mut huge_block = archive[..block_length_byte].to_vec();
+    huge_block.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge_block.extend_from_slice(&archive[block_length_byte + 4..]);
+    assert!(matches!(import(&huge_block), Err(Error::ArchiveIo(_))));
+
+    let mut wrong_magic = archive.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(
+        import(&wrong_magic),
+        Err(Error::InvalidArchive(_))
+    ));
+
+    let other_store = BlockStore::memory(vec![0u8; 4]).unwrap();
+    assert!(matches!(
+        other_store.import(&archive[..]),
+        Err(Error::InvalidArchive(_))
+    ));
+}
+
+#[test]
+fn tag_management() {
+    let (_file, store, main_branch, second_branch) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    let main_tip = main_branch.last().unwrap().id.serialize_as_vec();
+    let second_tip = second_branch.last().unwrap().id.serialize_as_vec();
+
+    store.create_tag("main", &main_tip).unwrap();
+    store.create_tag("second", &second_tip).unwrap();
+    assert!(matches!(
+        store.create_tag("main", &second_tip),
+        Err(Error::TagAlreadyExists)
+    ));
+    assert!(matches!(
+        store.move_tag("other", &second_tip),
+        Err(Error::TagNotFound)
+    ));
+
+    assert_eq!(
+        vec![
+            (
+                "main".to_string(),
+                main_branch.last().unwrap().id.serialize_as_value()
+            ),
+            (
+                "second".to_string(),
+                second_branch.last().unwrap().id.serialize_as_value()
+            ),
+        ],
+        store.get_tags().unwrap()
+    );
+
+    assert_eq!(
+        main_branch[BIFURCATION_POINT].id.serialize_as_value(),
+        *store
+            .find_tags_common_ancestor("main", "second")
+            .unwrap()
+            .unwrap()
+            .id()
+    );
+
+    // a tagged branch cannot be removed entirely
+    store.move_tag("second", &main_tip).unwrap();
+    store.create_tag("fork", &second_tip).unwrap();
+    store.prune_branch(&second_tip).unwrap();
+    assert!(store.block_exists(&second_tip).unwrap());
+
+    store.delete_tag("fork").unwrap();
+    assert!(matches!(store.delete_tag("fork"), Err(Error::TagNotFound)));
+    store.prune_branch(&second_tip).unwrap();
+    assert!(!store.block_exists(&second_tip).unwrap());
+}
+
+#[test]
+fn tag_move_from_permanent_block() {
+    let (_file, store, blocks) = prepare_permament_store();
+
+    store
+        .create_tag("tip", &blocks[FLUSH_TO_BLOCK].id.serialize_as_vec())
+        .unwrap();
+    store
+        .move_tag("tip", &blocks[FLUSH_TO_BLOCK + 1].id.serialize_as_vec())
+        .unwrap();
+    store
+        .flush_to_permanent_store(&blocks[FLUSH_TO_BLOCK_2].id.serialize_as_vec(), 1)
+        .unwrap();
+    store
+        .move_tag("tip", &blocks[FLUSH_TO_BLOCK_2 + 1].id.serialize_as_vec())
+        .unwrap();
+    store.delete_tag("tip").unwrap();
+    assert!(store.get_tags().unwrap().is_empty());
+}
+
+#[test]
+fn iterator_reverse() {
+    const FLUSH_AT: usize = 20;
+
+    let (_file, store, main_branch, second_branch) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    store
+        .flush_to_permanent_store(&main_branch[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    // from the tip of the second branch down to the genesis block
+    let second_tip = second_branch.last().unwrap().id.serialize_as_vec();
+    let actual: Vec<_> = store
+        .iter_reverse(&second_tip, u32::MAX)
+        .unwrap()
+        .map(|block| block.unwrap())
+        .collect();
+    let expected: Vec<_> = main_branch[..BIFURCATION_POINT]
+        .iter()
+        .chain(second_branch.iter())
+        .rev()
+        .map(Block::serialize_as_value)
+        .collect();
+    assert_eq!(expected, actual);
+
+    // bounded look-back
+    let main_tip = main_branch.last().unwrap().id.serialize_as_vec();
+    let actual: Vec<_> = store
+        .iter_reverse(&main_tip, 10)
+        .unwrap()
+        .map(|block| block.unwrap())
+        .collect();
+    let expected: Vec<_> = main_branch[MAIN_BRANCH_LEN - 10..]
+        .iter()
+        .rev()
+        .map(Block::serialize_as_value)
+        .collect();
+    assert_eq!(expected, actual);
+
+    assert_eq!(0, store.iter_reverse(&main_tip, 0).unwrap().count());
+    assert!(matches!(
+        store.iter_reverse(&BlockId(0).serialize_as_vec(), 1),
+        Err(Error::BlockNotFound)
+    ));
+}
+
+fn check_storage_semantics<S: BlockStorage>(store: S) {
+    const FLUSH_AT: usize = 20;
+
+    let put_blocks = |blocks: &[Block]| {
+        for block in blocks {
+            let block_info = BlockInfo::new(
+                block.id.serialize_as_vec(),
+                block.parent.serialize_as_vec(),
+                block.chain_length,
+            );
+            store
+                .put_block(&block.serialize_as_vec(), block_info)
+                .unwrap();
+        }
+    };
+
+    let mut main_branch = vec![Block::genesis(None)];
+    for _ in 1..MAIN_BRANCH_LEN {
+        main_branch.push(main_branch.last().unwrap().make_child(None));
+    }
+    let mut second_branch = vec![main_branch[BIFURCATION_POINT].make_child(None)];
+    for _ in 1..SECOND_BRANCH_LEN {
+        second_branch.push(second_branch.last().unwrap().make_child(None));
+    }
+    put_blocks(&main_branch);
+    put_blocks(&second_branch);
+
+    let main_tip = main_branch.last().unwrap().id.serialize_as_vec();
+    let second_tip = second_branch.last().unwrap().id.serialize_as_vec();
+
+    assert!(matches!(
+        store.put_block(
+            &main_branch[0].serialize_as_vec(),
+            BlockInfo::new(
+                main_branch[0].id.serialize_as_vec(),
+                main_branch[0].parent.serialize_as_vec(),
+                0,
+            ),
+        ),
+        Err(Error::BlockAlreadyPresent)
+    ));
+    let orphan = Block::genesis(None).make_child(None);
+    assert!(matches!(
+        store.put_block(
+            &orphan.serialize_as_vec(),
+            BlockInfo::new(
+                orphan.id.serialize_as_vec(),
+                orphan.parent.serialize_as_vec(),
+                1,
+            ),
+        ),
+        Err(Error::MissingParent)
+    ));
+
+    let tips: HashSet<_> = HashSet::from_iter(store.get_tips_ids().unwrap());
+    assert_eq!(
+        HashSet::from_iter(vec![
+            Value::from(main_tip.clone()),
+            Value::from(second_tip.clone())
+        ]),
+        tips
+    );
+
+    store
+        .flush_to_permanent_store(&main_branch[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+    assert_eq!(
+        0,
+        store
+            .flush_to_permanent_store(&main_branch[FLUSH_AT + 1].id.serialize_as_vec(), 5)
+            .unwrap()
+    );
+
+    for block in main_branch.iter().chain(second_branch.iter()) {
+        let block_id = block.id.serialize_as_vec();
+        assert_eq!(
+            block.serialize_as_value(),
+            store.get_block(&block_id).unwrap()
+        );
+        let block_info = store.get_block_info(&block_id).unwrap();
+        assert_eq!(block.chain_length, block_info.chain_length());
+        assert_eq!(block.parent.serialize_as_value(), *block_info.parent_id());
+    }
+
+    let at_fork = store
+        .get_blocks_by_chain_length(BIFURCATION_POINT as u32 + 1)
+        .unwrap();
+    assert_eq!(2, at_fork.len());
+    assert_eq!(
+        vec![main_branch[FLUSH_AT].serialize_as_value()],
+        store.get_blocks_by_chain_length(FLUSH_AT as u32).unwrap()
+    );
+
+    assert_eq!(
+        main_branch[BIFURCATION_POINT].id.serialize_as_value(),
+        *store
+            .find_lowest_common_ancestor(&main_tip, &second_tip)
+            .unwrap()
+            .unwrap()
+            .id()
+    );
+    assert_eq!(
+        Some(SECOND_BRANCH_LEN as u32),
+        store
+            .is_ancestor(
+                &main_branch[BIFURCATION_POINT].id.serialize_as_vec(),
+                &second_tip
+            )
+            .unwrap()
+    );
+    assert_eq!(
+        None,
+        store
+            .is_ancestor(
+                &main_branch[BIFURCATION_POINT + 1].id.serialize_as_vec(),
+                &second_tip
+            )
+            .unwrap()
+    );
+    assert_eq!(
+        main_branch[10].id.serialize_as_value(),
+        *store
+            .get_nth_ancestor(
+                &second_tip,
+                (BIFURCATION_POINT + SECOND_BRANCH_LEN - 10) as u32
+            )
+            .unwrap()
+            .id()
+    );
+
+    store.put_tag("second", &second_tip).unwrap();
+    store.prune_branch(&second_tip).unwrap();
+    assert!(store.block_exists(&second_tip).unwrap());
+
+    store.put_tag("second", &main_tip).unwrap();
+    assert_eq!(
+        Some(Value::from(main_tip.clone())),
+        store.get_tag("second").unwrap()
+    );
+    store.prune_branch(&second_tip).unwrap();
+    for block in second_branch.iter() {
+        assert!(!store.block_exists(&block.id.serialize_as_vec()).unwrap());
+    }
+    assert_eq!(
+        vec![Value::from(main_tip.clone())],
+        store.get_tips_ids().unwrap()
+    );
+    assert!(matches!(
+        store.prune_branch(&second_tip),
+        Err(Error::BranchNotFound)
+    ));
+
+    let collect = |iter: BlockIterator| iter.collect::<Result<Vec<_>, _>>().unwrap();
+    let values =
+        |blocks: &[Block]| -> Vec<Value> { blocks.iter().map(Block::serialize_as_value).collect() };
+
+    // the iteration starts in the permanent storage
+    let distance = MAIN_BRANCH_LEN - FLUSH_AT + 5;
+    assert_eq!(
+        values(&main_branch[FLUSH_AT - 5..]),
+        collect(store.iter(&main_tip, distance as u32).unwrap())
+    );
+    assert!(collect(store.iter(&main_tip, 0).unwrap()).is_empty());
+    assert!(matches!(
+        store.iter(&main_tip, MAIN_BRANCH_LEN as u32 + 1),
+        Err(Error::CannotIterate)
+    ));
+
+    let mut reversed = values(&main_branch);
+    reversed.reverse();
+    assert_eq!(
+        reversed,
+        collect(store.iter_reverse(&main_tip, u32::MAX).unwrap())
+    );
+    assert_eq!(
+        reversed[..3].to_vec(),
+        collect(store.iter_reverse(&main_tip, 3).unwrap())
+    );
+
+    assert_eq!(
+        values(&main_branch[10..FLUSH_AT + 5]),
+        collect(
+            store
+                .iter_range(&main_tip, 10..FLUSH_AT as u32 + 5)
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        values(&main_branch[MAIN_BRANCH_LEN - 2..]),
+        collect(
+            store
+                .iter_range(&main_tip, MAIN_BRANCH_LEN as u32 - 2..u32::MAX)
+                .unwrap()
+        )
+    );
+
+    let flushed = main_branch[FLUSH_AT - 1].id.serialize_as_vec();
+    store.create_tag("main", &main_tip).unwrap();
+    assert!(matches!(
+        store.create_tag("main", &flushed),
+        Err(Error::TagAlreadyExists)
+    ));
+    assert!(matches!(
+        store.move_tag("other", &flushed),
+        Err(Error::TagNotFound)
+    ));
+    assert_eq!(
+        values(&main_branch[MAIN_BRANCH_LEN - 2..]),
+        collect(
+            store
+                .iter_range_by_tag("main", MAIN_BRANCH_LEN as u32 - 2..u32::MAX)
+                .unwrap()
+        )
+    );
+
+    store.move_tag("main", &flushed).unwrap();
+    assert_eq!(
+        vec![
+            ("main".to_string(), Value::from(flushed)),
+            ("second".to_string(), Value::from(main_tip.clone())),
+        ],
+        store.get_tags().unwrap()
+    );
+    store.delete_tag("second").unwrap();
+    assert!(matches!(
+        store.delete_tag("second"),
+        Err(Error::TagNotFound)
+    ));
+    assert_eq!(None, store.get_tag("second").unwrap());
+    assert_eq!(1, store.get_tags().unwrap().len());
+}
+
+#[test]
+fn block_store_semantics() {
+    check_storage_semantics(BlockStore::memory(BlockId(0).serialize_as_vec()).unwrap());
+}
+
+#[test]
+fn memory_block_store_semantics() {
+    check_storage_semantics(MemoryBlockStore::new(BlockId(0).serialize_as_vec()));
+}
+
+#[test]
+fn compact_removes_dead_branches() {
+    const FLUSH_AT: usize = 60;
+
+    let (file, mut store, main_branch, second_branch) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    // a third branch, tagged, forking from the second one
+    let mut third_branch = vec![second_branch[5].make_child(None)];
+    for _ in 1..5 {
+        third_branch.push(third_branch.last().unwrap().make_child(None));
+    }
+    for block in third_branch.iter() {
+        let block_info = BlockInfo::new(
+            block.id.serialize_as_vec(),
+            block.parent.serialize_as_vec(),
+            block.chain_length,
+        );
+        store
+            .put_block(&block.serialize_as_vec(), block_info)
+            .unwrap();
+    }
+    let third_tip = third_branch.last().unwrap().id.serialize_as_vec();
+    store.put_tag("third", &third_tip).unwrap();
+
+    // nothing is dead before flushing past the fork
+    assert_eq!(0, store.compact().unwrap().pruned_branches);
+
+    store
+        .flush_to_permanent_store(&main_branch[FLUSH_AT].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    let stats = store.compact().unwrap();
+    assert_eq!(1, stats.pruned_branches);
+
+    for block in second_branch.iter().skip(6) {
+        assert!(!store.block_exists(&block.id.serialize_as_vec()).unwrap());
+    }
+    // the tagged branch and its ancestors are kept
+    assert!(store.block_exists(&third_tip).unwrap());
+    assert!(store
+        .block_exists(&second_branch[5].id.serialize_as_vec())
+        .unwrap());
+
+    drop(store);
+    let store = BlockStore::file(file.path(), BlockId(0).serialize_as_vec()).unwrap();
+    for block in main_branch.iter() {
+        assert_eq!(
+            block.serialize_as_value(),
+            store.get_block(&block.id.serialize_as_vec()).unwrap()
+        );
+    }
+    let tips: HashSet<_> = HashSet::from_iter(store.get_tips_ids().unwrap());
+    assert_eq!(
+        HashSet::from_iter(vec![
+            main_branch.last().unwrap().id.serialize_as_value(),
+            Value::from(third_tip)
+        ]),
+        tips
+    );
+}
+
+#[test]
+fn compact_reclaims_space() {
+    const DEAD_BRANCH_LEN: usize = 32;
+    const BLOCK_DATA_LEN: usize = 64 * 1024;
+
+    let (_file, mut store, main_branch, _) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    // a branch with large blocks, dead once the main branch is flushed
+    let mut block = main_branch[10].make_child(Some(vec![0xab; BLOCK_DATA_LEN].into()));
+    for _ in 0..DEAD_BRANCH_LEN {
+        let block_info = BlockInfo::new(
+            block.id.serialize_as_vec(),
+            block.parent.serialize_as_vec(),
+            block.chain_length,
+        );
+        store
+            .put_block(&block.serialize_as_vec(), block_info)
+            .unwrap();
+        block = block.make_child(Some(vec![0xab; BLOCK_DATA_LEN].into()));
+    }
+
+    store
+        .flush_to_permanent_store(&main_branch[60].id.serialize_as_vec(), 1)
+        .unwrap();
+
+    let stats = store.compact().unwrap();
+    assert_eq!(2, stats.pruned_branches);
+    assert!(stats.reclaimed_bytes > 0);
+
+    let tip = main_branch.last().unwrap();
+    assert_eq!(
+        tip.serialize_as_value(),
+        store.get_block(&tip.id.serialize_as_vec()).unwrap()
+    );
+}
+
+#[test]
+fn compact_requires_exclusive_handle() {
+    let (_file, mut store, main_branch, _) =
+        generate_two_branches(MAIN_BRANCH_LEN, SECOND_BRANCH_LEN, BIFURCATION_POINT);
+
+    let other = store.clone();
+    assert!(matches!(store.compact(), Err(Error::StoreInUse)));
+    drop(other);
+
+    let iter = store
+        .iter(&main_branch[10].id.serialize_as_vec(), 5)
+        .unwrap();
+    assert!(matches!(store.compact(), Err(Error::StoreInUse)));
+    drop(iter);
+
+    store.compact().unwrap();
+}
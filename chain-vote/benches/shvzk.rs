@@ -55,12 +55,34 @@ fn verify(c: &mut Criterion) {
     group.finish();
 }
 
+fn batch_verify(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut group = c.benchmark_group("Batch verify vote proofs");
+    let crs = Crs::from_hash(&[0u8; 32]);
+    let ek = common(&mut rng);
+
+    for &number_votes in [1usize, 16, 128].iter() {
+        let votes: Vec<_> = (0..number_votes)
+            .map(|i| ek.encrypt_and_prove_vote(&mut rng, &crs, Vote::new(4, i % 4)))
+            .collect();
+        let parameter_string = format!("{} votes", number_votes);
+        group.bench_with_input(
+            BenchmarkId::new("Batch verify with", parameter_string),
+            &number_votes,
+            |b, _| b.iter(|| Ballot::try_from_votes_and_proofs(votes.clone(), &crs, &ek)),
+        );
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     name = shvzk;
     config = Criterion::default().sample_size(500);
     targets =
     encrypt_and_prove,
     verify,
+    batch_verify,
 );
 
 criterion_main!(shvzk);
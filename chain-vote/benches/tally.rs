@@ -0,0 +1,115 @@
+//! Tally decryption benchmarks for vote plans with many options.
+//!
+//! Run with the default features to measure the parallel decryption, and with
+//! `--no-default-features --features ristretto255` for the sequential one.
+use chain_vote::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+
+const MAX_STAKE: u64 = 1 << 20;
+
+struct Election {
+    participants: Vec<MemberPublicKey>,
+    members: Vec<MemberState>,
+    encrypted_tally: EncryptedTally,
+}
+
+fn election(rng: &mut ChaCha20Rng, committee_size: usize, options: usize) -> Election {
+    let crs = Crs::from_hash(&[0u8; 32]);
+
+    let mc: Vec<_> = (0..committee_size)
+        .map(|_| MemberCommunicationKey::new(rng).to_public())
+        .collect();
+    let members: Vec<_> = (0..committee_size)
+        .map(|i| MemberState::new(rng, committee_size, &crs, &mc, i))
+        .collect();
+    let participants: Vec<_> = members.iter().map(|m| m.public_key()).collect();
+    let ek = ElectionPublicKey::from_participants(&participants);
+
+    let mut encrypted_tally = EncryptedTally::new(options, ek.clone(), crs.clone());
+    // spread the stake over the options, so that every discrete log is expensive
+    for option in 0..options {
+        let (vote, proof) = ek.encrypt_and_prove_vote(rng, &crs, Vote::new(options, option));
+        let ballot = Ballot::try_from_vote_and_proof(vote, &proof, &crs, &ek).unwrap();
+        encrypted_tally.add(&ballot, MAX_STAKE / options as u64);
+    }
+
+    Election {
+        participants,
+        members,
+        encrypted_tally,
+    }
+}
+
+fn verify_shares(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut group = c.benchmark_group("Verify decryption shares");
+
+    for &options in [8usize, 64, 256].iter() {
+        let election = election(&mut rng, 3, options);
+        let shares: Vec<_> = election
+            .members
+            .iter()
+            .map(|m| {
+                election
+                    .encrypted_tally
+                    .partial_decrypt(&mut rng, m.secret_key())
+            })
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("Validate", format!("{} options", options)),
+            &options,
+            |b, _| {
+                b.iter(|| {
+                    election
+                        .encrypted_tally
+                        .validate_partial_decryptions(&election.participants, &shares)
+                        .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn decrypt_tally(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut group = c.benchmark_group("Decrypt tally");
+    let table = TallyOptimizationTable::generate(MAX_STAKE.try_into().unwrap());
+
+    for &options in [8usize, 64, 256].iter() {
+        let election = election(&mut rng, 1, options);
+        let shares: Vec<_> = election
+            .members
+            .iter()
+            .map(|m| {
+                election
+                    .encrypted_tally
+                    .partial_decrypt(&mut rng, m.secret_key())
+            })
+            .collect();
+        let validated_tally = election
+            .encrypted_tally
+            .validate_partial_decryptions(&election.participants, &shares)
+            .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("Decrypt", format!("{} options", options)),
+            &options,
+            |b, _| b.iter(|| validated_tally.decrypt_tally(&table).unwrap()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    name = tally;
+    config = Criterion::default().sample_size(10);
+    targets =
+    verify_shares,
+    decrypt_tally,
+);
+
+criterion_main!(tally);
@@ -1,6 +1,6 @@
 use crate::cryptography::{Ciphertext, HybridCiphertext, PublicKey, SecretKey};
 use crate::encrypted_vote::{EncryptedVote, ProofOfCorrectVote, Vote};
-use crate::math::polynomial::Polynomial;
+use crate::math::polynomial::{lagrange_coefficient_at_zero, Polynomial};
 use crate::tally::Crs;
 use crate::{GroupElement, Scalar, CURVE_HRP};
 use chain_crypto::bech32::{to_bech32_from_bytes, try_from_bech32_to_bytes, Bech32, Error};
@@ -99,11 +99,54 @@ impl Bech32 for ElectionPublicKey {
 pub struct MemberState {
     sk: MemberSecretKey,
     owner_index: usize,
+    own_share: Scalar,
     apubs: Vec<GroupElement>,
     es: Vec<GroupElement>,
     encrypted: Vec<(HybridCiphertext, HybridCiphertext)>,
 }
 
+/// Public commitments to the coefficients of the polynomial a member uses to
+/// share its secret key with the rest of the committee. They allow the other
+/// members to check the shares they receive.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MemberCommitments(Vec<GroupElement>);
+
+/// A share of a member's secret key, dealt to another member of the committee
+/// and encrypted to its communication key.
+#[derive(Clone)]
+pub struct EncryptedSecretShare {
+    dealer: usize,
+    recipient: usize,
+    share: HybridCiphertext,
+}
+
+/// A committee member's share of the election secret key. Any `threshold`
+/// members can decrypt the tally with their shares.
+#[derive(Clone)]
+pub struct MemberSecretShare {
+    index: usize,
+    share: MemberSecretKey,
+}
+
+/// The public view of a committee set up for threshold decryption: the
+/// commitments published by every member, in the order of their index.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ThresholdCommittee {
+    commitments: Vec<MemberCommitments>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SecretShareError {
+    #[error("secret share is addressed to member {0}")]
+    WrongRecipient(usize),
+    #[error("unexpected secret share from member {0}")]
+    UnexpectedShare(usize),
+    #[error("invalid secret share from member {0}")]
+    InvalidShare(usize),
+    #[error("missing secret share from member {0}")]
+    MissingShare(usize),
+}
+
 impl MemberState {
     /// Generate a new member state from random, where the number
     pub fn new<R: RngCore + CryptoRng>(
@@ -118,8 +161,9 @@ impl MemberState {
         assert!(t <= n);
         assert!(my < n);
 
-        let pcomm = Polynomial::random(rng, t);
-        let pshek = Polynomial::random(rng, t);
+        // any t shares of a polynomial of degree t - 1 are needed to recover it
+        let pcomm = Polynomial::random(rng, t - 1);
+        let pshek = Polynomial::random(rng, t - 1);
 
         let mut apubs = Vec::new();
         let mut es = Vec::new();
@@ -151,8 +195,8 @@ impl MemberState {
             }
         }
 
-        assert_eq!(apubs.len(), t + 1);
-        assert_eq!(es.len(), t + 1);
+        assert_eq!(apubs.len(), t);
+        assert_eq!(es.len(), t);
         assert_eq!(encrypted.len(), n - 1);
 
         MemberState {
@@ -160,6 +204,7 @@ impl MemberState {
                 sk: pshek.at_zero(),
             }),
             owner_index: my + 1, // committee member are 1-indexed
+            own_share: pshek.evaluate(&Scalar::from_u64((my + 1) as u64)),
             apubs,
             es,
             encrypted,
@@ -175,6 +220,231 @@ impl MemberState {
             pk: self.apubs[0].clone(),
         })
     }
+
+    /// Index of the member in the committee, starting from 0
+    pub fn index(&self) -> usize {
+        self.owner_index - 1
+    }
+
+    /// Commitments to publish so the other members can check the shares
+    /// dealt to them
+    pub fn commitments(&self) -> MemberCommitments {
+        MemberCommitments(self.apubs.clone())
+    }
+
+    /// The share of this member's secret key dealt to the member at index
+    /// `recipient`. Returns `None` for the member's own index or an index
+    /// outside of the committee.
+    pub fn encrypted_share_for(&self, recipient: usize) -> Option<EncryptedSecretShare> {
+        let me = self.index();
+        let position = match recipient {
+            r if r < me => r,
+            r if r > me => r - 1,
+            _ => return None,
+        };
+        self.encrypted
+            .get(position)
+            .map(|(_, eshek)| EncryptedSecretShare {
+                dealer: me,
+                recipient,
+                share: eshek.clone(),
+            })
+    }
+}
+
+impl MemberCommitments {
+    /// Number of shares needed to recover the committed secret
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    // g^share must match the commitments evaluated at the member index
+    fn verify_share(&self, recipient: usize, share: &Scalar) -> bool {
+        GroupElement::generator() * share == self.evaluate(recipient)
+    }
+
+    fn evaluate(&self, index: usize) -> GroupElement {
+        let x = Scalar::from_u64((index + 1) as u64);
+        self.0
+            .iter()
+            .zip(x.exp_iter())
+            .fold(GroupElement::zero(), |acc, (c, xi)| acc + c * xi)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(GroupElement::BYTES_LEN * self.0.len());
+        for commitment in self.0.iter() {
+            out.extend_from_slice(&commitment.to_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() || bytes.len() % GroupElement::BYTES_LEN != 0 {
+            return None;
+        }
+
+        let commitments = bytes
+            .chunks(GroupElement::BYTES_LEN)
+            .map(GroupElement::from_bytes)
+            .collect::<Option<Vec<_>>>()?;
+        Some(MemberCommitments(commitments))
+    }
+}
+
+impl EncryptedSecretShare {
+    /// Index of the member who dealt the share
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+
+    /// Index of the member the share is addressed to
+    pub fn recipient(&self) -> usize {
+        self.recipient
+    }
+
+    /// The dealer and recipient indices, as 32-bit big endian integers,
+    /// followed by the encrypted share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&member_index_to_bytes(self.dealer));
+        out.extend_from_slice(&member_index_to_bytes(self.recipient));
+        out.extend_from_slice(&self.share.to_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 2 * MEMBER_INDEX_BYTES_LEN + GroupElement::BYTES_LEN {
+            return None;
+        }
+
+        let (dealer, bytes) = member_index_from_bytes(bytes);
+        let (recipient, bytes) = member_index_from_bytes(bytes);
+        let share = HybridCiphertext::from_bytes(bytes)?;
+        Some(EncryptedSecretShare {
+            dealer,
+            recipient,
+            share,
+        })
+    }
+}
+
+pub(crate) const MEMBER_INDEX_BYTES_LEN: usize = 4;
+
+pub(crate) fn member_index_to_bytes(index: usize) -> [u8; MEMBER_INDEX_BYTES_LEN] {
+    u32::try_from(index)
+        .expect("member index overflow")
+        .to_be_bytes()
+}
+
+// The caller has to check that `bytes` is long enough.
+pub(crate) fn member_index_from_bytes(bytes: &[u8]) -> (usize, &[u8]) {
+    let (index, rest) = bytes.split_at(MEMBER_INDEX_BYTES_LEN);
+    let index = u32::from_be_bytes(index.try_into().unwrap());
+    (index as usize, rest)
+}
+
+impl MemberSecretShare {
+    /// Decrypt and check the `shares` dealt by every other committee member to
+    /// `state`'s member and combine them with its own share.
+    pub fn combine(
+        state: &MemberState,
+        communication_key: &MemberCommunicationKey,
+        committee: &ThresholdCommittee,
+        shares: &[EncryptedSecretShare],
+    ) -> Result<Self, SecretShareError> {
+        let me = state.index();
+        if committee.commitments.get(me) != Some(&state.commitments()) {
+            return Err(SecretShareError::InvalidShare(me));
+        }
+
+        let mut received = vec![false; committee.size()];
+        received[me] = true;
+        let mut total = state.own_share.clone();
+        for share in shares {
+            if share.recipient != me {
+                return Err(SecretShareError::WrongRecipient(share.recipient));
+            }
+            match received.get_mut(share.dealer) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(SecretShareError::UnexpectedShare(share.dealer)),
+            }
+            let value = Scalar::from_bytes(&communication_key.0.hybrid_decrypt(&share.share))
+                .filter(|value| committee.commitments[share.dealer].verify_share(me, value))
+                .ok_or(SecretShareError::InvalidShare(share.dealer))?;
+            total = total + value;
+        }
+        if let Some(missing) = received.iter().position(|seen| !seen) {
+            return Err(SecretShareError::MissingShare(missing));
+        }
+
+        Ok(MemberSecretShare {
+            index: me,
+            share: MemberSecretKey(SecretKey { sk: total }),
+        })
+    }
+
+    /// Index of the member owning the share, starting from 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn secret_key(&self) -> &MemberSecretKey {
+        &self.share
+    }
+}
+
+impl ThresholdCommittee {
+    /// Create the committee from the commitments of all its members, ordered
+    /// by member index. All the members must use the same threshold.
+    pub fn new(commitments: Vec<MemberCommitments>) -> Self {
+        assert!(!commitments.is_empty());
+        let threshold = commitments[0].threshold();
+        assert!(commitments.iter().all(|c| c.threshold() == threshold));
+        ThresholdCommittee { commitments }
+    }
+
+    /// Number of members in the committee
+    pub fn size(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Number of decryption shares needed to decrypt a tally
+    pub fn threshold(&self) -> usize {
+        self.commitments[0].threshold()
+    }
+
+    /// The key to encrypt the votes to, equal to the one obtained from all the
+    /// members' public keys with `ElectionPublicKey::from_participants`
+    pub fn election_public_key(&self) -> ElectionPublicKey {
+        ElectionPublicKey(PublicKey {
+            pk: GroupElement::sum(self.commitments.iter().map(|c| &c.0[0])),
+        })
+    }
+
+    /// The public counterpart of the secret share of the member at `index`,
+    /// used to verify its decryption shares
+    pub fn share_verification_key(&self, index: usize) -> MemberPublicKey {
+        MemberPublicKey(PublicKey {
+            pk: self
+                .commitments
+                .iter()
+                .fold(GroupElement::zero(), |acc, c| acc + c.evaluate(index)),
+        })
+    }
+
+    /// Lagrange coefficients to combine the decryption shares of the members
+    /// at `indices`
+    pub(crate) fn lagrange_coefficients(&self, indices: &[usize]) -> Vec<Scalar> {
+        let points: Vec<Scalar> = indices
+            .iter()
+            .map(|i| Scalar::from_u64((*i + 1) as u64))
+            .collect();
+        points
+            .iter()
+            .map(|x| lagrange_coefficient_at_zero(x, &points))
+            .collect()
+    }
 }
 
 impl MemberSecretKey {
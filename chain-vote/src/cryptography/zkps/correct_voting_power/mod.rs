@@ -0,0 +1,3 @@
+mod zkp;
+
+pub use zkp::Zkp as CorrectVotingPowerZkp;
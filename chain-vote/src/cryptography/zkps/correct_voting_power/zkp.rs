@@ -0,0 +1,135 @@
+//! Non-interactive Zero Knowledge proof that a public voting power `w` is the
+//! value committed in a voting power snapshot. Given the Pedersen commitment
+//! key `h` and the commitment `C = g^w * h^r`, the proof is the following:
+//!
+//! `NIZK{(h, C, w, vote), (r): C * g^-w = h^r}`
+//!
+//! which is a proof of knowledge of a discrete logarithm. The encrypted vote
+//! is part of the statement, so the proof cannot be attached to another ballot.
+use crate::cryptography::{Ciphertext, CommitmentKey};
+use crate::{GroupElement, Scalar};
+use cryptoxide::blake2b::Blake2b;
+use cryptoxide::digest::Digest;
+use rand_core::{CryptoRng, RngCore};
+
+/// Proof of correct voting power.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Zkp {
+    challenge: Scalar,
+    response: Scalar,
+}
+
+impl Zkp {
+    pub const BYTES_LEN: usize = 2 * Scalar::BYTES_LEN;
+
+    /// Generate a voting power proof, given the `randomness` used in the
+    /// `commitment` to `weight`
+    pub(crate) fn generate<R>(
+        commitment_key: &CommitmentKey,
+        commitment: &GroupElement,
+        weight: u64,
+        randomness: &Scalar,
+        vote: &[Ciphertext],
+        rng: &mut R,
+    ) -> Self
+    where
+        R: CryptoRng + RngCore,
+    {
+        let w = Scalar::random(rng);
+        let announcement = &commitment_key.h * &w;
+        let challenge = challenge(commitment_key, commitment, weight, vote, &announcement);
+        let response = randomness * &challenge + &w;
+
+        Zkp {
+            challenge,
+            response,
+        }
+    }
+
+    /// Verify a voting power proof
+    pub(crate) fn verify(
+        &self,
+        commitment_key: &CommitmentKey,
+        commitment: &GroupElement,
+        weight: u64,
+        vote: &[Ciphertext],
+    ) -> bool {
+        let point = commitment - GroupElement::generator() * Scalar::from_u64(weight);
+        let announcement = &commitment_key.h * &self.response - point * &self.challenge;
+        // no need for constant time equality because of the hash in challenge()
+        challenge(commitment_key, commitment, weight, vote, &announcement) == self.challenge
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::BYTES_LEN] {
+        let mut output = [0u8; Self::BYTES_LEN];
+        output[0..Scalar::BYTES_LEN].copy_from_slice(&self.challenge.to_bytes());
+        output[Scalar::BYTES_LEN..].copy_from_slice(&self.response.to_bytes());
+        output
+    }
+
+    pub fn from_bytes(slice: &[u8]) -> Option<Self> {
+        if slice.len() != Self::BYTES_LEN {
+            return None;
+        }
+        let challenge = Scalar::from_bytes(&slice[..Scalar::BYTES_LEN])?;
+        let response = Scalar::from_bytes(&slice[Scalar::BYTES_LEN..])?;
+
+        Some(Zkp {
+            challenge,
+            response,
+        })
+    }
+}
+
+// The challenge includes the commitment key and the full statement, followed by
+// the announcement of the sigma protocol.
+fn challenge(
+    commitment_key: &CommitmentKey,
+    commitment: &GroupElement,
+    weight: u64,
+    vote: &[Ciphertext],
+    announcement: &GroupElement,
+) -> Scalar {
+    let mut ctx = Blake2b::new(64);
+    ctx.input(&commitment_key.to_bytes());
+    ctx.input(&commitment.to_bytes());
+    ctx.input(&weight.to_be_bytes());
+    for ciphertext in vote {
+        ctx.input(&ciphertext.to_bytes());
+    }
+    ctx.input(&announcement.to_bytes());
+    Scalar::hash_to_scalar(&ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tally::Crs;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn it_works() {
+        let mut r = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let commitment_key = CommitmentKey::from(Crs::from_hash(&[0u8]));
+        let weight = 42;
+        let (commitment, randomness) = commitment_key.commit(&Scalar::from_u64(weight), &mut r);
+        let vote = [Ciphertext::zero(), Ciphertext::zero()];
+
+        let proof = Zkp::generate(
+            &commitment_key,
+            &commitment,
+            weight,
+            &randomness,
+            &vote,
+            &mut r,
+        );
+        assert!(proof.verify(&commitment_key, &commitment, weight, &vote));
+        assert!(!proof.verify(&commitment_key, &commitment, weight + 1, &vote));
+        assert!(!proof.verify(&commitment_key, &commitment, weight, &vote[..1]));
+
+        let deserialised_proof = Zkp::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(proof, deserialised_proof);
+    }
+}
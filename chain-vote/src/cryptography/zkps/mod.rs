@@ -1,10 +1,12 @@
 mod correct_decryption;
 mod correct_hybrid_decryption_key;
 mod correct_share_generation;
+mod correct_voting_power;
 mod dl_equality;
 mod unit_vector;
 
 pub use correct_decryption::CorrectElGamalDecrZkp;
 pub use correct_hybrid_decryption_key::CorrectHybridDecrKeyZkp;
 pub use correct_share_generation::CorrectShareGenerationZkp;
+pub use correct_voting_power::CorrectVotingPowerZkp;
 pub use unit_vector::UnitVectorZkp;
@@ -147,34 +147,74 @@ impl Zkp {
     ///
     /// Description of the verification procedure available in Figure 9.
     pub fn verify(&self, crs: &Crs, public_key: &PublicKey, ciphertexts: &[Ciphertext]) -> bool {
-        let ck = CommitmentKey::from(crs.clone());
-        let ciphertexts = Ptp::new(ciphertexts.to_vec(), Ciphertext::zero);
-        let bits = ciphertexts.bits();
-        let mut cc = ChallengeContext::new(&ck, public_key, ciphertexts.as_ref());
-        let cy = cc.first_challenge(&self.ibas);
-        let cx = cc.second_challenge(&self.ds);
+        Self::batch_verify(
+            crs,
+            std::slice::from_ref(public_key),
+            &[(ciphertexts, self)],
+        )
+    }
 
-        if self.ibas.len() != bits {
+    /// Verify a batch of unit vector proofs, where the `i`th proof in `votes` is checked
+    /// against its ciphertexts and the `i`th public key of `public_keys`.
+    ///
+    /// The verification equations of all the proofs are combined with random weights, and
+    /// checked with a single vartime multiscalar multiplication. If the batch is rejected,
+    /// at least one of the proofs is invalid, but the batch does not tell which one.
+    pub fn batch_verify(
+        crs: &Crs,
+        public_keys: &[PublicKey],
+        votes: &[(&[Ciphertext], &Self)],
+    ) -> bool {
+        if public_keys.len() != votes.len() {
             return false;
         }
 
-        if self.zwvs.len() != bits {
-            return false;
+        let ck = CommitmentKey::from(crs.clone());
+        let mut rng = thread_rng();
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+
+        for (public_key, (ciphertexts, proof)) in public_keys.iter().zip(votes.iter()) {
+            let ciphertexts = Ptp::new(ciphertexts.to_vec(), Ciphertext::zero);
+            let bits = ciphertexts.bits();
+            if proof.ibas.len() != bits || proof.zwvs.len() != bits || proof.ds.len() != bits {
+                return false;
+            }
+
+            let mut cc = ChallengeContext::new(&ck, public_key, ciphertexts.as_ref());
+            let cy = cc.first_challenge(&proof.ibas);
+            let cx = cc.second_challenge(&proof.ds);
+
+            proof.verification_terms(
+                public_key,
+                &ck,
+                &ciphertexts,
+                &cx,
+                &cy,
+                &mut rng,
+                &mut scalars,
+                &mut points,
+            );
         }
 
-        self.verify_statements(public_key, &ck, &ciphertexts, &cx, &cy)
+        GroupElement::vartime_multiscalar_multiplication(scalars, points) == GroupElement::zero()
     }
 
-    /// Final verification of the proof, that we compute in a single vartime multiscalar
-    /// multiplication.
-    fn verify_statements(
+    /// Collect the terms of the verification equations of the proof, each equation
+    /// being weighted by a fresh random scalar. The proof is valid if the multiscalar
+    /// multiplication of the terms is zero.
+    #[allow(clippy::too_many_arguments)]
+    fn verification_terms<R: RngCore + CryptoRng>(
         &self,
         public_key: &PublicKey,
         commitment_key: &CommitmentKey,
         ciphertexts: &Ptp<Ciphertext>,
         challenge_x: &Scalar,
         challenge_y: &Scalar,
-    ) -> bool {
+        rng: &mut R,
+        scalars: &mut Vec<Scalar>,
+        points: &mut Vec<GroupElement>,
+    ) {
         let bits = ciphertexts.bits();
         let length = ciphertexts.len();
         let cx_pow = challenge_x.power(bits);
@@ -186,30 +226,31 @@ impl Zkp {
 
         let zero = public_key.encrypt_with_r(&Scalar::zero(), &self.r);
 
-        // Challenge value for batching two equations into a single multiscalar mult.
-        let batch_challenge = Scalar::random(&mut thread_rng());
-
         for (zwv, iba) in self.zwvs.iter().zip(self.ibas.iter()) {
-            if GroupElement::vartime_multiscalar_multiplication(
+            // Challenge value for batching the two equations of the announcement.
+            let batch_challenge = Scalar::random(rng);
+            let weight = Scalar::random(rng);
+            scalars.extend(
                 iter::once(zwv.z.clone())
                     .chain(iter::once(&zwv.w + &batch_challenge * &zwv.v))
                     .chain(iter::once(
                         &batch_challenge * (&zwv.z - challenge_x) - challenge_x,
                     ))
                     .chain(iter::once(Scalar::one().negate()))
-                    .chain(iter::once(batch_challenge.negate())),
+                    .chain(iter::once(batch_challenge.negate()))
+                    .map(|s| s * &weight),
+            );
+            points.extend(
                 iter::once(GroupElement::generator())
                     .chain(iter::once(commitment_key.h.clone()))
                     .chain(iter::once(iba.i.clone()))
                     .chain(iter::once(iba.b.clone()))
                     .chain(iter::once(iba.a.clone())),
-            ) != GroupElement::zero()
-            {
-                return false;
-            }
+            );
         }
 
-        let mega_check = GroupElement::vartime_multiscalar_multiplication(
+        let weight = Scalar::random(rng);
+        scalars.extend(
             powers_cy
                 .clone()
                 .take(length)
@@ -219,7 +260,10 @@ impl Zkp {
                 .chain(powers_cx.clone().take(bits))
                 .chain(powers_cx.take(bits))
                 .chain(iter::once(Scalar::one().negate()))
-                .chain(iter::once(Scalar::one().negate())),
+                .chain(iter::once(Scalar::one().negate()))
+                .map(|s| s * &weight),
+        );
+        points.extend(
             ciphertexts
                 .iter()
                 .map(|ctxt| ctxt.e2.clone())
@@ -230,8 +274,6 @@ impl Zkp {
                 .chain(iter::once(zero.e1.clone()))
                 .chain(iter::once(zero.e2)),
         );
-
-        mega_check == GroupElement::zero()
     }
 
     /// Try to generate a `Proof` from a buffer
@@ -465,6 +507,64 @@ mod tests {
         assert!(!proof.verify(&crs, &public_key, &fake_encryption))
     }
 
+    #[test]
+    fn batch_verify() {
+        let mut r = ChaCha20Rng::from_seed([0u8; 32]);
+        let shared_string =
+            b"Example of a shared string. This could be the latest block hash".to_owned();
+        let crs = Crs::from_hash(&shared_string);
+
+        let public_keys: Vec<PublicKey> = (0..4u8)
+            .map(|i| PublicKey {
+                pk: GroupElement::from_hash(&[i]),
+            })
+            .collect();
+        let votes: Vec<(Vec<Ciphertext>, Zkp)> = public_keys
+            .iter()
+            .enumerate()
+            .map(|(i, public_key)| {
+                let unit_vector = UnitVector::new(3 + i, i);
+                let encryption_randomness: Vec<Scalar> = (0..unit_vector.len())
+                    .map(|_| Scalar::random(&mut r))
+                    .collect();
+                let ciphertexts: Vec<Ciphertext> = unit_vector
+                    .iter()
+                    .zip(encryption_randomness.iter())
+                    .map(|(i, r)| public_key.encrypt_with_r(&Scalar::from(i), r))
+                    .collect();
+                let proof = Zkp::generate(
+                    &mut r,
+                    &crs,
+                    public_key,
+                    &unit_vector,
+                    &encryption_randomness,
+                    &ciphertexts,
+                );
+                (ciphertexts, proof)
+            })
+            .collect();
+        let statements: Vec<(&[Ciphertext], &Zkp)> = votes
+            .iter()
+            .map(|(ciphertexts, proof)| (ciphertexts.as_slice(), proof))
+            .collect();
+
+        assert!(Zkp::batch_verify(&crs, &public_keys, &statements));
+        assert!(Zkp::batch_verify(&crs, &[], &[]));
+        assert!(!Zkp::batch_verify(&crs, &public_keys[1..], &statements));
+
+        // a single proof checked against the wrong key invalidates the batch
+        let mut swapped_keys = public_keys.clone();
+        swapped_keys.swap(0, 1);
+        assert!(!Zkp::batch_verify(&crs, &swapped_keys, &statements));
+
+        // a single tampered ciphertext invalidates the batch
+        let mut tampered = votes[2].0.clone();
+        tampered[0] = &tampered[0] + &tampered[1];
+        let mut tampered_statements = statements.clone();
+        tampered_statements[2].0 = tampered.as_slice();
+        assert!(!Zkp::batch_verify(&crs, &public_keys, &tampered_statements));
+    }
+
     #[test]
     fn challenge_context() {
         let mut r = ChaCha20Rng::from_seed([0u8; 32]);
@@ -1,7 +1,8 @@
-use crate::cryptography::{Ciphertext, UnitVectorZkp};
+use crate::cryptography::{Ciphertext, CommitmentKey, CorrectVotingPowerZkp, UnitVectorZkp};
 use crate::tally::ElectionFingerprint;
-use crate::Scalar;
 use crate::{Crs, ElectionPublicKey};
+use crate::{GroupElement, Scalar};
+use rand_core::{CryptoRng, RngCore};
 /// A vote is represented by a standard basis unit vector of an N dimensional space
 ///
 /// Effectively each possible vote is represented by an axis, where the actual voted option
@@ -19,6 +20,28 @@ pub type EncryptedVote = Vec<Ciphertext>;
 /// the `EncryptedVote` is indeed a unit vector, and contains a vote for a single candidate.
 pub type ProofOfCorrectVote = UnitVectorZkp;
 
+/// A proof that the voting power a ballot is tallied with is the one committed in the
+/// voting power snapshot. The proof is bound to the `EncryptedVote` it was created for.
+pub type ProofOfVotingPower = CorrectVotingPowerZkp;
+
+/// Commitment to the voting power of a voter, as recorded in the voting power snapshot.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct VotingPowerCommitment(GroupElement);
+
+/// Opening of a `VotingPowerCommitment`, kept by the voter to prove its voting power.
+#[derive(Clone)]
+pub struct VotingPowerOpening {
+    weight: u64,
+    randomness: Scalar,
+}
+
+/// A verified ballot together with the voting power it is tallied with.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct WeightedBallot {
+    ballot: Ballot,
+    weight: u64,
+}
+
 /// Submitted ballot, which contains an always verified vote.
 /// Used for early verification of a vote without requiring additional
 /// checks down the chain.
@@ -51,6 +74,30 @@ impl Ballot {
         })
     }
 
+    /// Verify the proofs of a batch of votes for the same election at once (see
+    /// `ProofOfCorrectVote::batch_verify`), and build their ballots. Fails if any of
+    /// the proofs is invalid.
+    pub fn try_from_votes_and_proofs(
+        votes: Vec<(EncryptedVote, ProofOfCorrectVote)>,
+        crs: &Crs,
+        pk: &ElectionPublicKey,
+    ) -> Result<Vec<Self>, BallotVerificationError> {
+        let pks = vec![pk.0.clone(); votes.len()];
+        let statements: Vec<_> = votes
+            .iter()
+            .map(|(vote, proof)| (vote.as_slice(), proof))
+            .collect();
+        if !ProofOfCorrectVote::batch_verify(crs, &pks, &statements) {
+            return Err(BallotVerificationError);
+        }
+
+        let fingerprint = (pk, crs).into();
+        Ok(votes
+            .into_iter()
+            .map(|(vote, _)| Self { vote, fingerprint })
+            .collect())
+    }
+
     pub fn vote(&self) -> &EncryptedVote {
         &self.vote
     }
@@ -60,6 +107,80 @@ impl Ballot {
     }
 }
 
+impl VotingPowerCommitment {
+    pub const BYTES_LEN: usize = GroupElement::BYTES_LEN;
+
+    /// Commit to the voting power `weight`. The commitment is to be recorded in the
+    /// snapshot, while the opening is given to the voter.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        crs: &Crs,
+        weight: u64,
+    ) -> (Self, VotingPowerOpening) {
+        let ck = CommitmentKey::from(crs.clone());
+        let (commitment, randomness) = ck.commit(&Scalar::from_u64(weight), rng);
+        (
+            VotingPowerCommitment(commitment),
+            VotingPowerOpening { weight, randomness },
+        )
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::BYTES_LEN] {
+        self.0.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        GroupElement::from_bytes(bytes).map(VotingPowerCommitment)
+    }
+}
+
+impl VotingPowerOpening {
+    /// The committed voting power
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /// Prove that the voting power used for `vote` is the committed one
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        crs: &Crs,
+        vote: &EncryptedVote,
+    ) -> ProofOfVotingPower {
+        let ck = CommitmentKey::from(crs.clone());
+        let commitment =
+            &GroupElement::generator() * &Scalar::from_u64(self.weight) + &ck.h * &self.randomness;
+        ProofOfVotingPower::generate(&ck, &commitment, self.weight, &self.randomness, vote, rng)
+    }
+}
+
+impl WeightedBallot {
+    /// Attach the voting power `weight` to `ballot`, checking that `proof` shows it
+    /// matches the voter's `commitment` in the snapshot.
+    pub fn try_from_ballot(
+        ballot: Ballot,
+        weight: u64,
+        proof: &ProofOfVotingPower,
+        commitment: &VotingPowerCommitment,
+        crs: &Crs,
+    ) -> Result<Self, BallotVerificationError> {
+        let ck = CommitmentKey::from(crs.clone());
+        if !proof.verify(&ck, &commitment.0, weight, ballot.vote()) {
+            return Err(BallotVerificationError);
+        }
+
+        Ok(Self { ballot, weight })
+    }
+
+    pub fn ballot(&self) -> &Ballot {
+        &self.ballot
+    }
+
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+}
+
 /// To achieve logarithmic communication complexity in the unit_vector ZKP, we represent
 /// votes as Power of Two Padded vector structures.
 #[derive(Clone)]
@@ -27,8 +27,14 @@ const CURVE_HRP: &str = "ristretto255";
 pub use math::babystep::BabyStepsTable as TallyOptimizationTable;
 
 pub use crate::{
-    committee::{ElectionPublicKey, MemberCommunicationKey, MemberPublicKey, MemberState},
+    committee::{
+        ElectionPublicKey, EncryptedSecretShare, MemberCommitments, MemberCommunicationKey,
+        MemberPublicKey, MemberSecretShare, MemberState, SecretShareError, ThresholdCommittee,
+    },
     cryptography::Ciphertext, //todo: why this?
-    encrypted_vote::{Ballot, BallotVerificationError, EncryptedVote, ProofOfCorrectVote, Vote},
-    tally::{Crs, EncryptedTally, Tally, TallyDecryptShare},
+    encrypted_vote::{
+        Ballot, BallotVerificationError, EncryptedVote, ProofOfCorrectVote, ProofOfVotingPower,
+        Vote, VotingPowerCommitment, VotingPowerOpening, WeightedBallot,
+    },
+    tally::{Crs, EncryptedTally, MergeError, Tally, TallyDecryptShare, ThresholdDecryptShare},
 };
@@ -3,6 +3,7 @@
 #[cfg(crypto_backend = "__internal_ex_backend_p256k1")]
 use crate::Coordinate;
 use crate::{GroupElement, Scalar};
+#[cfg(feature = "rayon")]
 use rayon::prelude::*;
 use std::{collections::HashMap, num::NonZeroU64};
 
@@ -77,8 +78,11 @@ pub fn baby_step_giant_step(
     let baby_step_size = table.baby_step_size;
     let giant_step = &table.giant_step;
     let table = &table.table;
+    #[cfg(feature = "rayon")]
+    let points = points.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let points = points.into_iter();
     points
-        .into_par_iter()
         .map(|mut point| {
             let mut a = 0;
             loop {
@@ -84,6 +84,20 @@ impl Polynomial {
     }
 }
 
+/// Lagrange coefficient at x=0 of the point `at`, for the interpolation over all the
+/// points in `points` (which must contain `at` and have no duplicates).
+///
+/// l(0) = Π_{j != at} x_j / (x_j - at)
+pub fn lagrange_coefficient_at_zero(at: &Scalar, points: &[Scalar]) -> Scalar {
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for x in points.iter().filter(|x| *x != at) {
+        numerator = &numerator * x;
+        denominator = &denominator * (x - at);
+    }
+    numerator * denominator.inverse()
+}
+
 impl std::ops::Add<Polynomial> for Polynomial {
     type Output = Polynomial;
 
@@ -162,4 +176,26 @@ mod tests {
             assert_eq!(a, b);
         }
     }
+
+    #[test]
+    fn lagrange_interpolation_at_zero() {
+        // 7 + 3x + 2x^2
+        let poly = Polynomial::from_vec(vec![
+            Scalar::from_u64(7),
+            Scalar::from_u64(3),
+            Scalar::from_u64(2),
+        ]);
+        let points = [
+            Scalar::from_u64(1),
+            Scalar::from_u64(3),
+            Scalar::from_u64(4),
+        ];
+        let interpolated = Scalar::sum(
+            points
+                .iter()
+                .map(|x| poly.evaluate(x) * lagrange_coefficient_at_zero(x, &points)),
+        )
+        .unwrap();
+        assert_eq!(interpolated, poly.at_zero());
+    }
 }
@@ -3,7 +3,7 @@ use std::num::NonZeroU64;
 use crate::{
     committee::*,
     cryptography::{Ciphertext, CorrectShareGenerationZkp},
-    encrypted_vote::Ballot,
+    encrypted_vote::{Ballot, WeightedBallot},
     math::babystep::baby_step_giant_step,
     TallyOptimizationTable,
 };
@@ -12,6 +12,8 @@ use crate::GroupElement;
 use cryptoxide::blake2b::Blake2b;
 use cryptoxide::digest::Digest;
 use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 /// Secret key for opening vote
 pub type OpeningVoteKey = MemberSecretKey;
@@ -54,9 +56,8 @@ pub struct EncryptedTally {
 }
 
 /// `TallyDecryptShare` contains one decryption share per existing option. All committee
-/// members (todo: this will change once DKG is completed)
-/// need to submit a `TallyDecryptShare` in order to successfully decrypt
-/// the `EncryptedTally`.
+/// members need to submit a `TallyDecryptShare` in order to successfully decrypt
+/// the `EncryptedTally`, unless threshold decryption is used (see `ThresholdDecryptShare`).
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct TallyDecryptShare {
     elements: Vec<ProvenDecryptShare>,
@@ -74,10 +75,20 @@ pub struct TallyDecryptShare {
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct ValidatedTally {
     r: Vec<Ciphertext>,
-    decrypt_shares: Vec<TallyDecryptShare>,
+    // one combined decryption share per option
+    decrypt_shares: Vec<GroupElement>,
     max_stake: u64,
 }
 
+/// `ThresholdDecryptShare` is a `TallyDecryptShare` computed with a member's share of
+/// the election secret key (see `MemberSecretShare`). Any `threshold` of them are
+/// enough to decrypt the `EncryptedTally`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ThresholdDecryptShare {
+    member: usize,
+    share: TallyDecryptShare,
+}
+
 /// `ProvenDecryptShare` consists of a group element (the partial decryption), and `CorrectShareGenerationZkp`,
 /// a proof of correct decryption.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -101,6 +112,16 @@ pub struct TallyError;
 #[error("Incorrect decryption shares")]
 pub struct DecryptionError;
 
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum MergeError {
+    #[error("cannot merge tallies with {0} and {1} options")]
+    OptionsMismatch(usize, usize),
+    #[error("cannot merge tallies of different elections")]
+    ElectionMismatch,
+    #[error("merged tally stake overflows")]
+    StakeOverflow,
+}
+
 impl EncryptedTally {
     const MAX_STAKE_BYTES_LEN: usize = std::mem::size_of::<u64>();
 
@@ -132,6 +153,40 @@ impl EncryptedTally {
         self.max_stake += weight;
     }
 
+    /// Add a `ballot` to the tally, with the voting power that was proven for it.
+    /// See `add` for the requirements on the ballot.
+    pub fn add_weighted(&mut self, ballot: &WeightedBallot) {
+        self.add(ballot.ballot(), ballot.weight())
+    }
+
+    /// Merge two partial tallies of the same election, leveraging the additive homomorphic
+    /// property of the underlying ciphertexts. This allows ballots to be aggregated in
+    /// shards (e.g. per region), with the shards' tallies exchanged with `to_bytes` and
+    /// merged into the final `EncryptedTally`.
+    pub fn merge(&self, other: &Self) -> Result<Self, MergeError> {
+        if self.r.len() != other.r.len() {
+            return Err(MergeError::OptionsMismatch(self.r.len(), other.r.len()));
+        }
+        if self.fingerprint != other.fingerprint {
+            return Err(MergeError::ElectionMismatch);
+        }
+        let max_stake = self
+            .max_stake
+            .checked_add(other.max_stake)
+            .ok_or(MergeError::StakeOverflow)?;
+        let r = self
+            .r
+            .iter()
+            .zip(other.r.iter())
+            .map(|(left, right)| left + right)
+            .collect();
+        Ok(Self {
+            r,
+            fingerprint: self.fingerprint,
+            max_stake,
+        })
+    }
+
     /// Given a single committee member's `secret_key`, returns a partial decryption of
     /// the `EncryptedTally`
     pub fn partial_decrypt<R: RngCore + CryptoRng>(
@@ -167,19 +222,85 @@ impl EncryptedTally {
         pks: &[MemberPublicKey],
         decrypt_shares: &[TallyDecryptShare],
     ) -> Result<ValidatedTally, DecryptionError> {
-        for (pk, decrypt_share) in pks.iter().zip(decrypt_shares.iter()) {
-            if !decrypt_share.verify(self, pk) {
+        #[cfg(feature = "rayon")]
+        let shares = pks.par_iter().zip(decrypt_shares.par_iter());
+        #[cfg(not(feature = "rayon"))]
+        let mut shares = pks.iter().zip(decrypt_shares.iter());
+        if !shares.all(|(pk, decrypt_share)| decrypt_share.verify(self, pk)) {
+            return Err(DecryptionError);
+        }
+        let combined = (0..self.r.len())
+            .map(|i| GroupElement::sum(decrypt_shares.iter().map(|ds| &ds.elements[i].r1)))
+            .collect();
+        Ok(ValidatedTally {
+            r: self.r.clone(),
+            decrypt_shares: combined,
+            max_stake: self.max_stake,
+        })
+    }
+
+    /// Given a committee member's share of the election secret key, returns a partial
+    /// decryption of the `EncryptedTally` to be used for threshold decryption
+    pub fn threshold_partial_decrypt<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        secret_share: &MemberSecretShare,
+    ) -> ThresholdDecryptShare {
+        ThresholdDecryptShare {
+            member: secret_share.index(),
+            share: self.partial_decrypt(rng, secret_share.secret_key()),
+        }
+    }
+
+    /// Validates the threshold `decrypt_shares` of distinct members of the `committee`
+    /// against their share verification keys, and combines them into a `ValidatedTally`.
+    /// At least `committee.threshold()` shares are needed.
+    pub fn validate_threshold_decryptions(
+        &self,
+        committee: &ThresholdCommittee,
+        decrypt_shares: &[ThresholdDecryptShare],
+    ) -> Result<ValidatedTally, DecryptionError> {
+        if decrypt_shares.len() < committee.threshold() {
+            return Err(DecryptionError);
+        }
+        let members: Vec<usize> = decrypt_shares.iter().map(|ds| ds.member).collect();
+        for (i, member) in members.iter().enumerate() {
+            if *member >= committee.size() || members[..i].contains(member) {
                 return Err(DecryptionError);
             }
         }
+        #[cfg(feature = "rayon")]
+        let shares = decrypt_shares.par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let mut shares = decrypt_shares.iter();
+        if !shares.all(|ds| {
+            ds.share
+                .verify(self, &committee.share_verification_key(ds.member))
+        }) {
+            return Err(DecryptionError);
+        }
+
+        let lagrange = committee.lagrange_coefficients(&members);
+        let combined = (0..self.r.len())
+            .map(|i| {
+                decrypt_shares
+                    .iter()
+                    .zip(lagrange.iter())
+                    .fold(GroupElement::zero(), |acc, (ds, l)| {
+                        acc + &ds.share.elements[i].r1 * l
+                    })
+            })
+            .collect();
         Ok(ValidatedTally {
             r: self.r.clone(),
-            decrypt_shares: decrypt_shares.to_vec(),
+            decrypt_shares: combined,
             max_stake: self.max_stake,
         })
     }
 
-    /// Returns a byte array with every ciphertext in the `EncryptedTally`
+    /// Returns a byte array with every ciphertext in the `EncryptedTally`, preceded by the
+    /// election fingerprint and the tallied stake (little endian u64), so that partial
+    /// tallies can be merged after deserialization.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes: Vec<u8> = Vec::with_capacity(
             Ciphertext::BYTES_LEN * self.r.len()
@@ -196,10 +317,12 @@ impl EncryptedTally {
     }
 
     /// Tries to generate an `EncryptedTally` out of an array of bytes. Returns `None` if the
-    /// size of the byte array is not a multiply of `Ciphertext::BYTES_LEN`.
+    /// array is too short, or if the size of the ciphertexts part is not a multiple of
+    /// `Ciphertext::BYTES_LEN`.
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        let cyphertext_len =
-            bytes.len() - ElectionFingerprint::BYTES_LEN - Self::MAX_STAKE_BYTES_LEN;
+        let cyphertext_len = bytes
+            .len()
+            .checked_sub(ElectionFingerprint::BYTES_LEN + Self::MAX_STAKE_BYTES_LEN)?;
         if cyphertext_len % Ciphertext::BYTES_LEN != 0 {
             return None;
         }
@@ -236,14 +359,10 @@ impl ValidatedTally {
     // needs to compute the discrete logarithm of these values, which is performed in
     // `decrypt_tally`.
     fn decrypt(&self) -> Vec<GroupElement> {
-        let state: Vec<GroupElement> = self.r.iter().map(|c| c.e2.clone()).collect();
-        let ris = (0..state.len())
-            .map(|i| GroupElement::sum(self.decrypt_shares.iter().map(|ds| &ds.elements[i].r1)));
-
-        state
+        self.r
             .iter()
-            .zip(ris)
-            .map(|(r2, r1)| r2 - r1)
+            .zip(self.decrypt_shares.iter())
+            .map(|(c, r1)| &c.e2 - r1)
             .collect::<Vec<_>>()
     }
 
@@ -261,23 +380,10 @@ impl ValidatedTally {
 impl std::ops::Add for EncryptedTally {
     type Output = Self;
 
-    // Ads two `EncryptedTally`, leveraging the additive homomorphic property of the
-    // underlying ciphertexts. If the public keys or the crs are not equal, it panics
-    // todo: maybe we want to handle the errors?
+    // Adds two `EncryptedTally`, see `EncryptedTally::merge`. If the tallies cannot be
+    // merged, it panics.
     fn add(self, rhs: Self) -> Self::Output {
-        assert_eq!(self.r.len(), rhs.r.len());
-        let r = self
-            .r
-            .iter()
-            .zip(rhs.r.iter())
-            .map(|(left, right)| left + right)
-            .collect();
-        let max_stake = self.max_stake + rhs.max_stake;
-        Self {
-            r,
-            fingerprint: self.fingerprint,
-            max_stake,
-        }
+        self.merge(&rhs).unwrap()
     }
 }
 
@@ -311,12 +417,11 @@ impl TallyDecryptShare {
             return false;
         }
 
-        for (element, r) in self.elements.iter().zip(encrypted_tally.r.iter()) {
-            if !element.pi.verify(r, &element.r1, &pk.0) {
-                return false;
-            }
-        }
-        true
+        #[cfg(feature = "rayon")]
+        let elements = self.elements.par_iter().zip(encrypted_tally.r.par_iter());
+        #[cfg(not(feature = "rayon"))]
+        let mut elements = self.elements.iter().zip(encrypted_tally.r.iter());
+        elements.all(|(element, r)| element.pi.verify(r, &element.r1, &pk.0))
     }
 
     /// Number of voting options this tally decrypt share structure is
@@ -354,6 +459,37 @@ impl TallyDecryptShare {
     }
 }
 
+impl ThresholdDecryptShare {
+    /// Index of the committee member who produced the share
+    pub fn member(&self) -> usize {
+        self.member
+    }
+
+    /// The decryption share, to be verified against the member's share
+    /// verification key (see `ThresholdCommittee::share_verification_key`)
+    pub fn decrypt_share(&self) -> &TallyDecryptShare {
+        &self.share
+    }
+
+    /// The member index, as a 32-bit big endian integer, followed by the
+    /// bytes of the decryption share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = member_index_to_bytes(self.member).to_vec();
+        out.extend_from_slice(&self.share.to_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < MEMBER_INDEX_BYTES_LEN {
+            return None;
+        }
+
+        let (member, bytes) = member_index_from_bytes(bytes);
+        let share = TallyDecryptShare::from_bytes(bytes)?;
+        Some(ThresholdDecryptShare { member, share })
+    }
+}
+
 impl Tally {
     /// Verifies that `TallyDecryptShare` are correct decryptions of `encrypted_tally` for public
     /// keys `pks`.
@@ -424,7 +560,7 @@ fn trivial_convert(validated_tally: &ValidatedTally) -> Tally {
 mod tests {
     use super::*;
     use crate::cryptography::{Keypair, PublicKey};
-    use crate::encrypted_vote::Vote;
+    use crate::encrypted_vote::{Vote, VotingPowerCommitment};
     use crate::Ballot;
     use rand_chacha::ChaCha20Rng;
     use rand_core::{CryptoRng, RngCore, SeedableRng};
@@ -670,6 +806,76 @@ mod tests {
         assert!(tr.verify(&encrypted_tally, &[m1.public_key()], &shares));
     }
 
+    #[test]
+    fn weighted_ballots() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let shared_string =
+            b"Example of a shared string. This should be VotePlan.to_id()".to_owned();
+        let h = Crs::from_hash(&shared_string);
+
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let m1 = MemberState::new(&mut rng, 1, &h, &[mc1.to_public()], 0);
+        let participants = vec![m1.public_key()];
+        let ek = ElectionPublicKey::from_participants(&participants);
+
+        let vote_options = 3;
+        let mut encrypted_tally = EncryptedTally::new(vote_options, ek.clone(), h.clone());
+        let mut ballots = Vec::new();
+        for (choice, power) in [(0, 7), (2, 11), (0, 5)] {
+            let (commitment, opening) = VotingPowerCommitment::new(&mut rng, &h, power);
+            let (vote, proof) =
+                ek.encrypt_and_prove_vote(&mut rng, &h, Vote::new(vote_options, choice));
+            let power_proof = opening.prove(&mut rng, &h, &vote);
+            let ballot = Ballot::try_from_vote_and_proof(vote, &proof, &h, &ek).unwrap();
+
+            // the voter cannot claim more than the committed voting power
+            assert!(WeightedBallot::try_from_ballot(
+                ballot.clone(),
+                power + 1,
+                &power_proof,
+                &commitment,
+                &h
+            )
+            .is_err());
+
+            let weighted = WeightedBallot::try_from_ballot(
+                ballot,
+                opening.weight(),
+                &power_proof,
+                &commitment,
+                &h,
+            )
+            .unwrap();
+            encrypted_tally.add_weighted(&weighted);
+            ballots.push((weighted, power_proof, commitment));
+        }
+
+        // a voting power proof cannot be reused with another ballot
+        let (other, other_proof, other_commitment) = &ballots[1];
+        let (ballot, _, _) = &ballots[0];
+        assert!(WeightedBallot::try_from_ballot(
+            ballot.ballot().clone(),
+            other.weight(),
+            other_proof,
+            other_commitment,
+            &h
+        )
+        .is_err());
+
+        let shares = vec![encrypted_tally.partial_decrypt(&mut rng, m1.secret_key())];
+        let table = TallyOptimizationTable::generate_with_balance(
+            23.try_into().unwrap(),
+            1.try_into().unwrap(),
+        );
+        let tr = encrypted_tally
+            .validate_partial_decryptions(&participants, &shares)
+            .unwrap()
+            .decrypt_tally(&table)
+            .unwrap();
+        assert_eq!(tr.votes, vec![12, 0, 11]);
+    }
+
     #[test]
     fn tally_wrong_elements_size() {
         let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
@@ -720,6 +926,75 @@ mod tests {
         assert_eq!(tally, deserialized_tally);
     }
 
+    #[test]
+    fn merge_sharded_tallies() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let shared_string =
+            b"Example of a shared string. This should be VotePlan.to_id()".to_owned();
+        let h = Crs::from_hash(&shared_string);
+
+        let mc1 = MemberCommunicationKey::new(&mut rng);
+        let m1 = MemberState::new(&mut rng, 1, &h, &[mc1.to_public()], 0);
+        let participants = vec![m1.public_key()];
+        let ek = ElectionPublicKey::from_participants(&participants);
+
+        let vote_options = 3;
+        let ballots: Vec<_> = [0, 1, 2, 0, 0, 2]
+            .iter()
+            .map(|choice| get_encrypted_ballot(&mut rng, &ek, &h, Vote::new(vote_options, *choice)))
+            .collect();
+
+        let mut single = EncryptedTally::new(vote_options, ek.clone(), h.clone());
+        for (weight, ballot) in ballots.iter().enumerate() {
+            single.add(ballot, weight as u64 + 1);
+        }
+
+        // aggregate the ballots in 2 shards, exchanged as bytes
+        let shards: Vec<Vec<u8>> = ballots
+            .chunks(4)
+            .enumerate()
+            .map(|(shard, ballots)| {
+                let mut tally = EncryptedTally::new(vote_options, ek.clone(), h.clone());
+                for (i, ballot) in ballots.iter().enumerate() {
+                    tally.add(ballot, (shard * 4 + i) as u64 + 1);
+                }
+                tally.to_bytes()
+            })
+            .collect();
+        let merged = shards
+            .iter()
+            .map(|bytes| EncryptedTally::from_bytes(bytes).unwrap())
+            .try_fold(
+                EncryptedTally::new(vote_options, ek.clone(), h.clone()),
+                |acc, shard| acc.merge(&shard),
+            )
+            .unwrap();
+        assert_eq!(merged, single);
+
+        let shares = vec![merged.partial_decrypt(&mut rng, m1.secret_key())];
+        let tr = merged
+            .validate_partial_decryptions(&participants, &shares)
+            .unwrap()
+            .decrypt_tally(&TallyOptimizationTable::generate(21.try_into().unwrap()))
+            .unwrap();
+        assert_eq!(tr.votes, vec![10, 2, 9]);
+
+        assert_eq!(
+            merged.merge(&EncryptedTally::new(2, ek.clone(), h.clone())),
+            Err(MergeError::OptionsMismatch(3, 2))
+        );
+        assert_eq!(
+            merged.merge(&EncryptedTally::new(
+                vote_options,
+                ek,
+                Crs::from_hash(&[1u8])
+            )),
+            Err(MergeError::ElectionMismatch)
+        );
+        assert!(EncryptedTally::from_bytes(&[0u8; 16]).is_none());
+    }
+
     #[test]
     fn serialize_tally_decrypt_share() {
         let mut r = ChaCha20Rng::from_seed([0u8; 32]);
@@ -754,6 +1029,196 @@ mod tests {
         assert!(tally_from_bytes.is_some());
     }
 
+    fn threshold_committee<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        h: &Crs,
+        threshold: usize,
+        size: usize,
+    ) -> (ThresholdCommittee, Vec<MemberSecretShare>) {
+        let mcs: Vec<_> = (0..size)
+            .map(|_| MemberCommunicationKey::new(rng))
+            .collect();
+        let mc: Vec<_> = mcs.iter().map(|k| k.to_public()).collect();
+        let members: Vec<_> = (0..size)
+            .map(|i| MemberState::new(rng, threshold, h, &mc, i))
+            .collect();
+        let committee = ThresholdCommittee::new(members.iter().map(|m| m.commitments()).collect());
+        let secret_shares = members
+            .iter()
+            .zip(mcs.iter())
+            .map(|(m, mc)| {
+                let dealt: Vec<_> = members
+                    .iter()
+                    .filter_map(|dealer| dealer.encrypted_share_for(m.index()))
+                    .collect();
+                MemberSecretShare::combine(m, mc, &committee, &dealt).unwrap()
+            })
+            .collect();
+        (committee, secret_shares)
+    }
+
+    #[test]
+    fn threshold_encdec() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+
+        let shared_string =
+            b"Example of a shared string. This should be VotePlan.to_id()".to_owned();
+        let h = Crs::from_hash(&shared_string);
+
+        let (committee, secret_shares) = threshold_committee(&mut rng, &h, 2, 3);
+        assert_eq!(committee.threshold(), 2);
+        let ek = committee.election_public_key();
+
+        let vote_options = 2;
+        let e1 = get_encrypted_ballot(&mut rng, &ek, &h, Vote::new(vote_options, 0));
+        let e2 = get_encrypted_ballot(&mut rng, &ek, &h, Vote::new(vote_options, 1));
+        let e3 = get_encrypted_ballot(&mut rng, &ek, &h, Vote::new(vote_options, 0));
+
+        let mut encrypted_tally = EncryptedTally::new(vote_options, ek, h);
+        encrypted_tally.add(&e1, 1);
+        encrypted_tally.add(&e2, 3);
+        encrypted_tally.add(&e3, 4);
+
+        let shares: Vec<_> = secret_shares
+            .iter()
+            .map(|s| encrypted_tally.threshold_partial_decrypt(&mut rng, s))
+            .collect();
+
+        let table = TallyOptimizationTable::generate_with_balance(
+            20.try_into().unwrap(),
+            1.try_into().unwrap(),
+        );
+        // any 2 of the 3 members can decrypt
+        for subset in [[0, 1], [0, 2], [2, 1]] {
+            let subset_shares: Vec<_> = subset.iter().map(|i| shares[*i].clone()).collect();
+            let tr = encrypted_tally
+                .validate_threshold_decryptions(&committee, &subset_shares)
+                .unwrap()
+                .decrypt_tally(&table)
+                .unwrap();
+            assert_eq!(tr.votes, vec![5, 3]);
+        }
+        let tr = encrypted_tally
+            .validate_threshold_decryptions(&committee, &shares)
+            .unwrap()
+            .decrypt_tally(&table)
+            .unwrap();
+        assert_eq!(tr.votes, vec![5, 3]);
+
+        // not enough shares
+        assert!(encrypted_tally
+            .validate_threshold_decryptions(&committee, &shares[..1])
+            .is_err());
+        // the same member twice
+        assert!(encrypted_tally
+            .validate_threshold_decryptions(&committee, &[shares[0].clone(), shares[0].clone()])
+            .is_err());
+        // a share presented for another member
+        let forged = ThresholdDecryptShare {
+            member: 2,
+            share: shares[1].share.clone(),
+        };
+        assert!(encrypted_tally
+            .validate_threshold_decryptions(&committee, &[shares[0].clone(), forged])
+            .is_err());
+    }
+
+    #[test]
+    fn threshold_secret_share_errors() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = Crs::from_hash(&[1u8]);
+
+        let mcs: Vec<_> = (0..3)
+            .map(|_| MemberCommunicationKey::new(&mut rng))
+            .collect();
+        let mc: Vec<_> = mcs.iter().map(|k| k.to_public()).collect();
+        let members: Vec<_> = (0..3)
+            .map(|i| MemberState::new(&mut rng, 2, &h, &mc, i))
+            .collect();
+        let committee = ThresholdCommittee::new(members.iter().map(|m| m.commitments()).collect());
+
+        assert!(members[0].encrypted_share_for(0).is_none());
+        assert!(members[0].encrypted_share_for(3).is_none());
+
+        let from_1 = members[1].encrypted_share_for(0).unwrap();
+        let from_2 = members[2].encrypted_share_for(0).unwrap();
+        assert_eq!(
+            MemberSecretShare::combine(&members[0], &mcs[0], &committee, &[from_1.clone()]).err(),
+            Some(SecretShareError::MissingShare(2))
+        );
+        assert_eq!(
+            MemberSecretShare::combine(
+                &members[0],
+                &mcs[0],
+                &committee,
+                &[from_1.clone(), from_1.clone()]
+            )
+            .err(),
+            Some(SecretShareError::UnexpectedShare(1))
+        );
+        // decrypting with the wrong communication key gives an invalid share
+        assert_eq!(
+            MemberSecretShare::combine(
+                &members[0],
+                &mcs[1],
+                &committee,
+                &[from_1.clone(), from_2.clone()]
+            )
+            .err(),
+            Some(SecretShareError::InvalidShare(1))
+        );
+        let for_1 = members[2].encrypted_share_for(1).unwrap();
+        assert_eq!(
+            MemberSecretShare::combine(&members[0], &mcs[0], &committee, &[from_1.clone(), for_1])
+                .err(),
+            Some(SecretShareError::WrongRecipient(1))
+        );
+        assert!(
+            MemberSecretShare::combine(&members[0], &mcs[0], &committee, &[from_2, from_1]).is_ok()
+        );
+    }
+
+    #[test]
+    fn threshold_types_bytes_round_trip() {
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let h = Crs::from_hash(&[1u8]);
+
+        let mcs: Vec<_> = (0..3)
+            .map(|_| MemberCommunicationKey::new(&mut rng))
+            .collect();
+        let mc: Vec<_> = mcs.iter().map(|k| k.to_public()).collect();
+        let members: Vec<_> = (0..3)
+            .map(|i| MemberState::new(&mut rng, 2, &h, &mc, i))
+            .collect();
+
+        let commitments = members[1].commitments();
+        let bytes = commitments.to_bytes();
+        assert_eq!(bytes.len(), 2 * GroupElement::BYTES_LEN);
+        assert_eq!(MemberCommitments::from_bytes(&bytes), Some(commitments));
+        assert!(MemberCommitments::from_bytes(&[]).is_none());
+        assert!(MemberCommitments::from_bytes(&bytes[1..]).is_none());
+
+        let share = members[1].encrypted_share_for(2).unwrap();
+        let bytes = share.to_bytes();
+        let decoded = EncryptedSecretShare::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.dealer(), 1);
+        assert_eq!(decoded.recipient(), 2);
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert!(EncryptedSecretShare::from_bytes(&bytes[..8]).is_none());
+
+        let (committee, secret_shares) = threshold_committee(&mut rng, &h, 2, 3);
+        let encrypted_tally = EncryptedTally::new(2, committee.election_public_key(), h);
+        let decrypt_share = encrypted_tally.threshold_partial_decrypt(&mut rng, &secret_shares[2]);
+        let bytes = decrypt_share.to_bytes();
+        assert_eq!(bytes.len(), 4 + TallyDecryptShare::bytes_len(2));
+        assert_eq!(
+            ThresholdDecryptShare::from_bytes(&bytes),
+            Some(decrypt_share)
+        );
+        assert!(ThresholdDecryptShare::from_bytes(&bytes[..3]).is_none());
+        assert!(ThresholdDecryptShare::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    }
+
     #[test]
     fn batch_decrypt_empty_slice() {
         assert_eq!(batch_decrypt(&[]).unwrap(), []);
@@ -63,6 +63,24 @@ impl SmallBitmap {
         SmallBitmap(0u32)
     }
 
+    /// Create a bitmap from its raw representation
+    pub const fn from_u32(v: u32) -> Self {
+        SmallBitmap(v)
+    }
+
+    /// Get the raw representation of the bitmap
+    pub const fn to_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Iterate over the level indices set, in the order
+    /// of their elements in the sparse array
+    pub fn indices(self) -> impl Iterator<Item = LevelIndex> {
+        (0..32)
+            .filter(move |i| self.0 & (1 << i) != 0)
+            .map(LevelIndex)
+    }
+
     #[inline]
     pub fn is_empty(self) -> bool {
         self.0 == 0
@@ -1,13 +1,15 @@
 use super::hash::{Hash, HashedKey, Hasher};
 use super::node::{
-    insert_rec, lookup_one, remove_eq_rec, remove_rec, replace_rec, replace_with_rec, size_rec,
-    update_rec, Entry, LookupRet, Node, NodeIter,
+    insert_rec, insert_replace_rec, lookup_one, remove_eq_rec, remove_lookup_rec, remove_rec,
+    replace_rec, replace_with_rec, update_rec, Entry, LookupRet, Node, NodeIter,
 };
 pub use super::operation::{InsertError, RemoveError, ReplaceError, UpdateError};
+use super::sharedref::SharedRef;
 use std::borrow::Borrow;
+use std::convert::Infallible;
 use std::error::Error;
 use std::fmt::Debug;
-use std::iter::FromIterator;
+use std::iter::{FromIterator, FusedIterator};
 use std::marker::PhantomData;
 use std::mem::swap;
 use std::slice;
@@ -16,10 +18,23 @@ use std::slice;
 #[must_use = "`Hamt`s are not modified in place, instead modified copies are returned`"]
 pub struct Hamt<H: Hasher + Default, K: PartialEq + Eq + Hash, V> {
     root: Node<K, V>,
+    len: usize,
     hasher: PhantomData<H>,
 }
 
 pub struct HamtIter<'a, K, V> {
+    entries: EntriesIter<'a, K, V>,
+    remaining: usize,
+}
+
+/// Iterator over the keys of a HAMT
+pub struct Keys<'a, K, V>(HamtIter<'a, K, V>);
+
+/// Iterator over the values of a HAMT
+pub struct Values<'a, K, V>(HamtIter<'a, K, V>);
+
+/// Depth-first iteration over the elements below some node children
+pub(crate) struct EntriesIter<'a, K, V> {
     stack: Vec<NodeIter<'a, K, V>>,
     content: Option<slice::Iter<'a, (K, V)>>,
 }
@@ -34,16 +49,34 @@ impl<H: Hasher + Default, K: Eq + Hash, V> Hamt<H, K, V> {
     pub fn new() -> Self {
         Hamt {
             root: Node::new(),
+            len: 0,
             hasher: PhantomData,
         }
     }
 
+    pub(crate) fn from_root(root: Option<Node<K, V>>, len: usize) -> Self {
+        match root {
+            None => Self::new(),
+            Some(root) => Hamt {
+                root,
+                len,
+                hasher: PhantomData,
+            },
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.root.is_empty()
+        self.len == 0
+    }
+
+    /// Number of elements in the HAMT, in constant time
+    pub fn len(&self) -> usize {
+        self.len
     }
 
+    /// Number of elements in the HAMT, same as `len`
     pub fn size(&self) -> usize {
-        size_rec(&self.root)
+        self.len
     }
 }
 
@@ -51,10 +84,22 @@ impl<H: Hasher + Default, K: Clone + Eq + Hash, V: Clone> Hamt<H, K, V> {
     pub fn insert(&self, k: K, v: V) -> Result<Self, InsertError> {
         let h = HashedKey::compute(self.hasher, &k);
         let newroot = insert_rec(&self.root, h, 0, k, v)?;
-        Ok(Hamt {
-            root: newroot,
-            hasher: PhantomData,
-        })
+        Ok(Self::from_root(Some(newroot), self.len + 1))
+    }
+}
+
+impl<H: Hasher + Default, K: Clone + Eq + Hash, V: Clone> Hamt<H, K, V> {
+    /// Insert the element at the key, replacing the current value if the key
+    /// is already present. Return the new tree and the replaced value, if any.
+    pub fn insert_replace(&self, k: K, v: V) -> (Self, Option<V>) {
+        let h = HashedKey::compute(self.hasher, &k);
+        let (newroot, oldv) = insert_replace_rec(&self.root, h, 0, k, v);
+        let len = if oldv.is_some() {
+            self.len
+        } else {
+            self.len + 1
+        };
+        (Self::from_root(Some(newroot), len), oldv)
     }
 }
 
@@ -66,13 +111,7 @@ impl<H: Hasher + Default, K: Eq + Hash + Clone, V: PartialEq + Clone> Hamt<H, K,
     {
         let h = HashedKey::compute(self.hasher, &k);
         let newroot = remove_eq_rec(&self.root, h, 0, k, v)?;
-        match newroot {
-            None => Ok(Self::new()),
-            Some(r) => Ok(Hamt {
-                root: r,
-                hasher: PhantomData,
-            }),
-        }
+        Ok(Self::from_root(newroot, self.len - 1))
     }
 }
 
@@ -84,13 +123,19 @@ impl<H: Hasher + Default, K: Clone + Eq + Hash, V: Clone> Hamt<H, K, V> {
     {
         let h = HashedKey::compute(self.hasher, k);
         let newroot = remove_rec(&self.root, h, 0, k)?;
-        match newroot {
-            None => Ok(Self::new()),
-            Some(r) => Ok(Hamt {
-                root: r,
-                hasher: PhantomData,
-            }),
-        }
+        Ok(Self::from_root(newroot, self.len - 1))
+    }
+
+    /// Remove the element at the key and return the new tree
+    /// and the removed value.
+    pub fn remove_lookup<Q>(&self, k: &Q) -> Result<(Self, V), RemoveError>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let h = HashedKey::compute(self.hasher, k);
+        let (newroot, v) = remove_lookup_rec(&self.root, h, 0, k)?;
+        Ok((Self::from_root(newroot, self.len - 1), v))
     }
 }
 
@@ -100,13 +145,7 @@ impl<H: Hasher + Default, K: Eq + Hash + Clone, V: Clone> Hamt<H, K, V> {
     pub fn replace(&self, k: &K, v: V) -> Result<(Self, V), ReplaceError> {
         let h = HashedKey::compute(self.hasher, &k);
         let (newroot, oldv) = replace_rec(&self.root, h, 0, k, v)?;
-        Ok((
-            Hamt {
-                root: newroot,
-                hasher: PhantomData,
-            },
-            oldv,
-        ))
+        Ok((Self::from_root(Some(newroot), self.len), oldv))
     }
 
     /// Replace the element at the key by the v and return the new tree
@@ -117,10 +156,7 @@ impl<H: Hasher + Default, K: Eq + Hash + Clone, V: Clone> Hamt<H, K, V> {
     {
         let h = HashedKey::compute(self.hasher, &k);
         let newroot = replace_with_rec(&self.root, h, 0, k, f)?;
-        Ok(Hamt {
-            root: newroot,
-            hasher: PhantomData,
-        })
+        Ok(Self::from_root(Some(newroot), self.len))
     }
 }
 
@@ -136,14 +172,14 @@ impl<H: Hasher + Default, K: Eq + Hash + Clone, V: Clone> Hamt<H, K, V> {
         U: Error + Debug + 'static,
     {
         let h = HashedKey::compute(self.hasher, &k);
-        let newroot = update_rec(&self.root, h, 0, k, f)?;
-        match newroot {
-            None => Ok(Self::new()),
-            Some(r) => Ok(Hamt {
-                root: r,
-                hasher: PhantomData,
-            }),
-        }
+        let mut removed = false;
+        let newroot = update_rec(&self.root, h, 0, k, |v| {
+            let newv = f(v)?;
+            removed = newv.is_none();
+            Ok(newv)
+        })?;
+        let len = if removed { self.len - 1 } else { self.len };
+        Ok(Self::from_root(newroot, len))
     }
 
     /// Update or insert the element at the key K
@@ -190,6 +226,86 @@ impl<H: Hasher + Default, K: Eq + Hash + Clone, V: Clone> Hamt<H, K, V> {
     }
 }
 
+impl<H: Hasher + Default, K: Eq + Hash + Clone, V: Clone> Hamt<H, K, V> {
+    /// Merge two HAMTs, keeping the keys present in either of them.
+    ///
+    /// When a key is present in both, the closure is called with the value
+    /// from `self` then the value from `other`, and returns the merged value,
+    /// or None to leave the key out of the result.
+    ///
+    /// The entries of the smaller HAMT are applied onto the larger one, so
+    /// the nodes of the larger HAMT that are not modified are shared with
+    /// the result.
+    pub fn merge_with<F>(&self, other: &Self, mut f: F) -> Self
+    where
+        F: FnMut(&K, &V, &V) -> Option<V>,
+    {
+        let (base, entries, swapped) = if self.len >= other.len {
+            (self, other, false)
+        } else {
+            (other, self, true)
+        };
+        let mut result = base.clone();
+        for (k, v) in entries.iter() {
+            let updated = result.update(k, |base_v| {
+                let merged = if swapped {
+                    f(k, v, base_v)
+                } else {
+                    f(k, base_v, v)
+                };
+                Ok::<_, Infallible>(merged)
+            });
+            result = match updated {
+                Ok(new_self) => new_self,
+                Err(UpdateError::KeyNotFound) => result
+                    .insert(k.clone(), v.clone())
+                    .expect("the key is not present"),
+                Err(UpdateError::ValueCallbackError(e)) => match e {},
+            };
+        }
+        result
+    }
+
+    /// Keep only the keys present in both HAMTs.
+    ///
+    /// The closure is called with the value from `self` then the value
+    /// from `other`, and returns the merged value, or None to leave the key
+    /// out of the result.
+    ///
+    /// The result is built from the smaller HAMT, sharing its nodes
+    /// that are not modified.
+    pub fn intersection_with<F>(&self, other: &Self, mut f: F) -> Self
+    where
+        F: FnMut(&K, &V, &V) -> Option<V>,
+    {
+        let (base, lookup, swapped) = if self.len <= other.len {
+            (self, other, false)
+        } else {
+            (other, self, true)
+        };
+        let mut result = base.clone();
+        for (k, v) in base.iter() {
+            let merged = lookup.lookup(k).and_then(|lookup_v| {
+                if swapped {
+                    f(k, lookup_v, v)
+                } else {
+                    f(k, v, lookup_v)
+                }
+            });
+            result = match merged {
+                None => result.remove(k).expect("the key is present"),
+                Some(merged) => result.replace(k, merged).expect("the key is present").0,
+            };
+        }
+        result
+    }
+
+    /// Keep only the keys present in exactly one of the HAMTs.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.merge_with(other, |_, _, _| None)
+    }
+}
+
 impl<H: Hasher + Default, K: Hash + Eq, V> Hamt<H, K, V> {
     /// Try to get the element related to key K
     pub fn lookup<Q>(&self, k: &Q) -> Option<&V>
@@ -223,7 +339,43 @@ impl<H: Hasher + Default, K: Hash + Eq, V> Hamt<H, K, V> {
 
     pub fn iter(&self) -> HamtIter<K, V> {
         HamtIter {
-            stack: vec![self.root.iter()],
+            entries: EntriesIter::from_children(&self.root.children),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<K, V> {
+        Keys(self.iter())
+    }
+
+    pub fn values(&self) -> Values<K, V> {
+        Values(self.iter())
+    }
+
+    pub(crate) fn root(&self) -> &Node<K, V> {
+        &self.root
+    }
+}
+
+impl<H: Hasher + Default, K: Eq + Hash + Ord, V> Hamt<H, K, V> {
+    /// Iterate over the elements in the order of their keys.
+    ///
+    /// The order of `iter` depends on the hashes of the keys, whereas this
+    /// order is deterministic, e.g. for hashing the content of the HAMT.
+    /// The elements are collected and sorted first, so this costs
+    /// O(n log n) time and O(n) memory.
+    pub fn iter_sorted_by_key(&self) -> std::vec::IntoIter<(&K, &V)> {
+        let mut elements: Vec<_> = self.iter().collect();
+        elements.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        elements.into_iter()
+    }
+}
+
+impl<'a, K, V> EntriesIter<'a, K, V> {
+    /// Iterate over the elements below the given children of a node
+    pub(crate) fn from_children(children: &'a [SharedRef<Entry<K, V>>]) -> Self {
+        EntriesIter {
+            stack: vec![children.iter()],
             content: None,
         }
     }
@@ -232,6 +384,58 @@ impl<H: Hasher + Default, K: Hash + Eq, V> Hamt<H, K, V> {
 impl<'a, K, V> Iterator for HamtIter<'a, K, V> {
     type Item = (&'a K, &'a V);
 
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.entries.next();
+        if next.is_some() {
+            self.remaining -= 1;
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for HamtIter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for HamtIter<'a, K, V> {}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> Iterator for EntriesIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let mut x = None;
@@ -278,12 +482,12 @@ impl<H: Default + Hasher, K: Eq + Hash + Clone, V: Clone> FromIterator<(K, V)> f
 impl<H: Default + Hasher, K: Eq + Hash, V: PartialEq> PartialEq for Hamt<H, K, V> {
     fn eq(&self, other: &Self) -> bool {
         // optimised the obvious cases first
-        if self.is_empty() && other.is_empty() {
-            return true;
-        }
-        if self.is_empty() != other.is_empty() {
+        if self.len != other.len {
             return false;
         }
+        if self.is_empty() {
+            return true;
+        }
         // then compare key and values
         // TODO : optimise by comparing nodes directly
         for (k, v) in self.iter() {
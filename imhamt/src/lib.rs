@@ -6,9 +6,14 @@ mod hash;
 mod helper;
 mod node;
 mod operation;
+#[cfg(feature = "rayon")]
+mod par;
 mod sharedref;
+mod snapshot;
+mod stats;
 
 pub use hamt::*;
+pub use stats::HamtStats;
 
 #[cfg(test)]
 mod tests {
@@ -32,6 +37,9 @@ mod tests {
         UpdateRemoval(usize),
         Replace(usize, u32),
         ReplaceWith(usize),
+        InsertReplace(Vec<u8>, u32),
+        InsertReplaceExisting(usize, u32),
+        RemoveLookup(usize),
     }
 
     #[test]
@@ -93,6 +101,20 @@ mod tests {
         assert_eq!(h.size(), 0)
     }
 
+    #[test]
+    fn iter_exact_size() {
+        let h: Hamt<DefaultHasher, u32, u32> = (0..100).map(|i| (i, i)).collect();
+        let mut iter = h.iter();
+        for i in 0..100 {
+            assert_eq!(iter.len(), 100 - i);
+            iter.next().unwrap();
+        }
+        assert_eq!(iter.len(), 0);
+        assert!(iter.next().is_none());
+        assert_eq!(h.keys().len(), 100);
+        assert_eq!(h.values().sum::<u32>(), (0..100).sum());
+    }
+
     #[test]
     fn delete_key_not_exist() {
         let mut h: Hamt<DefaultHasher, &String, u32> = Hamt::new();
@@ -344,6 +366,32 @@ mod tests {
                         h = h.update(&k, next_u32).unwrap();
                     }
                 },
+                PlanOperation::InsertReplace(k, v) => {
+                    let oldv = reference.insert(k.clone(), *v);
+                    let (newh, h_oldv) = h.insert_replace(k.clone(), *v);
+                    assert_eq!(oldv, h_oldv);
+                    h = newh;
+                }
+                PlanOperation::InsertReplaceExisting(r, newv) => {
+                    match get_key_nth(&reference, *r) {
+                        None => continue,
+                        Some(k) => {
+                            let oldv = reference.insert(k.clone(), *newv);
+                            let (newh, h_oldv) = h.insert_replace(k, *newv);
+                            assert_eq!(oldv, h_oldv);
+                            h = newh;
+                        }
+                    }
+                }
+                PlanOperation::RemoveLookup(r) => match get_key_nth(&reference, *r) {
+                    None => continue,
+                    Some(k) => {
+                        let v = reference.remove(&k);
+                        let (newh, h_v) = h.remove_lookup(&k).unwrap();
+                        assert_eq!(v, Some(h_v));
+                        h = newh;
+                    }
+                },
                 PlanOperation::UpdateRemoval(r) => match get_key_nth(&reference, *r) {
                     None => continue,
                     Some(k) => {
@@ -371,6 +419,7 @@ mod tests {
     ) {
         let (h, reference) = data;
         prop_assert!(property_btreemap_eq(&reference, &h));
+        prop_assert_eq!(h.len(), reference.len());
     }
 
     #[proptest]
@@ -383,6 +432,86 @@ mod tests {
         use std::iter::FromIterator;
         let (h, reference) = data;
         let after_iter = BTreeMap::from_iter(h.iter().map(|(k, v)| (k.clone(), *v)));
-        prop_assert_eq!(reference, after_iter);
+        prop_assert_eq!(&reference, &after_iter);
+        prop_assert_eq!(h.iter().len(), reference.len());
+
+        let mut keys: Vec<_> = h.keys().cloned().collect();
+        keys.sort();
+        prop_assert_eq!(keys, reference.keys().cloned().collect::<Vec<_>>());
+
+        let mut values: Vec<_> = h.values().copied().collect();
+        values.sort_unstable();
+        let mut expected_values: Vec<_> = reference.values().copied().collect();
+        expected_values.sort_unstable();
+        prop_assert_eq!(values, expected_values);
+    }
+
+    #[proptest]
+    fn iter_sorted_equivalent(
+        #[allow(clippy::type_complexity)]
+        #[strategy(arbitrary_hamt_and_btree())]
+        data: (Hamt<DefaultHasher, Vec<u8>, u32>, BTreeMap<Vec<u8>, u32>),
+    ) {
+        let (h, reference) = data;
+        prop_assert!(h.iter_sorted_by_key().eq(reference.iter()));
+    }
+
+    #[proptest]
+    fn merge_equivalent(
+        #[allow(clippy::type_complexity)]
+        #[strategy(arbitrary_hamt_and_btree())]
+        left: (Hamt<DefaultHasher, Vec<u8>, u32>, BTreeMap<Vec<u8>, u32>),
+        #[allow(clippy::type_complexity)]
+        #[strategy(arbitrary_hamt_and_btree())]
+        right: (Hamt<DefaultHasher, Vec<u8>, u32>, BTreeMap<Vec<u8>, u32>),
+    ) {
+        let (h1, r1) = left;
+        let (h2, r2) = right;
+        // a resolver that is not symmetric and removes some of the keys
+        let resolve = |_: &Vec<u8>, a: &u32, b: &u32| {
+            let merged = a.wrapping_mul(3).wrapping_add(*b);
+            if merged % 5 == 0 {
+                None
+            } else {
+                Some(merged)
+            }
+        };
+
+        let mut union = r1.clone();
+        let mut intersection = BTreeMap::new();
+        let mut symmetric_difference = r1.clone();
+        for (k, v) in r2.iter() {
+            match r1.get(k) {
+                None => {
+                    union.insert(k.clone(), *v);
+                    symmetric_difference.insert(k.clone(), *v);
+                }
+                Some(v1) => {
+                    symmetric_difference.remove(k);
+                    match resolve(k, v1, v) {
+                        None => {
+                            union.remove(k);
+                        }
+                        Some(merged) => {
+                            union.insert(k.clone(), merged);
+                            intersection.insert(k.clone(), merged);
+                        }
+                    }
+                }
+            }
+        }
+
+        let h_union = h1.merge_with(&h2, resolve);
+        let h_intersection = h1.intersection_with(&h2, resolve);
+        let h_symmetric_difference = h1.symmetric_difference(&h2);
+        prop_assert!(property_btreemap_eq(&union, &h_union));
+        prop_assert_eq!(h_union.len(), union.len());
+        prop_assert!(property_btreemap_eq(&intersection, &h_intersection));
+        prop_assert_eq!(h_intersection.len(), intersection.len());
+        prop_assert!(property_btreemap_eq(
+            &symmetric_difference,
+            &h_symmetric_difference
+        ));
+        prop_assert_eq!(h_symmetric_difference.len(), symmetric_difference.len());
     }
 }
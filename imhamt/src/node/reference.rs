@@ -79,6 +79,15 @@ impl<K: Clone + PartialEq, V: Clone> Collision<K, V> {
         }
     }
 
+    pub fn remove_lookup<Q>(&self, h: HashedKey, k: &Q) -> Result<(Entry<K, V>, V), RemoveError>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq,
+    {
+        let (_, (_, v)) = self.get_record_and_pos(k).ok_or(RemoveError::KeyNotFound)?;
+        Ok((self.remove(h, k)?, v.clone()))
+    }
+
     pub fn remove_match<Q>(&self, h: HashedKey, k: &Q, v: &V) -> Result<Entry<K, V>, RemoveError>
     where
         K: Borrow<Q>,
@@ -318,6 +327,50 @@ pub fn insert_rec<K: Clone + PartialEq, V: Clone>(
     }
 }
 
+// Insert leaf recursively, replacing the value if the key is already present
+// and returning the old value in that case.
+pub fn insert_replace_rec<K: Clone + PartialEq, V: Clone>(
+    node: &Node<K, V>,
+    hash: HashedKey,
+    lvl: usize,
+    key: K,
+    value: V,
+) -> (Node<K, V>, Option<V>) {
+    let level_hash = hash.level_index(lvl);
+    let idx = node.bitmap.get_index_sparse(level_hash);
+    if idx.is_not_found() {
+        let e = SharedRef::new(Entry::Leaf(hash, key, value));
+        return (node.set_at(level_hash, e), None);
+    }
+    match node.get_child(idx).as_ref() {
+        Entry::Leaf(lh, lk, lv) if *lh == hash && lk == &key => {
+            let e = SharedRef::new(Entry::Leaf(hash, key, value));
+            (node.replace_at(idx, e), Some(lv.clone()))
+        }
+        Entry::LeafMany(lh, col) if *lh == hash => {
+            let (col, oldv) = if col.get_record_and_pos(&key).is_some() {
+                let (col, oldv) = col.replace(&key, value).expect("the key is present");
+                (col, Some(oldv))
+            } else {
+                let col = col.insert(key, value).expect("the key is not present");
+                (col, None)
+            };
+            let e = SharedRef::new(Entry::LeafMany(*lh, col));
+            (node.replace_at(idx, e), oldv)
+        }
+        Entry::SubNode(sub) => {
+            let (r, oldv) = insert_replace_rec(sub, hash, lvl + 1, key, value);
+            let e = SharedRef::new(Entry::SubNode(r));
+            (node.replace_at(idx, e), oldv)
+        }
+        _ => {
+            // the key is not present: the insertion cannot fail
+            let r = insert_rec(node, hash, lvl, key, value).expect("the key is not present");
+            (r, None)
+        }
+    }
+}
+
 pub enum LookupRet<'a, K, V> {
     Found(&'a V),
     NotFound,
@@ -445,6 +498,47 @@ where
     }
 }
 
+// recursively try to remove a key, returning the removed value
+pub fn remove_lookup_rec<Q, K, V>(
+    node: &Node<K, V>,
+    h: HashedKey,
+    lvl: usize,
+    k: &Q,
+) -> Result<(Option<Node<K, V>>, V), RemoveError>
+where
+    Q: PartialEq,
+    K: Borrow<Q> + PartialEq + Clone,
+    V: Clone,
+{
+    let level_hash = h.level_index(lvl);
+    let idx = node.bitmap.get_index_sparse(level_hash);
+    if idx.is_not_found() {
+        Err(RemoveError::KeyNotFound)
+    } else {
+        match node.get_child(idx).as_ref() {
+            Entry::Leaf(lh, lk, lv) => {
+                if *lh == h && lk.borrow() == k {
+                    Ok((node.clear_at(level_hash), lv.clone()))
+                } else {
+                    Err(RemoveError::KeyNotFound)
+                }
+            }
+            Entry::LeafMany(lh, col) => {
+                assert_eq!(*lh, h);
+                let (replacement, v) = col.remove_lookup(h, k)?;
+                Ok((Some(node.replace_at(idx, SharedRef::new(replacement))), v))
+            }
+            Entry::SubNode(sub) => match remove_lookup_rec(sub, h, lvl + 1, k)? {
+                (None, v) => Ok((node.clear_at(level_hash), v)),
+                (Some(newsub), v) => {
+                    let e = Entry::SubNode(newsub);
+                    Ok((Some(node.replace_at(idx, SharedRef::new(e))), v))
+                }
+            },
+        }
+    }
+}
+
 // recursively try to update a key.
 //
 // note, an update cannot create a new value, it can only delete or update an existing value.
@@ -572,18 +666,6 @@ where
     }
 }
 
-pub fn size_rec<K, V>(node: &Node<K, V>) -> usize {
-    let mut sum = 0;
-    for c in node.children.iter() {
-        match &c.as_ref() {
-            Entry::Leaf(_, _, _) => sum += 1,
-            Entry::LeafMany(_, col) => sum += col.len(),
-            Entry::SubNode(sub) => sum += size_rec(sub),
-        }
-    }
-    sum
-}
-
 // debug module
 pub mod debug {
     use super::*;
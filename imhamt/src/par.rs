@@ -0,0 +1,87 @@
+//! Parallel iteration over a HAMT, with rayon
+//!
+//! The work is split at node boundaries: the children of a node are divided
+//! in halves, and a single child which is itself a node is divided in turn,
+//! so that each task iterates a whole subtree sequentially.
+
+use super::hamt::{EntriesIter, Hamt};
+use super::hash::{Hash, Hasher};
+use super::node::Entry;
+use super::sharedref::SharedRef;
+use rayon::iter::{self, ParallelIterator};
+
+type Children<'a, K, V> = &'a [SharedRef<Entry<K, V>>];
+
+fn split_children<K, V>(children: Children<K, V>) -> (Children<K, V>, Option<Children<K, V>>) {
+    match children {
+        [] => (children, None),
+        [single] => match single.as_ref() {
+            Entry::SubNode(sub) if sub.children.len() > 1 => {
+                let (left, right) = sub.children.split_at(sub.children.len() / 2);
+                (left, Some(right))
+            }
+            Entry::SubNode(sub) => split_children(&sub.children),
+            _ => (children, None),
+        },
+        _ => {
+            let (left, right) = children.split_at(children.len() / 2);
+            (left, Some(right))
+        }
+    }
+}
+
+impl<H, K, V> Hamt<H, K, V>
+where
+    H: Hasher + Default,
+    K: Eq + Hash + Send + Sync,
+    V: Send + Sync,
+{
+    /// Iterate over the elements of the HAMT in parallel.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&K, &V)> + '_ {
+        iter::split(&self.root().children[..], split_children)
+            .flat_map_iter(EntriesIter::from_children)
+    }
+
+    /// Fold the elements of the HAMT in parallel.
+    ///
+    /// Each task folds a part of the HAMT starting from a value created by
+    /// `identity`, then the results of the tasks are combined with `reduce`.
+    pub fn par_fold<T, ID, F, R>(&self, identity: ID, fold: F, reduce: R) -> T
+    where
+        T: Send,
+        ID: Fn() -> T + Sync + Send,
+        F: Fn(T, (&K, &V)) -> T + Sync + Send,
+        R: Fn(T, T) -> T + Sync + Send,
+    {
+        self.par_iter()
+            .fold(&identity, fold)
+            .reduce(&identity, reduce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn par_iter_visits_all_elements() {
+        let h: Hamt<DefaultHasher, u32, u64> = (0..10_000u32).map(|i| (i, i as u64)).collect();
+
+        let mut keys: Vec<u32> = h.par_iter().map(|(k, _)| *k).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (0..10_000u32).collect::<Vec<_>>());
+
+        let sum = h.par_fold(|| 0u64, |acc, (_, v)| acc + v, |a, b| a + b);
+        assert_eq!(sum, (0..10_000u64).sum::<u64>());
+    }
+
+    #[test]
+    fn par_iter_small_and_empty() {
+        let empty: Hamt<DefaultHasher, u32, u32> = Hamt::new();
+        assert_eq!(empty.par_iter().count(), 0);
+
+        let single = empty.insert(1, 2).unwrap();
+        assert_eq!(single.par_iter().collect::<Vec<_>>(), vec![(&1, &2)]);
+    }
+}
@@ -0,0 +1,274 @@
+//! Binary snapshots of the HAMT structure
+//!
+//! A snapshot records the shape of the trie, and not only its elements, so
+//! that reloading it gives back the exact same nodes without going through
+//! insertions. The encoding of the keys and values is left to the caller.
+//!
+//! ```text
+//! SNAPSHOT  := LENGTH (u64) || NODE
+//! NODE      := BITMAP (u32) || CHILD * popcount(BITMAP)
+//! CHILD     := 0 (u8) || ENTRY
+//!            | 1 (u8) || COUNT (u32) || ENTRY * COUNT
+//!            | 2 (u8) || NODE
+//! ```
+//!
+//! All the integers are big endian. When reading a snapshot, the hashes of the
+//! keys are recomputed and checked against the position of the entries in
+//! the trie.
+
+use super::bitmap::SmallBitmap;
+use super::hamt::Hamt;
+use super::hash::{Hash, HashedKey, Hasher, LevelIndex};
+use super::node::{Collision, Entry, Node};
+use super::sharedref::SharedRef;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+const TAG_LEAF: u8 = 0;
+const TAG_LEAF_MANY: u8 = 1;
+const TAG_SUBNODE: u8 = 2;
+
+// with 5 bits of hash used per level, the last level of a 64 bits hash
+const MAX_LEVEL: usize = 12;
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+fn write_node<W, K, V, F>(w: &mut W, node: &Node<K, V>, write_entry: &mut F) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(&mut W, &K, &V) -> io::Result<()>,
+{
+    w.write_all(&node.bitmap.to_u32().to_be_bytes())?;
+    for child in node.iter() {
+        match child.as_ref() {
+            Entry::Leaf(_, k, v) => {
+                w.write_all(&[TAG_LEAF])?;
+                write_entry(w, k, v)?;
+            }
+            Entry::LeafMany(_, col) => {
+                w.write_all(&[TAG_LEAF_MANY])?;
+                w.write_all(&(col.len() as u32).to_be_bytes())?;
+                for (k, v) in col.iter() {
+                    write_entry(w, k, v)?;
+                }
+            }
+            Entry::SubNode(sub) => {
+                w.write_all(&[TAG_SUBNODE])?;
+                write_node(w, sub, write_entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct NodeReader<H, F> {
+    read_entry: F,
+    count: u64,
+    // level indices of the position being read
+    path: Vec<LevelIndex>,
+    hasher: PhantomData<H>,
+}
+
+impl<H, F> NodeReader<H, F>
+where
+    H: Hasher + Default,
+{
+    fn read_hashed_entry<R, K, V>(&mut self, r: &mut R) -> io::Result<(HashedKey, K, V)>
+    where
+        R: Read,
+        K: Hash,
+        F: FnMut(&mut R) -> io::Result<(K, V)>,
+    {
+        let (k, v) = (self.read_entry)(r)?;
+        let h = HashedKey::compute(self.hasher, &k);
+        let matching = self
+            .path
+            .iter()
+            .enumerate()
+            .all(|(lvl, idx)| h.level_index(lvl) == *idx);
+        if !matching {
+            return Err(invalid("entry at a position not matching its hash"));
+        }
+        self.count += 1;
+        Ok((h, k, v))
+    }
+
+    fn read_node<R, K, V>(&mut self, r: &mut R, lvl: usize) -> io::Result<Node<K, V>>
+    where
+        R: Read,
+        K: Hash + PartialEq,
+        F: FnMut(&mut R) -> io::Result<(K, V)>,
+    {
+        let bitmap = SmallBitmap::from_u32(read_u32(r)?);
+        if lvl > 0 && bitmap.is_empty() {
+            return Err(invalid("empty sub node"));
+        }
+        let mut children = Vec::with_capacity(bitmap.present());
+        for idx in bitmap.indices() {
+            self.path.push(idx);
+            let entry = match read_u8(r)? {
+                TAG_LEAF => {
+                    let (h, k, v) = self.read_hashed_entry(r)?;
+                    Entry::Leaf(h, k, v)
+                }
+                TAG_LEAF_MANY => {
+                    let count = read_u32(r)?;
+                    if count < 2 {
+                        return Err(invalid("collision with less than 2 entries"));
+                    }
+                    let (h, k, v) = self.read_hashed_entry(r)?;
+                    let mut entries = vec![(k, v)];
+                    for _ in 1..count {
+                        let (eh, k, v) = self.read_hashed_entry(r)?;
+                        if eh != h {
+                            return Err(invalid("collision entries with different hashes"));
+                        }
+                        if entries.iter().any(|(ek, _)| ek == &k) {
+                            return Err(invalid("duplicated key"));
+                        }
+                        entries.push((k, v));
+                    }
+                    Entry::LeafMany(h, Collision::from_vec(entries))
+                }
+                TAG_SUBNODE => {
+                    if lvl >= MAX_LEVEL {
+                        return Err(invalid("sub node beyond the last level"));
+                    }
+                    Entry::SubNode(self.read_node(r, lvl + 1)?)
+                }
+                _ => return Err(invalid("unknown child tag")),
+            };
+            children.push(SharedRef::new(entry));
+            self.path.pop();
+        }
+        Ok(Node {
+            bitmap,
+            children: children.into(),
+        })
+    }
+}
+
+impl<H: Hasher + Default, K: Eq + Hash, V> Hamt<H, K, V> {
+    /// Write a snapshot of the HAMT, using the closure to write the keys
+    /// and values.
+    pub fn write_snapshot<W, F>(&self, w: &mut W, mut write_entry: F) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&mut W, &K, &V) -> io::Result<()>,
+    {
+        w.write_all(&(self.len() as u64).to_be_bytes())?;
+        write_node(w, self.root(), &mut write_entry)
+    }
+
+    /// Read back a snapshot written by `write_snapshot`, using the closure
+    /// to read the keys and values.
+    ///
+    /// An error of kind `InvalidData` is returned if the snapshot does
+    /// not describe a valid trie.
+    pub fn read_snapshot<R, F>(r: &mut R, read_entry: F) -> io::Result<Self>
+    where
+        R: Read,
+        F: FnMut(&mut R) -> io::Result<(K, V)>,
+    {
+        let len = read_u64(r)?;
+        let mut reader = NodeReader {
+            read_entry,
+            count: 0,
+            path: Vec::new(),
+            hasher: PhantomData::<H>,
+        };
+        let root = reader.read_node(r, 0)?;
+        if reader.count != len {
+            return Err(invalid("number of entries not matching the length"));
+        }
+        Ok(Self::from_root(Some(root), len as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    type TestHamt = Hamt<DefaultHasher, u32, u64>;
+
+    fn write_entry(w: &mut Vec<u8>, k: &u32, v: &u64) -> io::Result<()> {
+        w.write_all(&k.to_be_bytes())?;
+        w.write_all(&v.to_be_bytes())
+    }
+
+    fn read_entry(r: &mut &[u8]) -> io::Result<(u32, u64)> {
+        Ok((read_u32(r)?, read_u64(r)?))
+    }
+
+    fn snapshot(h: &TestHamt) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        h.write_snapshot(&mut bytes, write_entry).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn snapshot_round_trip_preserves_shape() {
+        let mut h: TestHamt = (0..2000u32).map(|i| (i, i as u64 * 3)).collect();
+        // removals leave a shape that rebuilding from the elements would not give
+        for i in (0..2000u32).step_by(3) {
+            h = h.remove(&i).unwrap();
+        }
+        let bytes = snapshot(&h);
+        let reloaded = TestHamt::read_snapshot(&mut bytes.as_slice(), read_entry).unwrap();
+
+        assert_eq!(reloaded, h);
+        assert_eq!(reloaded.len(), h.len());
+        assert_eq!(snapshot(&reloaded), bytes);
+    }
+
+    #[test]
+    fn empty_snapshot() {
+        let h = TestHamt::new();
+        let bytes = snapshot(&h);
+        let reloaded = TestHamt::read_snapshot(&mut bytes.as_slice(), read_entry).unwrap();
+        assert!(reloaded.is_empty());
+    }
+
+    #[test]
+    fn invalid_snapshots_are_rejected() {
+        let h: TestHamt = (0..10u32).map(|i| (i, 0)).collect();
+        let bytes = snapshot(&h);
+
+        // wrong length
+        let mut wrong_len = bytes.clone();
+        wrong_len[7] += 1;
+        let err = TestHamt::read_snapshot(&mut wrong_len.as_slice(), read_entry).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // keys not matching their position
+        let mut wrong_key = bytes.clone();
+        let key_offset = 8 + 4 + 1;
+        wrong_key[key_offset..key_offset + 4].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(TestHamt::read_snapshot(&mut wrong_key.as_slice(), read_entry).is_err());
+
+        // truncated
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(TestHamt::read_snapshot(&mut &truncated[..], read_entry).is_err());
+    }
+}
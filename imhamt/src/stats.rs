@@ -0,0 +1,140 @@
+//! Statistics about the structure of a HAMT
+//!
+//! Updating a HAMT copies the nodes on the path to the modified element,
+//! and shares all the others with the previous version. The statistics help
+//! diagnosing memory usage by showing the shape of a trie and how much of it
+//! is shared with another version.
+
+use super::hamt::Hamt;
+use super::hash::{Hash, Hasher};
+use super::node::{Entry, Node};
+use std::collections::HashSet;
+
+/// Statistics about the structure of a HAMT, as returned by `Hamt::stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HamtStats {
+    /// Number of nodes, including the root node
+    pub nodes: usize,
+    /// Number of leaves holding a single element
+    pub leaves: usize,
+    /// Number of leaves holding elements with colliding hashes
+    pub collisions: usize,
+    /// Number of elements stored at each depth, the root node being at depth 0
+    pub depth_histogram: Vec<usize>,
+    /// Number of nodes and leaves shared with the reference HAMT
+    pub shared: usize,
+}
+
+impl HamtStats {
+    /// Maximum depth at which elements are stored
+    pub fn max_depth(&self) -> usize {
+        self.depth_histogram.len().saturating_sub(1)
+    }
+
+    /// Number of nodes and leaves that could be shared with another HAMT,
+    /// which is all of them except the root node
+    pub fn shareable(&self) -> usize {
+        self.nodes.saturating_sub(1) + self.leaves + self.collisions
+    }
+
+    /// Fraction of the nodes and leaves shared with the reference HAMT
+    pub fn shared_fraction(&self) -> f64 {
+        match self.shareable() {
+            0 => 0.0,
+            n => self.shared as f64 / n as f64,
+        }
+    }
+}
+
+fn collect_pointers<K, V>(node: &Node<K, V>, pointers: &mut HashSet<*const Entry<K, V>>) {
+    for child in node.iter() {
+        pointers.insert(child.as_ref() as *const _);
+        if let Entry::SubNode(sub) = child.as_ref() {
+            collect_pointers(sub, pointers);
+        }
+    }
+}
+
+fn stats_rec<K, V>(
+    node: &Node<K, V>,
+    depth: usize,
+    reference: &HashSet<*const Entry<K, V>>,
+    stats: &mut HamtStats,
+) {
+    stats.nodes += 1;
+    for child in node.iter() {
+        if reference.contains(&(child.as_ref() as *const _)) {
+            stats.shared += 1;
+        }
+        let elements = match child.as_ref() {
+            Entry::Leaf(..) => {
+                stats.leaves += 1;
+                1
+            }
+            Entry::LeafMany(_, col) => {
+                stats.collisions += 1;
+                col.len()
+            }
+            Entry::SubNode(sub) => {
+                stats_rec(sub, depth + 1, reference, stats);
+                continue;
+            }
+        };
+        if stats.depth_histogram.len() <= depth {
+            stats.depth_histogram.resize(depth + 1, 0);
+        }
+        stats.depth_histogram[depth] += elements;
+    }
+}
+
+impl<H: Hasher + Default, K: Eq + Hash, V> Hamt<H, K, V> {
+    /// Compute statistics about the structure of the HAMT.
+    ///
+    /// If a reference HAMT is given, e.g. a previous version of this one,
+    /// the nodes and leaves shared with it are counted too.
+    pub fn stats(&self, reference: Option<&Self>) -> HamtStats {
+        let mut pointers = HashSet::new();
+        if let Some(reference) = reference {
+            collect_pointers(reference.root(), &mut pointers);
+        }
+        let mut stats = HamtStats::default();
+        stats_rec(self.root(), 0, &pointers, &mut stats);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn stats_count_all_elements() {
+        let h: Hamt<DefaultHasher, u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let stats = h.stats(None);
+
+        assert_eq!(stats.depth_histogram.iter().sum::<usize>(), 1000);
+        assert!(stats.nodes > 1);
+        assert!(stats.max_depth() >= 1);
+        assert_eq!(stats.shared, 0);
+        assert_eq!(stats.shared_fraction(), 0.0);
+    }
+
+    #[test]
+    fn stats_sharing() {
+        let h: Hamt<DefaultHasher, u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let stats = h.clone().stats(Some(&h));
+        assert_eq!(stats.shared, stats.shareable());
+        assert_eq!(stats.shared_fraction(), 1.0);
+
+        // an update copies the path to the updated element only
+        let updated = h.replace(&500, 0).unwrap().0;
+        let stats = updated.stats(Some(&h));
+        assert!(stats.shared < stats.shareable());
+        assert!(stats.shared_fraction() > 0.9);
+
+        let empty = Hamt::<DefaultHasher, u32, u32>::new().stats(None);
+        assert_eq!(empty.nodes, 1);
+        assert_eq!(empty.max_depth(), 0);
+    }
+}
@@ -35,6 +35,46 @@ impl<T> ByteBuilder<T> {
         }
     }
 
+    /// Create an unconstrained Builder with room for at least `capacity` bytes
+    /// before reallocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        ByteBuilder {
+            buffer: Vec::with_capacity(capacity),
+            phantom: PhantomData,
+            expected: None,
+        }
+    }
+
+    /// Reserve room for at least `additional` more bytes.
+    ///
+    /// Hints can be chained as the serializer learns about the size of
+    /// the elements it is about to append, e.g. before a `fold` over a
+    /// list of fixed size items.
+    pub fn size_hint(self, additional: usize) -> Self {
+        let mut buf = self.buffer;
+        buf.reserve(additional);
+        ByteBuilder {
+            buffer: buf,
+            phantom: self.phantom,
+            expected: self.expected,
+        }
+    }
+
+    /// Number of bytes written in the builder so far
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Check if nothing has been written in the builder yet
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Number of bytes the builder can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
     /// Append an u8 in the builder
     pub fn u8(self, v: u8) -> Self {
         let mut buf = self.buffer;
@@ -133,6 +173,65 @@ impl<T> ByteBuilder<T> {
         }
     }
 
+    /// Write a sub structure of maximum 255 bytes using the closure `f`,
+    /// preceded by its length.
+    ///
+    /// The length prefix (1 byte) is backfilled once `f` returns, so the
+    /// sub structure does not need to be serialized beforehand to learn
+    /// its size.
+    pub fn sub8<F, U>(self, f: F) -> Self
+    where
+        F: FnOnce(ByteBuilder<U>) -> ByteBuilder<U>,
+    {
+        self.sub_prefixed(1, u8::MAX as usize, f)
+    }
+
+    /// Write a sub structure of maximum 2^16 - 1 bytes using the closure `f`,
+    /// preceded by its length.
+    ///
+    /// The length prefix (2 bytes) is backfilled once `f` returns.
+    pub fn sub16<F, U>(self, f: F) -> Self
+    where
+        F: FnOnce(ByteBuilder<U>) -> ByteBuilder<U>,
+    {
+        self.sub_prefixed(2, u16::MAX as usize, f)
+    }
+
+    /// Write a sub structure of maximum 2^32 - 1 bytes using the closure `f`,
+    /// preceded by its length.
+    ///
+    /// The length prefix (4 bytes) is backfilled once `f` returns.
+    pub fn sub32<F, U>(self, f: F) -> Self
+    where
+        F: FnOnce(ByteBuilder<U>) -> ByteBuilder<U>,
+    {
+        self.sub_prefixed(4, u32::MAX as usize, f)
+    }
+
+    fn sub_prefixed<F, U>(self, prefix_size: usize, max: usize, f: F) -> Self
+    where
+        F: FnOnce(ByteBuilder<U>) -> ByteBuilder<U>,
+    {
+        let mut buf = self.buffer;
+        let start = buf.len();
+        buf.resize(start + prefix_size, 0);
+        let res = f(ByteBuilder {
+            buffer: buf,
+            phantom: PhantomData,
+            expected: None,
+        });
+        let mut buf = res.buffer;
+        let len = buf.len() - start - prefix_size;
+        assert!(len <= max);
+        let len_bytes = (len as u64).to_be_bytes();
+        buf[start..start + prefix_size].copy_from_slice(&len_bytes[8 - prefix_size..]);
+        ByteBuilder {
+            buffer: buf,
+            phantom: self.phantom,
+            expected: self.expected,
+        }
+    }
+
     /// Append an u16 in the builder
     pub fn u16(self, v: u16) -> Self {
         self.bytes(&v.to_be_bytes())
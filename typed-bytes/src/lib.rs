@@ -1,7 +1,10 @@
 mod builder;
+mod reader;
 
 pub use builder::ByteBuilder;
+pub use reader::{ByteReader, ReadError, ReadErrorKind};
 use std::marker::PhantomData;
+use std::ops::Range;
 
 /// A typed slice of bytes
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -65,6 +68,14 @@ impl<'a, T> ByteSlice<'a, T> {
         self.slice
     }
 
+    /// Create a cursor to read the fields of this slice
+    pub fn reader(&self) -> ByteReader<'a, T> {
+        ByteReader::new(ByteSlice {
+            slice: self.slice,
+            phantom: PhantomData,
+        })
+    }
+
     fn sub_byteslice<U>(&'a self, start: usize, size: usize) -> ByteSlice<'a, U> {
         ByteSlice {
             slice: &self.slice[start..start + size],
@@ -90,6 +101,57 @@ impl<T> ByteArray<T> {
     }
 }
 
+impl<T> ByteArray<T> {
+    /// Get a typed view over `range` of the array, without copying.
+    ///
+    /// The offsets are usually recorded with `ByteBuilder::len` while
+    /// building the array.
+    ///
+    /// Panics if the range is out of the array's bounds
+    pub fn slice<U>(&self, range: Range<usize>) -> ByteSlice<'_, U> {
+        ByteSlice {
+            slice: &self.array[range],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Split the array in two typed views at `mid`, without copying.
+    ///
+    /// Panics if `mid` is greater than the array's length
+    pub fn split_at<U, V>(&self, mid: usize) -> (ByteSlice<'_, U>, ByteSlice<'_, V>) {
+        self.as_byteslice().split_at(mid)
+    }
+}
+
+impl<'a, T> ByteSlice<'a, T> {
+    /// Get a typed view over `range` of the slice, without copying.
+    ///
+    /// Panics if the range is out of the slice's bounds
+    pub fn slice<U>(&self, range: Range<usize>) -> ByteSlice<'a, U> {
+        ByteSlice {
+            slice: &self.slice[range],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Split the slice in two typed views at `mid`, without copying.
+    ///
+    /// Panics if `mid` is greater than the slice's length
+    pub fn split_at<U, V>(&self, mid: usize) -> (ByteSlice<'a, U>, ByteSlice<'a, V>) {
+        let (left, right) = self.slice.split_at(mid);
+        (
+            ByteSlice {
+                slice: left,
+                phantom: PhantomData,
+            },
+            ByteSlice {
+                slice: right,
+                phantom: PhantomData,
+            },
+        )
+    }
+}
+
 impl<'a, T> ByteSlice<'a, T> {
     pub fn sub<U>(&'a self) -> ByteSlice<'a, U>
     where
@@ -117,4 +179,125 @@ mod tests {
         let b: ByteArray<Big> = v.into();
         assert_eq!(b.sub::<Little>().as_slice(), [2, 3, 4])
     }
+
+    #[test]
+    pub fn builder_capacity() {
+        let bb: ByteBuilder<Big> = ByteBuilder::with_capacity(4);
+        assert!(bb.is_empty());
+        assert!(bb.capacity() >= 4);
+
+        let bb = bb.u32(0x0102_0304).size_hint(16);
+        assert_eq!(bb.len(), 4);
+        assert!(bb.capacity() >= 20);
+
+        let bb = bb.fold(0..16u8, |bb, i| bb.u8(i));
+        assert_eq!(bb.len(), 20);
+        let v = bb.finalize_as_vec();
+        assert_eq!(&v[..4], &[1, 2, 3, 4]);
+        assert_eq!(v[19], 15);
+    }
+
+    #[test]
+    pub fn builder_length_prefixed_sub() {
+        let bb: ByteBuilder<Big> = ByteBuilder::new();
+        let v = bb
+            .u8(0xff)
+            .sub8(|bb: ByteBuilder<Little>| bb.u16(0x0102))
+            .sub16(|bb: ByteBuilder<Little>| bb.bytes(&[7; 3]))
+            .sub32(|bb: ByteBuilder<Little>| bb)
+            .u8(0xee)
+            .finalize_as_vec();
+        assert_eq!(v, [0xff, 2, 1, 2, 0, 3, 7, 7, 7, 0, 0, 0, 0, 0xee].to_vec());
+    }
+
+    #[test]
+    pub fn builder_nested_length_prefixed_sub() {
+        let bb: ByteBuilder<Big> = ByteBuilder::new();
+        let v = bb
+            .sub16(|bb: ByteBuilder<Little>| bb.u8(1).sub8(|bb: ByteBuilder<()>| bb.u8(2).u8(3)))
+            .finalize_as_vec();
+        assert_eq!(v, [0, 4, 1, 2, 2, 3].to_vec());
+    }
+
+    #[test]
+    pub fn reader_roundtrip() {
+        let bb: ByteBuilder<Big> = ByteBuilder::new();
+        let array = bb
+            .u8(1)
+            .u16(2)
+            .u32(3)
+            .u64(4)
+            .u128(5)
+            .sub16(|bb: ByteBuilder<Little>| bb.bytes(&[6, 7]))
+            .finalize();
+
+        let mut reader = array.as_byteslice().reader();
+        assert_eq!(reader.u8(), Ok(1));
+        assert_eq!(reader.u16(), Ok(2));
+        assert_eq!(reader.u32(), Ok(3));
+        assert_eq!(reader.u64(), Ok(4));
+        assert_eq!(reader.u128(), Ok(5));
+        let little: ByteSlice<Little> = reader.sub16().unwrap();
+        assert_eq!(little.as_slice(), [6, 7]);
+        assert!(reader.expect_end().is_ok());
+    }
+
+    #[test]
+    pub fn reader_errors() {
+        let v: Vec<u8> = vec![0, 1, 2, 3, 4];
+        let b: ByteArray<Big> = v.into();
+
+        let mut reader = b.as_byteslice().reader();
+        assert_eq!(reader.u16(), Ok(1));
+        let err = reader.u32().unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(
+            err.kind(),
+            ReadErrorKind::NotEnoughBytes {
+                needed: 4,
+                remaining: 3
+            }
+        );
+        assert!(err.type_name().ends_with("Big"));
+        // a failed read does not move the cursor
+        assert_eq!(reader.offset(), 2);
+
+        let err = reader.expect_end().unwrap_err();
+        assert_eq!(err.kind(), ReadErrorKind::UnconsumedBytes(3));
+    }
+
+    pub struct Payload;
+    pub struct Auth;
+
+    #[test]
+    pub fn typed_regions() {
+        let bb: ByteBuilder<Big> = ByteBuilder::new().u8(2).bytes(&[1, 2]);
+        let payload_end = bb.len();
+        let array = bb.bytes(&[3, 4, 5]).finalize();
+
+        let payload: ByteSlice<Payload> = array.slice(1..payload_end);
+        assert_eq!(payload.as_slice(), [1, 2]);
+        assert_eq!(payload.as_slice().as_ptr(), array.as_slice()[1..].as_ptr());
+
+        let (payload, auth): (ByteSlice<Payload>, ByteSlice<Auth>) = array.split_at(payload_end);
+        assert_eq!(payload.as_slice(), [2, 1, 2]);
+        assert_eq!(auth.as_slice(), [3, 4, 5]);
+
+        let inner: ByteSlice<Little> = auth.slice(1..3);
+        assert_eq!(inner.as_slice(), [4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn typed_region_out_of_bounds() {
+        let array: ByteArray<Big> = vec![0, 1, 2].into();
+        array.slice::<Little>(2..4);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn builder_sub8_too_long() {
+        let bb: ByteBuilder<Big> = ByteBuilder::new();
+        bb.sub8(|bb: ByteBuilder<Little>| bb.bytes(&[0; 256]));
+    }
 }
@@ -0,0 +1,183 @@
+use crate::ByteSlice;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The reason a read failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadErrorKind {
+    /// the field needed more bytes than what remained in the slice
+    NotEnoughBytes { needed: usize, remaining: usize },
+    /// some bytes were left after the end of the structure
+    UnconsumedBytes(usize),
+}
+
+/// An error while reading a typed slice, with the offset of the
+/// field that failed to be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadError {
+    type_name: &'static str,
+    offset: usize,
+    kind: ReadErrorKind,
+}
+
+impl ReadError {
+    /// Name of the type being read when the error happened
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Offset (in bytes, from the start of the reader) of the failing field
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> ReadErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ReadErrorKind::NotEnoughBytes { needed, remaining } => write!(
+                f,
+                "{}: needed {} bytes at offset {} but only {} remaining",
+                self.type_name, needed, self.offset, remaining
+            ),
+            ReadErrorKind::UnconsumedBytes(left) => write!(
+                f,
+                "{}: {} unconsumed bytes at offset {}",
+                self.type_name, left, self.offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+/// A cursor reading the fields of a typed slice of T, the reading
+/// counterpart of `ByteBuilder`
+#[derive(Debug, Clone)]
+pub struct ByteReader<'a, T> {
+    slice: &'a [u8],
+    offset: usize,
+    phantom: PhantomData<T>,
+}
+
+impl<'a, T> ByteReader<'a, T> {
+    /// Create a reader at the start of the typed slice
+    pub fn new(slice: ByteSlice<'a, T>) -> Self {
+        ByteReader {
+            slice: slice.slice,
+            offset: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Current position of the cursor
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.slice.len() - self.offset
+    }
+
+    /// Check if every byte has been read
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    fn error(&self, kind: ReadErrorKind) -> ReadError {
+        ReadError {
+            type_name: std::any::type_name::<T>(),
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    /// Read `size` bytes
+    pub fn bytes(&mut self, size: usize) -> Result<&'a [u8], ReadError> {
+        if size > self.remaining() {
+            return Err(self.error(ReadErrorKind::NotEnoughBytes {
+                needed: size,
+                remaining: self.remaining(),
+            }));
+        }
+        let slice = self.slice;
+        let bytes = &slice[self.offset..self.offset + size];
+        self.offset += size;
+        Ok(bytes)
+    }
+
+    /// Read a fixed size array of bytes
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], ReadError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    /// Read an u8
+    pub fn u8(&mut self) -> Result<u8, ReadError> {
+        self.array::<1>().map(|v| v[0])
+    }
+
+    /// Read an u16
+    pub fn u16(&mut self) -> Result<u16, ReadError> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    /// Read an u32
+    pub fn u32(&mut self) -> Result<u32, ReadError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    /// Read an u64
+    pub fn u64(&mut self) -> Result<u64, ReadError> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    /// Read an u128
+    pub fn u128(&mut self) -> Result<u128, ReadError> {
+        self.array().map(u128::from_be_bytes)
+    }
+
+    /// Read `size` bytes as a typed slice of U
+    pub fn sub<U>(&mut self, size: usize) -> Result<ByteSlice<'a, U>, ReadError> {
+        self.bytes(size).map(|slice| ByteSlice {
+            slice,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Read a typed slice of U preceded by its length on 1 byte,
+    /// as written by `ByteBuilder::sub8`
+    pub fn sub8<U>(&mut self) -> Result<ByteSlice<'a, U>, ReadError> {
+        let size = self.u8()? as usize;
+        self.sub(size)
+    }
+
+    /// Read a typed slice of U preceded by its length on 2 bytes,
+    /// as written by `ByteBuilder::sub16`
+    pub fn sub16<U>(&mut self) -> Result<ByteSlice<'a, U>, ReadError> {
+        let size = self.u16()? as usize;
+        self.sub(size)
+    }
+
+    /// Read a typed slice of U preceded by its length on 4 bytes,
+    /// as written by `ByteBuilder::sub32`
+    pub fn sub32<U>(&mut self) -> Result<ByteSlice<'a, U>, ReadError> {
+        let size = self.u32()? as usize;
+        self.sub(size)
+    }
+
+    /// Check that every byte has been read
+    pub fn expect_end(&self) -> Result<(), ReadError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ReadErrorKind::UnconsumedBytes(self.remaining())))
+        }
+    }
+}